@@ -0,0 +1,353 @@
+use std::collections::{HashMap, VecDeque};
+
+use raylib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    SCREEN_HEIGHT, SCREEN_WIDTH,
+    app::App,
+    hotkey_handler::{HotkeyCategory, HotkeyHandler},
+    input::InputHandler,
+    locale::Locale,
+    map::Level,
+    profile,
+    scene::{Scene, SceneHandler, SceneState},
+    texture_handler::TextureHandler,
+    ui::get_text_size,
+};
+
+const ACHIEVEMENTS_PATH: &str = "static/achievements.json";
+const PROGRESS_PATH: &str = "achievements.json";
+
+const TOAST_TIME: f32 = 3.;
+const TOAST_SLIDE_TIME: f32 = 0.3;
+const TOAST_WIDTH: f32 = 120.;
+const TOAST_HEIGHT: f32 = 24.;
+const TOAST_MARGIN: f32 = 4.;
+
+const GALLERY_TITLE: &str = "achievements.title";
+const GALLERY_COLUMNS: usize = 2;
+const GALLERY_TOP: f32 = 26.;
+const GALLERY_ROW_HEIGHT: f32 = 28.;
+const GALLERY_COLUMN_WIDTH: f32 = 150.;
+
+const NAME_TEXT_SIZE: f32 = 8.;
+const DESCRIPTION_TEXT_SIZE: f32 = 6.;
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameEvent {
+    SpiritSaved,
+    TreeChopped,
+    BonfireLit,
+    LevelWithoutBuilding,
+    EnemiesAvoided,
+    SwampTeleport,
+    SpiritCaught,
+    WoodRanOut,
+    NearlyLost, // one more spirit lost and the level is lost
+}
+
+#[derive(Deserialize)]
+struct Achievement {
+    id: String,
+    texture: String,
+    name: String,
+    description: String,
+    event: GameEvent,
+    target: u32,
+}
+
+#[derive(Deserialize)]
+struct AchievementWrapper {
+    achievements: Vec<Achievement>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct AchievementProgress {
+    #[serde(default)]
+    counters: HashMap<GameEvent, u32>,
+    #[serde(default)]
+    unlocked: Vec<String>,
+}
+
+struct Toast {
+    achievement: usize,
+    timer: f32,
+}
+
+pub struct AchievementHandler {
+    achievements: Vec<Achievement>,
+    progress: AchievementProgress,
+    toasts: VecDeque<Toast>,
+}
+
+impl AchievementHandler {
+    #[profiling::function]
+    pub fn new() -> Self {
+        let Ok(string_json) = std::fs::read_to_string(ACHIEVEMENTS_PATH) else {
+            panic!("COULDN'T LOAD JSON FOR ACHIEVEMENTS");
+        };
+        let Ok(wrapper) = serde_json::from_str::<AchievementWrapper>(&string_json) else {
+            panic!("COULDN'T PARSE JSON FOR ACHIEVEMENTS");
+        };
+
+        let progress = match std::fs::read_to_string(profile::path(PROGRESS_PATH)) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+            Err(_) => AchievementProgress::default(),
+        };
+
+        Self {
+            achievements: wrapper.achievements,
+            progress,
+            toasts: VecDeque::new(),
+        }
+    }
+
+    pub fn textures(&self) -> Vec<String> {
+        self.achievements
+            .iter()
+            .map(|achievement| achievement.texture.clone())
+            .collect()
+    }
+
+    // eats whatever the level collected this frame, runs in every scene so toasts keep moving
+    #[profiling::function]
+    pub fn update(&mut self, level: &mut Level, delta: f32) {
+        let mut changed = false;
+
+        for event in level.events.drain(..) {
+            let counter = self.progress.counters.entry(event).or_insert(0);
+            *counter += 1;
+            changed = true;
+
+            for (i, achievement) in self.achievements.iter().enumerate() {
+                if achievement.event != event
+                    || *counter < achievement.target
+                    || self.progress.unlocked.contains(&achievement.id)
+                {
+                    continue;
+                }
+                self.progress.unlocked.push(achievement.id.clone());
+                self.toasts.push_back(Toast {
+                    achievement: i,
+                    timer: 0.,
+                });
+            }
+        }
+
+        if changed {
+            self.save();
+        }
+
+        if let Some(toast) = self.toasts.front_mut() {
+            toast.timer += delta;
+            if toast.timer >= TOAST_TIME {
+                self.toasts.pop_front();
+            }
+        }
+    }
+
+    #[profiling::function]
+    fn save(&self) {
+        let Ok(s) = serde_json::to_string_pretty(&self.progress) else {
+            panic!("COULDN'T SERIALIZE ACHIEVEMENTS TO JSON");
+        };
+
+        std::fs::write(profile::path(PROGRESS_PATH), s)
+            .expect("COULDN'T WRITE ACHIEVEMENTS TO FILE");
+    }
+
+    fn draw_icon(
+        &self,
+        achievement: &Achievement,
+        position: Vector2,
+        color: Color,
+        texture_handler: &TextureHandler,
+        rl: &mut RaylibDrawHandle,
+    ) {
+        texture_handler.draw(rl, &achievement.texture, position, color);
+    }
+
+    // slides down from the top right corner, stays for a bit and goes back up
+    #[profiling::function]
+    pub fn draw_toast(
+        &self,
+        locale: &Locale,
+        font: &Font,
+        texture_handler: &TextureHandler,
+        rl: &mut RaylibDrawHandle,
+    ) {
+        let Some(toast) = self.toasts.front() else {
+            return;
+        };
+        let achievement = &self.achievements[toast.achievement];
+
+        let shown = (toast.timer / TOAST_SLIDE_TIME)
+            .min((TOAST_TIME - toast.timer) / TOAST_SLIDE_TIME)
+            .clamp(0., 1.);
+        let rect = Rectangle::new(
+            SCREEN_WIDTH as f32 - TOAST_WIDTH - TOAST_MARGIN,
+            -TOAST_HEIGHT + (TOAST_HEIGHT + TOAST_MARGIN) * shown,
+            TOAST_WIDTH,
+            TOAST_HEIGHT,
+        );
+
+        rl.draw_rectangle_rec(rect, Color::from_hex("1e1d39").unwrap());
+        rl.draw_rectangle_lines_ex(rect, 1., Color::from_hex("f9c22b").unwrap());
+
+        self.draw_icon(
+            achievement,
+            Vector2::new(rect.x + 4., rect.y + 4.),
+            Color::WHITE,
+            texture_handler,
+            rl,
+        );
+
+        rl.draw_text_ex(
+            font,
+            locale.get("achievements.new"),
+            Vector2::new(rect.x + 24., rect.y + 3.),
+            DESCRIPTION_TEXT_SIZE,
+            0.,
+            Color::from_hex("f9c22b").unwrap(),
+        );
+        rl.draw_text_ex(
+            font,
+            locale.get(&achievement.name),
+            Vector2::new(rect.x + 24., rect.y + 11.),
+            NAME_TEXT_SIZE,
+            0.,
+            Color::RAYWHITE,
+        );
+    }
+
+    #[profiling::function]
+    pub fn update_gallery(
+        &self,
+        input_handler: &InputHandler,
+        hotkey_handler: &mut HotkeyHandler,
+        scene_handler: &mut SceneHandler,
+    ) {
+        if hotkey_handler.check_pressed(input_handler, HotkeyCategory::Exit)
+            || hotkey_handler.check_pressed(input_handler, HotkeyCategory::Continue)
+            || input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+        {
+            scene_handler.set(Scene::MainMenu);
+        }
+    }
+
+    #[profiling::function]
+    pub fn draw_gallery(
+        &self,
+        locale: &Locale,
+        font: &Font,
+        texture_handler: &TextureHandler,
+        rl: &mut RaylibDrawHandle,
+    ) {
+        rl.clear_background(Color::from_hex("0b5e65").unwrap());
+
+        let title = locale.get(GALLERY_TITLE);
+        let title_size = get_text_size(font, title, 12., 1.25);
+        rl.draw_text_ex(
+            font,
+            title,
+            Vector2::new((SCREEN_WIDTH as f32 - title_size.x) / 2., 8.),
+            12.,
+            1.25,
+            Color::RAYWHITE,
+        );
+
+        let left = (SCREEN_WIDTH as f32 - GALLERY_COLUMN_WIDTH * GALLERY_COLUMNS as f32) / 2.;
+
+        for (i, achievement) in self.achievements.iter().enumerate() {
+            let x = left + (i % GALLERY_COLUMNS) as f32 * GALLERY_COLUMN_WIDTH;
+            let y = GALLERY_TOP + (i / GALLERY_COLUMNS) as f32 * GALLERY_ROW_HEIGHT;
+
+            let unlocked = self.progress.unlocked.contains(&achievement.id);
+            let color = if unlocked {
+                Color::RAYWHITE
+            } else {
+                Color::RAYWHITE.alpha(0.4)
+            };
+
+            self.draw_icon(
+                achievement,
+                Vector2::new(x, y),
+                if unlocked {
+                    Color::WHITE
+                } else {
+                    Color::BLACK.alpha(0.6)
+                },
+                texture_handler,
+                rl,
+            );
+
+            rl.draw_text_ex(
+                font,
+                locale.get(&achievement.name),
+                Vector2::new(x + 20., y),
+                NAME_TEXT_SIZE,
+                0.,
+                color,
+            );
+
+            let counter = self
+                .progress
+                .counters
+                .get(&achievement.event)
+                .copied()
+                .unwrap_or(0)
+                .min(achievement.target);
+            let description = if unlocked || achievement.target == 1 {
+                locale.get(&achievement.description).to_string()
+            } else {
+                format!(
+                    "{} {}/{}",
+                    locale.get(&achievement.description),
+                    counter,
+                    achievement.target
+                )
+            };
+
+            rl.draw_text_ex(
+                font,
+                &description,
+                Vector2::new(x + 20., y + 9.),
+                DESCRIPTION_TEXT_SIZE,
+                0.,
+                color,
+            );
+        }
+
+        let text = locale.get("press_to_return");
+        let text_size = get_text_size(font, text, DESCRIPTION_TEXT_SIZE, 0.);
+        rl.draw_text_ex(
+            font,
+            text,
+            Vector2::new(
+                (SCREEN_WIDTH as f32 - text_size.x) / 2.,
+                SCREEN_HEIGHT as f32 - 10.,
+            ),
+            DESCRIPTION_TEXT_SIZE,
+            0.,
+            Color::RAYWHITE,
+        );
+    }
+}
+
+pub struct AchievementGallery;
+
+impl SceneState for AchievementGallery {
+    fn update(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        app.achievement_handler.update_gallery(
+            &app.input_handler,
+            &mut app.hotkey_handler,
+            &mut app.scene_handler,
+        );
+    }
+
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        app.achievement_handler
+            .draw_gallery(&app.locale, &app.font, &app.texture_handler, rl);
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+use serde::Deserialize;
+
+const ANIMATIONS_PATH: &str = "static/animations.json";
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum PlayMode {
+    #[default]
+    Loop,
+    Once,
+}
+
+#[derive(Deserialize)]
+pub struct Clip {
+    frames: Vec<[f32; 4]>,
+    fps: f32,
+    #[serde(default)]
+    mode: PlayMode,
+    // a one-shot clip goes on with this one once it's over
+    #[serde(default)]
+    next: Option<String>,
+}
+
+impl Clip {
+    pub fn duration(&self) -> f32 {
+        self.frames.len() as f32 / self.fps
+    }
+
+    pub fn frame(&self, elapsed: f32) -> Rectangle {
+        let index = (elapsed * self.fps).floor().max(0.) as usize;
+        let index = match self.mode {
+            PlayMode::Loop => index % self.frames.len(),
+            PlayMode::Once => index.min(self.frames.len() - 1),
+        };
+
+        let [x, y, width, height] = self.frames[index];
+        Rectangle::new(x, y, width, height)
+    }
+}
+
+// a sheet is named after its texture
+#[derive(Deserialize)]
+struct Sheet {
+    default: String,
+    clips: HashMap<String, Clip>,
+}
+
+#[derive(Deserialize)]
+struct AnimationsWrapper {
+    sheets: HashMap<String, Sheet>,
+}
+
+pub struct Animations {
+    sheets: HashMap<String, Sheet>,
+}
+
+impl Animations {
+    #[profiling::function]
+    pub fn new() -> Self {
+        let Ok(string_json) = std::fs::read_to_string(ANIMATIONS_PATH) else {
+            panic!("COULDN'T LOAD JSON FOR ANIMATIONS");
+        };
+        let Ok(wrapper) = serde_json::from_str::<AnimationsWrapper>(&string_json) else {
+            panic!("COULDN'T PARSE JSON FOR ANIMATIONS");
+        };
+
+        for (name, sheet) in wrapper.sheets.iter() {
+            for (clip_name, clip) in sheet.clips.iter() {
+                if clip.frames.is_empty() || clip.fps <= 0. {
+                    panic!("CLIP {clip_name} OF {name} HAS NO FRAMES OR FPS");
+                }
+                if clip
+                    .next
+                    .as_ref()
+                    .is_some_and(|n| !sheet.clips.contains_key(n))
+                {
+                    panic!("CLIP {clip_name} OF {name} GOES ON WITH A MISSING CLIP");
+                }
+            }
+            if !sheet.clips.contains_key(&sheet.default) {
+                panic!("SHEET {name} HAS NO DEFAULT CLIP {}", sheet.default);
+            }
+        }
+
+        Self {
+            sheets: wrapper.sheets,
+        }
+    }
+
+    pub fn clip(&self, sheet: &str, clip: &str) -> &Clip {
+        let Some(clip) = self
+            .sheets
+            .get(sheet)
+            .and_then(|sheet| sheet.clips.get(clip))
+        else {
+            panic!("NO CLIP {clip} IN SHEET {sheet}");
+        };
+        clip
+    }
+
+    // follows one-shot clips into their next ones, a finished one-shot stays on its last frame
+    fn current(&self, player: &AnimationPlayer) -> (&Clip, f32) {
+        let Some(sheet) = self.sheets.get(&player.sheet) else {
+            panic!("NO ANIMATION SHEET {}", player.sheet);
+        };
+        let mut clip = self.clip(
+            &player.sheet,
+            player.clip.as_ref().unwrap_or(&sheet.default),
+        );
+        let mut elapsed = player.elapsed;
+
+        while clip.mode == PlayMode::Once && elapsed >= clip.duration() {
+            let Some(next) = &clip.next else {
+                break;
+            };
+            elapsed -= clip.duration();
+            clip = self.clip(&player.sheet, next);
+        }
+
+        (clip, elapsed)
+    }
+
+    pub fn source(&self, player: &AnimationPlayer) -> Rectangle {
+        let (clip, elapsed) = self.current(player);
+        clip.frame(elapsed)
+    }
+
+    pub fn finished(&self, player: &AnimationPlayer) -> bool {
+        let (clip, elapsed) = self.current(player);
+        clip.mode == PlayMode::Once && elapsed >= clip.duration()
+    }
+
+    pub fn has_sheet(&self, sheet: &str) -> bool {
+        self.sheets.contains_key(sheet)
+    }
+
+    // every frame of every clip along with its sheet, so they can be checked against the textures
+    pub fn frames(&self) -> Vec<(&str, Rectangle)> {
+        self.sheets
+            .iter()
+            .flat_map(|(name, sheet)| {
+                sheet.clips.values().flat_map(move |clip| {
+                    clip.frames.iter().map(move |&[x, y, width, height]| {
+                        (name.as_str(), Rectangle::new(x, y, width, height))
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+// what an entity owns, the frames themselves stay in Animations
+#[derive(Clone)]
+pub struct AnimationPlayer {
+    sheet: String,
+    clip: Option<String>, // None is the sheet's default
+    elapsed: f32,
+    paused: bool,
+}
+
+impl AnimationPlayer {
+    pub fn new(sheet: &str) -> Self {
+        Self {
+            sheet: sheet.to_string(),
+            clip: None,
+            elapsed: 0.,
+            paused: false,
+        }
+    }
+
+    pub fn with_clip(sheet: &str, clip: &str) -> Self {
+        let mut player = Self::new(sheet);
+        player.play(clip);
+        player
+    }
+
+    // always from the start, even if it's already playing
+    pub fn play(&mut self, clip: &str) {
+        self.clip = Some(clip.to_string());
+        self.elapsed = 0.;
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        if !self.paused {
+            self.elapsed += delta;
+        }
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn sheet(&self) -> &str {
+        &self.sheet
+    }
+
+    pub fn clip(&self) -> Option<&str> {
+        self.clip.as_deref()
+    }
+}
@@ -0,0 +1,120 @@
+use raylib::prelude::*;
+
+use crate::{
+    achievements::AchievementHandler,
+    controls_menu,
+    daily::DailyHandler,
+    dialogue,
+    game::Game,
+    gamepad::GamepadHandler,
+    hotkey_handler::HotkeyHandler,
+    input::InputHandler,
+    level_selection,
+    locale::Locale,
+    main_menu, map,
+    music_handler::MusicHandler,
+    order, pause_menu,
+    save_handler::SaveHandler,
+    scene::{Scene, SceneHandler},
+    settings::SettingsHandler,
+    settings_menu, text_layout,
+    texture_handler::TextureHandler,
+    ui,
+};
+
+const MAIN_FONT: &str = "main";
+
+// what every scene gets handed, the scenes themselves live in Scenes
+pub struct App<'a> {
+    pub game: Game,
+    pub scene_handler: SceneHandler,
+    pub music_handler: MusicHandler<'a>,
+    pub settings_handler: SettingsHandler,
+    pub hotkey_handler: HotkeyHandler,
+    pub input_handler: InputHandler,
+    pub gamepad_handler: GamepadHandler,
+    pub save_handler: SaveHandler,
+    pub daily_handler: DailyHandler,
+    pub achievement_handler: AchievementHandler,
+    pub texture_handler: TextureHandler,
+    pub font: Font,
+    pub locale: Locale,
+    pub should_close: bool,
+}
+
+impl App<'_> {
+    // the daily forest has its own way back to the start, on hard restarting gives the level up
+    // and it goes to the game over screen like any other lost one
+    #[profiling::function]
+    pub fn restart_level(&mut self, rl: &mut RaylibHandle) {
+        if !self.game.level.modifier.free_restart() {
+            self.music_handler
+                .play("death", self.settings_handler.get_settings());
+            if self.daily_handler.active {
+                self.daily_handler.lose(&mut self.scene_handler);
+            } else {
+                self.scene_handler.set(Scene::GameOver);
+            }
+            return;
+        }
+
+        if self.daily_handler.active {
+            self.daily_handler.restart(&mut self.game);
+        } else {
+            self.game.restart_level(rl, &self.settings_handler);
+        }
+    }
+
+    // new strings can bring new characters, so the font follows the locale around
+    #[profiling::function]
+    pub fn reload_font(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        text_layout::clear_cache();
+        self.font = load_font(
+            &self.texture_handler,
+            &self.locale,
+            &self.settings_handler,
+            rl,
+            thread,
+        );
+    }
+
+    // every name the game can ask for, so a typo shows up right away and not mid-level,
+    // takes the handler separately so a freshly reloaded one can be checked before it's used
+    #[profiling::function]
+    pub fn validate_textures(&self, texture_handler: &TextureHandler) -> Result<(), String> {
+        texture_handler.validate(
+            &[
+                ("achievements.json", self.achievement_handler.textures()),
+                ("dialogues.dg", dialogue::speakers()),
+                ("ui", names(&ui::TEXTURES)),
+                ("map", names(&map::TEXTURES)),
+                ("order", names(&order::TEXTURES)),
+                ("main_menu", names(&main_menu::TEXTURES)),
+                ("settings_menu", names(&settings_menu::TEXTURES)),
+                ("controls_menu", names(&controls_menu::TEXTURES)),
+                ("pause_menu", names(&pause_menu::TEXTURES)),
+                ("level_selection", names(&level_selection::TEXTURES)),
+            ],
+            &[
+                ("unlocks.json", self.game.level_transition.sheets()),
+                ("ui", names(&ui::SHEETS)),
+            ],
+        )
+    }
+}
+
+#[profiling::function]
+pub fn load_font(
+    texture_handler: &TextureHandler,
+    locale: &Locale,
+    settings_handler: &SettingsHandler,
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+) -> Font {
+    let glyphs = locale.glyphs(&[&settings_handler.settings.player_name]);
+    texture_handler.load_font(rl, thread, MAIN_FONT, locale.language(), &glyphs)
+}
+
+fn names(constants: &[&str]) -> Vec<String> {
+    constants.iter().map(|name| name.to_string()).collect()
+}
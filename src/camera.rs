@@ -0,0 +1,66 @@
+use raylib::prelude::*;
+
+use crate::{
+    SCREEN_HEIGHT, SCREEN_WIDTH, input::InputHandler, map::TILE_SIZE_PX, settings::SettingsHandler,
+};
+
+// where the SCREEN_WIDTH x SCREEN_HEIGHT picture ends up in the window,
+// whole pixels only unless smooth scaling is on, the rest is black bars
+#[profiling::function]
+pub fn viewport(rl: &RaylibHandle, settings_handler: &SettingsHandler) -> Rectangle {
+    let (width, height) = if rl.is_window_fullscreen() {
+        let monitor = get_current_monitor();
+        (get_monitor_width(monitor), get_monitor_height(monitor))
+    } else {
+        (rl.get_screen_width(), rl.get_screen_height())
+    };
+
+    let mut scale = (width as f32 / SCREEN_WIDTH as f32).min(height as f32 / SCREEN_HEIGHT as f32);
+    if !settings_handler.settings.smooth_scaling {
+        scale = scale.floor().max(1.);
+    }
+
+    let size = Vector2::new(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32) * scale;
+    return Rectangle::new(
+        ((width as f32 - size.x) / 2.).floor(),
+        ((height as f32 - size.y) / 2.).floor(),
+        size.x,
+        size.y,
+    );
+}
+
+// the mouse in game pixels, it's outside of 0..SCREEN_WIDTH when it's over the bars,
+// only InputHandler asks, the rest read its pointer
+#[profiling::function]
+pub fn mouse_position(rl: &RaylibHandle, settings_handler: &SettingsHandler) -> Vector2 {
+    let viewport = viewport(rl, settings_handler);
+    return (rl.get_mouse_position() - Vector2::new(viewport.x, viewport.y)) * SCREEN_WIDTH as f32
+        / viewport.width;
+}
+
+// the world lives in tiles, this is the only place that knows how big a tile is on screen
+pub struct WorldCamera;
+
+impl WorldCamera {
+    // in game pixels
+    pub fn tile_size(&self) -> f32 {
+        TILE_SIZE_PX as f32
+    }
+
+    pub fn to_screen(&self, position: Vector2) -> Vector2 {
+        position * self.tile_size()
+    }
+
+    pub fn to_world(&self, position: Vector2) -> Vector2 {
+        position / self.tile_size()
+    }
+
+    pub fn tile_rect(&self, position: Vector2) -> Rectangle {
+        let position = self.to_screen(position);
+        Rectangle::new(position.x, position.y, self.tile_size(), self.tile_size())
+    }
+
+    pub fn mouse_world(&self, input_handler: &InputHandler) -> Vector2 {
+        self.to_world(input_handler.pointer())
+    }
+}
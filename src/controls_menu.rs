@@ -0,0 +1,353 @@
+use raylib::prelude::*;
+
+use crate::{
+    SCREEN_WIDTH,
+    app::App,
+    hotkey_handler::{self, HotkeyCategory},
+    scene::SceneState,
+    ui::{Button, get_text_size},
+};
+
+const BACKGROUND_IMAGE: &str = "settings_bg";
+const BUTTONS_TEXTURE: &str = "main_menu_buttons";
+
+pub const TEXTURES: [&str; 2] = [BACKGROUND_IMAGE, BUTTONS_TEXTURE];
+
+const TITLE: &str = "controls.title";
+const PRESS_KEY: &str = "controls.press_key";
+const PRESS_BUTTON: &str = "controls.press_button";
+const HELP: &str = "controls.help";
+const CONFLICT: &str = "controls.conflict";
+const BUTTON_LABELS: [&str; 2] = ["settings.back", "controls.reset"];
+
+const BUTTON_WIDTH: f32 = 64.;
+const BUTTON_HEIGHT: f32 = 16.;
+const BUTTON_MARGIN: f32 = 1.;
+
+const TITLE_SIZE: f32 = 12.;
+const TITLE_SPACING: f32 = 1.25;
+const TITLE_Y: f32 = 3.;
+
+const ROW_TOP: f32 = 18.;
+const ROW_HEIGHT: f32 = 6.;
+const ROW_TEXT_SIZE: f32 = 6.;
+const ROW_TEXT_SPACING: f32 = 0.5;
+const NAME_X: f32 = 24.;
+const KEYS_X: f32 = 150.;
+const BUTTONS_X: f32 = 248.;
+const KEY_SLOT_WIDTH: f32 = (BUTTONS_X - KEYS_X) / 2.;
+const HELP_Y: f32 = 174.;
+const HELP_TEXT_SIZE: f32 = 6.;
+
+const CONFLICT_COLOR: &str = "e83b3b";
+const CAPTURE_COLOR: &str = "f9c22b";
+
+// one binding of a row, the first key, the second one and the gamepad buttons
+#[derive(Clone, Copy, PartialEq)]
+enum Slot {
+    Key(usize),
+    Button(usize),
+}
+
+const SLOTS: [Slot; 3] = [Slot::Key(0), Slot::Key(1), Slot::Button(0)];
+
+// every hotkey on its own row with its keys and gamepad buttons, a click or Select on one of them
+// waits for the key or button to put there, a right click puts the row's defaults back,
+// pushed from the settings menu and saved to hotkeys.json on the way out
+pub struct ControlsMenu {
+    buttons: Vec<Button>,
+    capturing: Option<(HotkeyCategory, Slot)>,
+}
+
+impl ControlsMenu {
+    pub fn new() -> Self {
+        let buttons = (0..BUTTON_LABELS.len())
+            .map(|i| Button {
+                rect: Rectangle::new(
+                    BUTTON_MARGIN
+                        + (SCREEN_WIDTH as f32 - BUTTON_WIDTH - BUTTON_MARGIN * 2.) * i as f32,
+                    BUTTON_MARGIN,
+                    BUTTON_WIDTH,
+                    BUTTON_HEIGHT,
+                ),
+                ..Default::default()
+            })
+            .collect();
+
+        Self {
+            buttons,
+            capturing: None,
+        }
+    }
+
+    fn row_rect(index: usize) -> Rectangle {
+        Rectangle::new(
+            NAME_X - 2.,
+            ROW_TOP + index as f32 * ROW_HEIGHT,
+            SCREEN_WIDTH as f32 - (NAME_X - 2.) * 2.,
+            ROW_HEIGHT,
+        )
+    }
+
+    fn slot_rect(index: usize, slot: Slot) -> Rectangle {
+        let row = Self::row_rect(index);
+        let (x, width) = match slot {
+            Slot::Key(0) => (KEYS_X, KEY_SLOT_WIDTH),
+            Slot::Key(_) => (KEYS_X + KEY_SLOT_WIDTH, KEY_SLOT_WIDTH),
+            Slot::Button(_) => (BUTTONS_X, row.x + row.width - BUTTONS_X),
+        };
+        Rectangle::new(x - 1., row.y, width, row.height)
+    }
+
+    // while a slot waits for its key, fullscreen and the volume keys don't get it too
+    fn capture(&mut self, app: &mut App, capturing: Option<(HotkeyCategory, Slot)>) {
+        self.capturing = capturing;
+        app.input_handler.set_capturing(capturing.is_some());
+    }
+
+    // the second key slot shows everything past the first, in case hotkeys.json has more
+    fn slot_text(app: &App, category: HotkeyCategory, slot: Slot) -> String {
+        match slot {
+            Slot::Key(0) => app
+                .hotkey_handler
+                .bindings(category)
+                .first()
+                .map_or(String::new(), |key| hotkey_handler::key_label(*key)),
+            Slot::Key(_) => app
+                .hotkey_handler
+                .bindings(category)
+                .iter()
+                .skip(1)
+                .map(|key| hotkey_handler::key_label(*key))
+                .collect::<Vec<String>>()
+                .join(", "),
+            Slot::Button(_) => app
+                .hotkey_handler
+                .button_bindings(category)
+                .iter()
+                .map(|button| hotkey_handler::button_label(*button))
+                .collect::<Vec<&str>>()
+                .join(", "),
+        }
+    }
+}
+
+impl SceneState for ControlsMenu {
+    fn enter(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        self.capture(app, None);
+    }
+
+    fn exit(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        self.capture(app, None);
+        app.hotkey_handler.save();
+    }
+
+    #[profiling::function]
+    fn update(&mut self, app: &mut App, rl: &mut RaylibHandle) {
+        if self.capturing.is_none() {
+            let targets: Vec<Rectangle> = self
+                .buttons
+                .iter()
+                .map(|button| button.rect)
+                .chain(
+                    (0..HotkeyCategory::ALL.len())
+                        .flat_map(|i| SLOTS.into_iter().map(move |slot| Self::slot_rect(i, slot))),
+                )
+                .collect();
+            app.gamepad_handler.navigate(
+                rl,
+                &mut app.input_handler,
+                &app.settings_handler,
+                &targets,
+            );
+        }
+
+        let mouse = app.input_handler.pointer();
+        let clicked = app.hotkey_handler.check_click(&app.input_handler);
+
+        // any key or button goes, even escape or B, a click anywhere gives up
+        if let Some((category, slot)) = self.capturing {
+            match slot {
+                Slot::Key(index) => {
+                    if let Some(key) = app.input_handler.pressed_key() {
+                        app.hotkey_handler.bind(category, index, key);
+                        self.capture(app, None);
+                        return;
+                    }
+                }
+                Slot::Button(index) => {
+                    if let Some(button) = app.input_handler.pressed_button() {
+                        app.hotkey_handler.bind_button(category, index, button);
+                        self.capture(app, None);
+                        return;
+                    }
+                }
+            }
+            if clicked
+                || app
+                    .input_handler
+                    .pointer_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
+            {
+                self.capture(app, None);
+            }
+            return;
+        }
+
+        if app
+            .hotkey_handler
+            .check_pressed(&app.input_handler, HotkeyCategory::Exit)
+            || (clicked && self.buttons[0].rect.check_collision_point_rec(mouse))
+        {
+            app.scene_handler.pop();
+            return;
+        }
+        if clicked && self.buttons[1].rect.check_collision_point_rec(mouse) {
+            app.hotkey_handler.reset_all();
+            return;
+        }
+
+        for (i, category) in HotkeyCategory::ALL.into_iter().enumerate() {
+            if !Self::row_rect(i).check_collision_point_rec(mouse) {
+                continue;
+            }
+            if clicked {
+                // the name edits the first key too, it's the biggest thing to click on
+                let slot = SLOTS
+                    .into_iter()
+                    .find(|slot| Self::slot_rect(i, *slot).check_collision_point_rec(mouse))
+                    .unwrap_or(Slot::Key(0));
+                self.capture(app, Some((category, slot)));
+            } else if app
+                .input_handler
+                .pointer_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
+            {
+                app.hotkey_handler.reset(category);
+            }
+        }
+    }
+
+    #[profiling::function]
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        let font = &app.font;
+        let locale = &app.locale;
+        let mouse = app.input_handler.pointer();
+
+        app.texture_handler
+            .draw(rl, BACKGROUND_IMAGE, Vector2::zero(), Color::WHITE);
+
+        let title = locale.get(TITLE);
+        let title_size = get_text_size(font, title, TITLE_SIZE, TITLE_SPACING);
+        rl.draw_text_ex(
+            font,
+            title,
+            Vector2::new((SCREEN_WIDTH as f32 - title_size.x) / 2., TITLE_Y),
+            TITLE_SIZE,
+            TITLE_SPACING,
+            Color::RAYWHITE,
+        );
+
+        for (i, button) in self.buttons.iter().enumerate() {
+            let hovered = button.rect.check_collision_point_rec(mouse) && self.capturing.is_none();
+            let (texture_offset, text_offset) = if hovered {
+                (0., 0.)
+            } else {
+                (BUTTON_HEIGHT, 1.)
+            };
+
+            let text = locale.get(BUTTON_LABELS[i]);
+            button.draw_with_text_middle(
+                rl,
+                text,
+                font,
+                &app.texture_handler,
+                BUTTONS_TEXTURE,
+                &Rectangle::new(0., texture_offset, BUTTON_WIDTH, BUTTON_HEIGHT),
+                get_text_size(font, text, TITLE_SIZE, TITLE_SPACING),
+                &Color::RAYWHITE,
+                TITLE_SIZE,
+                TITLE_SPACING,
+                Vector2::new(0., text_offset),
+                Vector2::new(0., -1.),
+            );
+        }
+
+        let mut help = locale.get(HELP).to_string();
+
+        for (i, category) in HotkeyCategory::ALL.into_iter().enumerate() {
+            let rect = Self::row_rect(i);
+            let hovered = rect.check_collision_point_rec(mouse);
+            let capturing = self
+                .capturing
+                .filter(|(captured, _)| *captured == category)
+                .map(|(_, slot)| slot);
+            if capturing.is_some() || hovered {
+                rl.draw_rectangle_rec(rect, Color::BLACK.alpha(0.3));
+            }
+            if self.capturing.is_none()
+                && let Some(slot) = SLOTS
+                    .into_iter()
+                    .find(|slot| Self::slot_rect(i, *slot).check_collision_point_rec(mouse))
+            {
+                rl.draw_rectangle_rec(Self::slot_rect(i, slot), Color::BLACK.alpha(0.3));
+            }
+
+            let conflicts = app.hotkey_handler.conflicts(category);
+            // the row is too short to say who else wants the key, the line at the bottom says it
+            if hovered && !conflicts.is_empty() {
+                let names: Vec<&str> = conflicts
+                    .iter()
+                    .map(|other| locale.get(other.name()))
+                    .collect();
+                help = locale.format(CONFLICT, &[&names.join(", ")]);
+            }
+            let color = if !conflicts.is_empty() {
+                Color::from_hex(CONFLICT_COLOR).unwrap()
+            } else {
+                Color::RAYWHITE
+            };
+
+            rl.draw_text_ex(
+                font,
+                locale.get(category.name()),
+                Vector2::new(NAME_X, rect.y),
+                ROW_TEXT_SIZE,
+                ROW_TEXT_SPACING,
+                color,
+            );
+
+            for slot in SLOTS {
+                let slot_rect = Self::slot_rect(i, slot);
+                let (text, slot_color) = if capturing == Some(slot) {
+                    let prompt = match slot {
+                        Slot::Key(_) => PRESS_KEY,
+                        Slot::Button(_) => PRESS_BUTTON,
+                    };
+                    (
+                        locale.get(prompt).to_string(),
+                        Color::from_hex(CAPTURE_COLOR).unwrap(),
+                    )
+                } else {
+                    (Self::slot_text(app, category, slot), color)
+                };
+                rl.draw_text_ex(
+                    font,
+                    &text,
+                    Vector2::new(slot_rect.x + 1., rect.y),
+                    ROW_TEXT_SIZE,
+                    ROW_TEXT_SPACING,
+                    slot_color,
+                );
+            }
+        }
+
+        let help_size = get_text_size(font, &help, HELP_TEXT_SIZE, 0.);
+        rl.draw_text_ex(
+            font,
+            &help,
+            Vector2::new((SCREEN_WIDTH as f32 - help_size.x) / 2., HELP_Y),
+            HELP_TEXT_SIZE,
+            0.,
+            Color::RAYWHITE.alpha(0.7),
+        );
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::HashMap,
+    sync::mpsc::{Receiver, TryRecvError, channel},
+};
+
+use chrono::Local;
+use raylib::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    SCREEN_HEIGHT, SCREEN_WIDTH,
+    app::App,
+    game::Game,
+    hotkey_handler::{HotkeyCategory, HotkeyHandler},
+    input::InputHandler,
+    level_generator::{Difficulty, GeneratedLevel, LevelGenerator},
+    locale::Locale,
+    map::Level,
+    music_handler::MusicHandler,
+    profile,
+    scene::{Scene, SceneHandler, SceneState},
+    settings::SettingsHandler,
+    ui::{UIHandler, get_text_size},
+};
+
+pub const DAILY_ARG: &str = "--daily";
+
+const DAILY_HISTORY_PATH: &str = "daily.json";
+
+// all three fires are open, same as the later hand-made levels
+const DAILY_DIFFICULTY_LEVEL: u8 = 8;
+// if a seed gives nothing solvable we walk forward, still the same for everyone
+const SEED_RETRIES: u64 = 16;
+
+const SCORE_PER_SPIRIT: i64 = 1000;
+const SCORE_PER_ACTION: i64 = 50;
+const SCORE_PER_SECOND: i64 = 5;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DailyResult {
+    pub saved: usize,
+    pub actions: usize,
+    pub time: f32,
+    pub score: i64,
+}
+
+impl DailyResult {
+    fn new(saved: usize, actions: usize, time: f32) -> Self {
+        let score = saved as i64 * SCORE_PER_SPIRIT
+            - actions as i64 * SCORE_PER_ACTION
+            - time.floor() as i64 * SCORE_PER_SECOND;
+        Self {
+            saved,
+            actions,
+            time,
+            score: score.max(0),
+        }
+    }
+}
+
+pub struct DailyHandler {
+    pub active: bool,
+    pub seed: u64,
+    generated: Option<GeneratedLevel>,
+    generating: Option<Receiver<Option<GeneratedLevel>>>,
+    failed: bool, // nothing solvable came out of the seed, the loading screen says so
+    time: f32,
+    history: HashMap<u64, DailyResult>, // best result for every seed played
+    last_result: Option<DailyResult>,
+    new_best: bool,
+}
+
+impl DailyHandler {
+    #[profiling::function]
+    pub fn new() -> Self {
+        let history = match std::fs::read_to_string(profile::path(DAILY_HISTORY_PATH)) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            active: false,
+            seed: 0,
+            generated: None,
+            generating: None,
+            failed: false,
+            time: 0.,
+            history,
+            last_result: None,
+            new_best: false,
+        }
+    }
+
+    // 19 october 2026 is 20261019, easy to read out to a friend
+    pub fn today_seed() -> u64 {
+        let Ok(seed) = Local::now().format("%Y%m%d").to_string().parse() else {
+            panic!("COULDN'T MAKE A SEED FROM TODAY'S DATE");
+        };
+        seed
+    }
+
+    #[profiling::function]
+    pub fn start(&mut self, seed: u64, game: &mut Game, scene_handler: &mut SceneHandler) {
+        self.active = true;
+        self.last_result = None;
+        self.failed = false;
+
+        if self.seed == seed && self.generated.is_some() {
+            self.play(game, scene_handler);
+            return;
+        }
+
+        self.seed = seed;
+        self.generated = None;
+
+        // the generator solves every try, the loading screen keeps drawing meanwhile
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || {
+            let generated = (0..SEED_RETRIES).find_map(|offset| {
+                LevelGenerator::new(
+                    seed.wrapping_add(offset),
+                    Difficulty::for_level(DAILY_DIFFICULTY_LEVEL),
+                )
+                .generate()
+            });
+            let _ = sender.send(generated);
+        });
+        self.generating = Some(receiver);
+        scene_handler.set(Scene::DailyLoading);
+    }
+
+    // a seed that gives nothing is a bad link from a friend, not a reason to crash,
+    // the player reads why and goes back to the menu
+    #[profiling::function]
+    pub fn update_loading(
+        &mut self,
+        game: &mut Game,
+        input_handler: &InputHandler,
+        hotkey_handler: &mut HotkeyHandler,
+        scene_handler: &mut SceneHandler,
+    ) {
+        if self.failed {
+            if !hotkey_handler.check_pressed(input_handler, HotkeyCategory::Continue)
+                && !input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            {
+                return;
+            }
+
+            self.failed = false;
+            self.stop();
+            scene_handler.set(Scene::MainMenu);
+            return;
+        }
+
+        let Some(receiver) = &self.generating else {
+            return;
+        };
+
+        // a generator thread that died gave nothing just the same
+        let generated = match receiver.try_recv() {
+            Ok(generated) => generated,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => None,
+        };
+        self.generating = None;
+
+        if generated.is_none() {
+            self.failed = true;
+            return;
+        }
+        self.generated = generated;
+
+        self.play(game, scene_handler);
+    }
+
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
+    fn play(&mut self, game: &mut Game, scene_handler: &mut SceneHandler) {
+        self.restart(game);
+
+        game.dialogue_handler.clear();
+        game.ui_handler = UIHandler::new(DAILY_DIFFICULTY_LEVEL as usize);
+        scene_handler.set(Scene::Level);
+    }
+
+    #[profiling::function]
+    pub fn restart(&mut self, game: &mut Game) {
+        let Some(generated) = &self.generated else {
+            panic!("NO DAILY FOREST TO RESTART");
+        };
+
+        self.time = 0.;
+        game.start_generated(generated);
+    }
+
+    // no result and no best, the history only keeps wins
+    pub fn lose(&mut self, scene_handler: &mut SceneHandler) {
+        self.last_result = None;
+        self.new_best = false;
+        scene_handler.set(Scene::DailyResult);
+    }
+
+    // quitting to the menu, nothing gets saved for the daily forest
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+
+    // takes over Level::update while the daily forest is played
+    #[profiling::function]
+    pub fn update(
+        &mut self,
+        level: &mut Level,
+        left_amount: u8,
+        scene_handler: &mut SceneHandler,
+        music_handler: &MusicHandler,
+        delta: f32,
+        settings_handler: &SettingsHandler,
+    ) {
+        // only runs while the level does, the pause menu stops the clock
+        self.time += delta;
+
+        if left_amount != 0 || !level.finish() {
+            return;
+        }
+
+        if !level.completed() {
+            music_handler.play("death", settings_handler.get_settings());
+            self.lose(scene_handler);
+            return;
+        }
+
+        let result = DailyResult::new(level.survived, level.actions, self.time);
+
+        self.new_best = match self.history.get(&self.seed) {
+            Some(best) => result.score > best.score,
+            None => true,
+        };
+
+        if self.new_best {
+            self.history.insert(self.seed, result.clone());
+            self.save();
+        }
+
+        self.last_result = Some(result);
+        scene_handler.set(Scene::DailyResult);
+    }
+
+    #[profiling::function]
+    fn save(&self) {
+        let Ok(s) = serde_json::to_string_pretty(&self.history) else {
+            panic!("COULDN'T SERIALIZE DAILY HISTORY TO JSON");
+        };
+
+        std::fs::write(profile::path(DAILY_HISTORY_PATH), s)
+            .expect("COULDN'T WRITE DAILY HISTORY TO FILE");
+    }
+
+    #[profiling::function]
+    pub fn update_result(
+        &mut self,
+        input_handler: &InputHandler,
+        hotkey_handler: &mut HotkeyHandler,
+        scene_handler: &mut SceneHandler,
+    ) {
+        if !hotkey_handler.check_pressed(input_handler, HotkeyCategory::Continue)
+            && !input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+        {
+            return;
+        }
+
+        self.stop();
+        scene_handler.set(Scene::MainMenu);
+    }
+
+    #[profiling::function]
+    pub fn draw_result(&self, locale: &Locale, font: &Font, rl: &mut RaylibDrawHandle) {
+        rl.clear_background(Color::from_hex("0b8a8f").unwrap());
+
+        let mut lines = vec![locale.format("daily.title", &[&self.seed])];
+
+        match &self.last_result {
+            Some(result) => {
+                lines.push(locale.format("daily.saved", &[&result.saved]));
+                lines.push(locale.format("daily.actions", &[&result.actions]));
+                let time = format!("{}:{:02}", result.time as u32 / 60, result.time as u32 % 60);
+                lines.push(locale.format("daily.time", &[&time]));
+                lines.push(locale.format("daily.score", &[&result.score]));
+                if self.new_best {
+                    lines.push(locale.get("daily.new_best").to_string());
+                } else if let Some(best) = self.history.get(&self.seed) {
+                    lines.push(locale.format("daily.best", &[&best.score]));
+                }
+            }
+            None => lines.push(locale.get("daily.not_cleared").to_string()),
+        }
+
+        let font_size = 12.;
+        let spacing = 1.25;
+
+        for (i, line) in lines.iter().enumerate() {
+            let text_size = get_text_size(font, line, font_size, spacing);
+            rl.draw_text_ex(
+                font,
+                line,
+                Vector2::new(
+                    SCREEN_WIDTH as f32 / 2. - text_size.x / 2.,
+                    (24 + i * 16) as f32,
+                ),
+                font_size,
+                spacing,
+                Color::RAYWHITE,
+            );
+        }
+
+        let continue_text = locale.get("press_to_continue");
+        let text_size = get_text_size(font, continue_text, font_size / 2., spacing / 2.);
+        rl.draw_text_ex(
+            font,
+            continue_text,
+            Vector2::new(
+                SCREEN_WIDTH as f32 / 2. - text_size.x / 2.,
+                (SCREEN_HEIGHT - 16) as f32,
+            ),
+            font_size / 2.,
+            spacing / 2.,
+            Color::RAYWHITE,
+        );
+    }
+}
+
+pub struct DailyLoadingScene;
+
+impl SceneState for DailyLoadingScene {
+    fn update(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        app.daily_handler.update_loading(
+            &mut app.game,
+            &app.input_handler,
+            &mut app.hotkey_handler,
+            &mut app.scene_handler,
+        );
+    }
+
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        rl.clear_background(Color::from_hex("0b8a8f").unwrap());
+
+        let font_size = 12.;
+        let spacing = 1.25;
+
+        let text = if app.daily_handler.failed() {
+            app.locale
+                .format("daily.failed", &[&app.daily_handler.seed])
+        } else {
+            app.locale.get("daily.generating").to_string()
+        };
+        let text_size = get_text_size(&app.font, &text, font_size, spacing);
+        rl.draw_text_ex(
+            &app.font,
+            &text,
+            Vector2::new(
+                SCREEN_WIDTH as f32 / 2. - text_size.x / 2.,
+                SCREEN_HEIGHT as f32 / 2. - text_size.y / 2.,
+            ),
+            font_size,
+            spacing,
+            Color::RAYWHITE,
+        );
+
+        if !app.daily_handler.failed() {
+            return;
+        }
+
+        let continue_text = app.locale.get("press_to_continue");
+        let text_size = get_text_size(&app.font, continue_text, font_size / 2., spacing / 2.);
+        rl.draw_text_ex(
+            &app.font,
+            continue_text,
+            Vector2::new(
+                SCREEN_WIDTH as f32 / 2. - text_size.x / 2.,
+                (SCREEN_HEIGHT - 16) as f32,
+            ),
+            font_size / 2.,
+            spacing / 2.,
+            Color::RAYWHITE,
+        );
+    }
+}
+
+pub struct DailyResultScene;
+
+impl SceneState for DailyResultScene {
+    fn update(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        app.daily_handler.update_result(
+            &app.input_handler,
+            &mut app.hotkey_handler,
+            &mut app.scene_handler,
+        );
+    }
+
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        app.daily_handler.draw_result(&app.locale, &app.font, rl);
+    }
+}
@@ -1,56 +1,481 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::GameEvent,
+    dialogue_script::{self, Node, Script},
+    game::Subsystem,
+    locale::{self, DEFAULT_LANGUAGE},
+    map::Level,
+    music_handler::MusicHandler,
+    profile,
+    save_handler::SAVE_PATH,
+    settings::{Settings, TextSpeed},
+};
+
+pub const DIALOGUES_FILE: &str = "dialogues.dg";
+
+// next to the level's own save files, the number in front keeps the save code reading it as one
+const PROGRESS_EXTENSION: &str = ".dialogue.json";
+
+// a bark stays up a couple of seconds and a bit longer the more it says
+const BARK_TIME: f32 = 2.;
+const BARK_TIME_PER_CHAR: f32 = 0.05;
+
+// the backlog only goes back this far
+const MAX_HISTORY: usize = 200;
+
+// a speaker with a voice_{speaker} sound blips every few typed characters
+const VOICE_PREFIX: &str = "voice_";
+const BLIP_EVERY: usize = 3;
+
+// what happens in a level that has a dialogue of its own, each one plays once per save
+const TRIGGERS: [(GameEvent, &str); 4] = [
+    (GameEvent::SwampTeleport, "on_swamp"),
+    (GameEvent::SpiritCaught, "on_caught"),
+    (GameEvent::WoodRanOut, "on_no_wood"),
+    (GameEvent::NearlyLost, "on_nearly_lost"),
+];
+
+// what's on screen right now, with the variables already filled in
+#[derive(Clone)]
+pub struct Phrase {
+    pub speaker: String,
+    pub portrait: String,
+    pub text: String,
+    pub choices: Vec<(String, String)>, // (text, label), only the ones whose conditions hold
+}
+
+// one line of the backlog, no speaker is the player picking a choice
+pub struct HistoryLine {
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+// goes into the save along with the level
+#[derive(Serialize, Deserialize, Default)]
+struct DialogueProgress {
+    #[serde(default)]
+    flags: HashSet<String>, // set by the dialogues themselves
+    #[serde(default)]
+    played: HashSet<String>, // triggered dialogues that already had their turn
+}
+
+// how far one dialogue got, the dialogue box and the bark bubble each have one
+#[derive(Default)]
+struct Cursor {
+    tag: String,
+    script: Script,
+    position: usize, // the next node to run
+    phrase_position: usize,
+}
+
 pub struct DialogueHandler {
-    pub dialogue_accumulator: String,
-    pub dialogue_counter: usize,
-    pub dialogue: Vec<(String, String)>, // (name, phrase)
-    pub current_phrase: usize,
+    // long phrases are split to fit the box, how many pages there are is only known once it's drawn
+    pub page: usize,
+    pub page_count: usize,
+    pub page_chars: usize,
+    typed: f32, // characters of the page typed out so far
+    skipping: bool,
+    history: Vec<HistoryLine>, // everything said since the game started
+    scripts: HashMap<String, Script>,
+    main: Cursor,
+    current: Option<Phrase>,
+    interrupting: bool, // started by something that happened in the level, which waits for it
+    bark: Cursor,
+    bark_phrase: Option<Phrase>,
+    bark_timer: f32,
+    pending: VecDeque<String>, // triggered while something else was being said
+    progress: DialogueProgress,
+    variables: HashMap<String, i64>,
+}
+
+impl Cursor {
+    fn new(tag: &str, scripts: &HashMap<String, Script>) -> Self {
+        Self {
+            tag: tag.to_string(),
+            script: scripts.get(tag).cloned().unwrap_or_default(),
+            position: 0,
+            phrase_position: 0,
+        }
+    }
+
+    // the same tag in another language, picks up at the same phrase
+    fn reload(&mut self, scripts: &HashMap<String, Script>) {
+        self.script = scripts.get(&self.tag).cloned().unwrap_or_default();
+        self.position = self.phrase_position;
+    }
+
+    // goes through jumps and flags until there's something to say or nothing left
+    fn run(
+        &mut self,
+        progress: &mut DialogueProgress,
+        variables: &HashMap<String, i64>,
+    ) -> Option<Phrase> {
+        // dialogues that could loop without a phrase don't get past parsing
+        loop {
+            let node = self.script.nodes.get(self.position).cloned()?;
+            self.position += 1;
+
+            match node {
+                Node::Say {
+                    speaker,
+                    emotion,
+                    text,
+                    choices,
+                } => {
+                    self.phrase_position = self.position - 1;
+                    return Some(Phrase {
+                        speaker: speaker.clone(),
+                        portrait: dialogue_script::portrait(&speaker, emotion.as_deref()),
+                        text: dialogue_script::interpolate(&text, variables),
+                        choices: choices
+                            .iter()
+                            .filter(|choice| {
+                                choice
+                                    .condition
+                                    .as_ref()
+                                    .is_none_or(|c| c.holds(&progress.flags, variables))
+                            })
+                            .map(|choice| {
+                                (
+                                    dialogue_script::interpolate(&choice.text, variables),
+                                    choice.target.clone(),
+                                )
+                            })
+                            .collect(),
+                    });
+                }
+                Node::Jump { target, condition } => {
+                    if condition
+                        .as_ref()
+                        .is_none_or(|c| c.holds(&progress.flags, variables))
+                    {
+                        self.position = self.script.target(&target);
+                    }
+                }
+                Node::Set { flag, value } => {
+                    if value {
+                        progress.flags.insert(flag);
+                    } else {
+                        progress.flags.remove(&flag);
+                    }
+                }
+                Node::End => {
+                    self.position = self.script.nodes.len();
+                    return None;
+                }
+            }
+        }
+    }
 }
 
 impl DialogueHandler {
-    pub fn new() -> Self {
+    pub fn new(language: &str, text_speed: TextSpeed) -> Self {
         Self {
-            dialogue_accumulator: String::new(),
-            dialogue_counter: 0,
-            dialogue: vec![],
-            current_phrase: 0,
+            page: 0,
+            page_count: 0,
+            page_chars: 0,
+            typed: 0.,
+            skipping: text_speed == TextSpeed::Skip,
+            history: Vec::new(),
+            scripts: load_scripts(language),
+            main: Cursor::default(),
+            current: None,
+            interrupting: false,
+            bark: Cursor::default(),
+            bark_phrase: None,
+            bark_timer: 0.,
+            pending: VecDeque::new(),
+            progress: DialogueProgress::default(),
+            variables: HashMap::new(),
+        }
+    }
+
+    // whatever is being said carries on in the other language
+    pub fn set_language(&mut self, language: &str) {
+        self.scripts = load_scripts(language);
+
+        // the backlog already has these phrases, in the language they were read in
+        if self.current.is_some() {
+            self.main.reload(&self.scripts);
+            self.current = self.main.run(&mut self.progress, &self.variables);
+            self.page = 0;
+            self.typed = 0.;
+        }
+        if self.bark_phrase.is_some() {
+            self.bark.reload(&self.scripts);
+            self.bark_phrase = self.bark.run(&mut self.progress, &self.variables);
+        }
+    }
+
+    // nothing to say, like in the daily forest
+    pub fn clear(&mut self) {
+        self.page = 0;
+        self.page_count = 0;
+        self.typed = 0.;
+        self.main = Cursor::default();
+        self.current = None;
+        self.interrupting = false;
+        self.bark = Cursor::default();
+        self.bark_phrase = None;
+        self.pending.clear();
+    }
+
+    // an old save without one starts the dialogues over
+    #[profiling::function]
+    pub fn load_progress(&mut self, level_number: u8) {
+        let path = profile::path(SAVE_PATH) + &level_number.to_string() + PROGRESS_EXTENSION;
+        self.progress = match std::fs::read_to_string(path) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+            Err(_) => DialogueProgress::default(),
+        };
+    }
+
+    // has to go after the metadata, saving that clears every other save file
+    #[profiling::function]
+    pub fn save_progress(&self, level_number: u8) {
+        let Ok(s) = serde_json::to_string_pretty(&self.progress) else {
+            panic!("COULDN'T SERIALIZE DIALOGUE PROGRESS TO JSON");
+        };
+
+        let path = profile::path(SAVE_PATH) + &level_number.to_string() + PROGRESS_EXTENSION;
+        std::fs::write(path, s).expect("COULDN'T WRITE DIALOGUE PROGRESS TO FILE");
+    }
+
+    // what {level} and the rest turn into, the game keeps them up to date
+    pub fn set_variables(&mut self, level: &Level, level_number: u8) {
+        for (name, value) in [
+            ("level", level_number as i64 + 1),
+            ("saved", level.survived as i64),
+            ("survive", level.survive as i64),
+            ("wood", level.get_wood() as i64),
+        ] {
+            self.variables.insert(name.to_string(), value);
         }
     }
 
     #[profiling::function]
     pub fn load_dialogue(&mut self, tag: &str) {
-        let result = std::fs::read_to_string("static/dialogues.dg");
-        let dialog_string;
-        match result {
-            Ok(d) => dialog_string = d,
-            Err(e) => panic!("Error parsing dialogues.dg file: {}", e),
-        }
-
-        self.dialogue_accumulator = String::new();
-        self.dialogue_counter = 0;
-        self.dialogue.clear();
-        self.current_phrase = 0;
-
-        let mut got_the_dialog = false;
-        for line in dialog_string.lines() {
-            if line.contains("---") {
-                if got_the_dialog {
-                    break;
-                }
+        self.clear();
+        self.main = Cursor::new(tag, &self.scripts);
+        self.run();
+    }
 
-                let cmp_tag = line.split(' ').collect::<Vec<&str>>()[1];
-                if cmp_tag == tag {
-                    got_the_dialog = true;
-                }
-            } else if got_the_dialog {
-                if !line.contains(&['[', ']'][..]) {
-                    continue;
-                }
+    // the first time something happens in the level, its dialogue waits for its turn
+    #[profiling::function]
+    pub fn react(&mut self, events: &[GameEvent]) {
+        for (event, tag) in TRIGGERS {
+            if events.contains(&event)
+                && self.scripts.contains_key(tag)
+                && !self.progress.played.contains(tag)
+                && !self.pending.iter().any(|pending| pending == tag)
+            {
+                self.pending.push_back(tag.to_string());
+            }
+        }
+    }
+
+    #[profiling::function]
+    pub fn update(&mut self, delta: f32, music_handler: &MusicHandler, settings: &Settings) {
+        self.skipping = settings.text_speed == TextSpeed::Skip;
+        if self.skipping {
+            self.current = None;
+            self.interrupting = false;
+            self.bark_phrase = None;
+        }
+
+        let before = self.typed().min(self.page_chars);
+        self.typed += delta * settings.text_speed.chars_per_second();
+        let after = self.typed().min(self.page_chars);
+        if let Some(phrase) = &self.current
+            && after / BLIP_EVERY > before / BLIP_EVERY
+        {
+            let voice = format!("{VOICE_PREFIX}{}", phrase.speaker);
+            if music_handler.has(&voice) {
+                music_handler.play(&voice, settings);
+            }
+        }
+
+        if self.bark_phrase.is_some() {
+            self.bark_timer -= delta;
+            if self.bark_timer <= 0. {
+                self.next_bark();
+            }
+        }
+
+        // one at a time, and never over a dialogue that's still being read
+        if self.active() || self.bark_phrase.is_some() {
+            return;
+        }
+        let Some(tag) = self.pending.pop_front() else {
+            return;
+        };
+        self.progress.played.insert(tag.clone());
+
+        if self.scripts.get(&tag).is_some_and(|script| script.bark) {
+            self.bark = Cursor::new(&tag, &self.scripts);
+            self.next_bark();
+        } else {
+            self.main = Cursor::new(&tag, &self.scripts);
+            self.interrupting = true;
+            self.run();
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.current.is_some()
+    }
+
+    // the level stands still until a dialogue that cut into it is read
+    pub fn pausing(&self) -> bool {
+        self.interrupting && self.current.is_some()
+    }
+
+    pub fn current(&self) -> Option<&Phrase> {
+        self.current.as_ref()
+    }
+
+    pub fn bark(&self) -> Option<&Phrase> {
+        self.bark_phrase.as_ref()
+    }
+
+    pub fn history(&self) -> &[HistoryLine] {
+        &self.history
+    }
+
+    // how much of the page is on screen, past the end when it's all there
+    pub fn typed(&self) -> usize {
+        self.typed as usize
+    }
+
+    pub fn next_page(&mut self) {
+        self.page += 1;
+        self.typed = 0.;
+    }
+
+    // a phrase with choices waits until one is picked
+    pub fn next(&mut self) {
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|phrase| !phrase.choices.is_empty())
+        {
+            return;
+        }
+        self.run();
+    }
+
+    pub fn choose(&mut self, choice: usize) {
+        let Some((text, label)) = self
+            .current
+            .as_ref()
+            .and_then(|phrase| phrase.choices.get(choice))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.remember(None, &text);
+        self.main.position = self.main.script.target(&label);
+        self.run();
+    }
+
+    fn run(&mut self) {
+        self.page = 0;
+        self.page_chars = 0;
+        self.typed = 0.;
+        self.current = if self.skipping {
+            None
+        } else {
+            self.main.run(&mut self.progress, &self.variables)
+        };
+
+        match self.current.clone() {
+            Some(phrase) => self.remember(Some(&phrase.speaker), &phrase.text),
+            None => self.interrupting = false,
+        }
+    }
+
+    fn next_bark(&mut self) {
+        self.bark_phrase = if self.skipping {
+            None
+        } else {
+            self.bark.run(&mut self.progress, &self.variables)
+        };
+
+        if let Some(phrase) = self.bark_phrase.clone() {
+            self.bark_timer = BARK_TIME + phrase.text.chars().count() as f32 * BARK_TIME_PER_CHAR;
+            self.remember(Some(&phrase.speaker), &phrase.text);
+        }
+    }
+
+    fn remember(&mut self, speaker: Option<&str>, text: &str) {
+        if self.history.len() == MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(HistoryLine {
+            speaker: speaker.map(str::to_string),
+            text: text.to_string(),
+        });
+    }
+}
+
+// a language without its own dialogues gets the default ones
+#[profiling::function]
+fn load_scripts(language: &str) -> HashMap<String, Script> {
+    let language = if std::fs::exists(locale::file_path(language, DIALOGUES_FILE)).unwrap_or(false)
+    {
+        language
+    } else {
+        DEFAULT_LANGUAGE
+    };
+    let path = locale::file_path(language, DIALOGUES_FILE);
+
+    let Ok(source) = std::fs::read_to_string(&path) else {
+        panic!("COULDN'T LOAD {path}");
+    };
+    match dialogue_script::parse(&source, &path) {
+        Ok(scripts) => scripts,
+        Err(message) => panic!("COULDN'T PARSE DIALOGUES, {message}"),
+    }
+}
+
+// every portrait any phrase in any language is drawn with, they have to exist
+#[profiling::function]
+pub fn speakers() -> Vec<String> {
+    let mut speakers: Vec<String> = Vec::new();
+
+    for (language, _) in locale::languages() {
+        let path = locale::file_path(&language, DIALOGUES_FILE);
+        let Ok(source) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let scripts = match dialogue_script::parse(&source, &path) {
+            Ok(scripts) => scripts,
+            Err(message) => panic!("COULDN'T PARSE DIALOGUES, {message}"),
+        };
 
-                let split_by_brackets = line.split(&['[', ']'][..]).collect::<Vec<&str>>();
-                let speaker = split_by_brackets[1];
-                let phrase = split_by_brackets[2].replace("\\", "\n");
-                self.dialogue
-                    .push((speaker.to_string(), phrase.trim_start().to_string()));
+        for node in scripts.values().flat_map(|script| script.nodes.iter()) {
+            let Node::Say {
+                speaker, emotion, ..
+            } = node
+            else {
+                continue;
+            };
+            let portrait = dialogue_script::portrait(speaker, emotion.as_deref());
+            if !speakers.contains(&portrait) {
+                speakers.push(portrait);
             }
         }
     }
+    return speakers;
+}
+
+impl Subsystem for DialogueHandler {
+    // tags in dialogues.dg count levels from one
+    fn level_started(&mut self, level_number: u8) {
+        self.load_dialogue(&format!("level_{}", level_number + 1));
+    }
 }
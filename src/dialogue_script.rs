@@ -0,0 +1,413 @@
+use std::collections::{HashMap, HashSet};
+
+// what {name} in a phrase and comparisons in conditions can refer to, DialogueHandler fills them in
+pub const VARIABLES: [&str; 4] = ["level", "saved", "survive", "wood"];
+
+// jumping here ends the dialogue, no need to put a label at the bottom
+pub const END_LABEL: &str = "end";
+
+const TAG_MARK: &str = "---";
+// --- bark tag --- is said in a bubble while the level goes on
+const BARK_MARK: &str = "bark";
+const COMMENT_MARK: char = '#';
+const LABEL_MARK: char = '@';
+const CHOICE_MARK: char = '>';
+const JUMP_MARK: &str = "->";
+const CONDITION_MARK: char = '?';
+
+#[derive(Clone, Copy)]
+pub enum Compare {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+// two characters first, so <= isn't read as <
+const COMPARES: [(&str, Compare); 6] = [
+    ("==", Compare::Equal),
+    ("!=", Compare::NotEqual),
+    ("<=", Compare::LessEqual),
+    (">=", Compare::GreaterEqual),
+    ("<", Compare::Less),
+    (">", Compare::Greater),
+];
+
+#[derive(Clone)]
+pub enum Condition {
+    Flag {
+        flag: String,
+        negated: bool,
+    },
+    Compare {
+        variable: String,
+        compare: Compare,
+        value: i64,
+    },
+}
+
+#[derive(Clone)]
+pub struct Choice {
+    pub text: String,
+    pub target: String,
+    pub condition: Option<Condition>,
+}
+
+#[derive(Clone)]
+pub enum Node {
+    Say {
+        speaker: String,
+        emotion: Option<String>,
+        text: String,
+        choices: Vec<Choice>,
+    },
+    Jump {
+        target: String,
+        condition: Option<Condition>,
+    },
+    Set {
+        flag: String,
+        value: bool,
+    },
+    End,
+}
+
+// everything under one --- tag ---
+#[derive(Default, Clone)]
+pub struct Script {
+    pub nodes: Vec<Node>,
+    pub bark: bool,
+    labels: HashMap<String, usize>,
+}
+
+impl Condition {
+    pub fn holds(&self, flags: &HashSet<String>, variables: &HashMap<String, i64>) -> bool {
+        match self {
+            Condition::Flag { flag, negated } => flags.contains(flag) != *negated,
+            Condition::Compare {
+                variable,
+                compare,
+                value,
+            } => {
+                let variable = variables.get(variable).copied().unwrap_or(0);
+                match compare {
+                    Compare::Equal => variable == *value,
+                    Compare::NotEqual => variable != *value,
+                    Compare::Less => variable < *value,
+                    Compare::LessEqual => variable <= *value,
+                    Compare::Greater => variable > *value,
+                    Compare::GreaterEqual => variable >= *value,
+                }
+            }
+        }
+    }
+}
+
+impl Script {
+    // labels are checked while parsing, anything unknown here is the end
+    pub fn target(&self, label: &str) -> usize {
+        self.labels.get(label).copied().unwrap_or(self.nodes.len())
+    }
+}
+
+// the texture a phrase is drawn with, emotions are separate portraits
+pub fn portrait(speaker: &str, emotion: Option<&str>) -> String {
+    match emotion {
+        Some(emotion) => format!("{speaker}_{emotion}"),
+        None => speaker.to_string(),
+    }
+}
+
+// {name} becomes the variable's value, anything else in braces is left for the text layout
+pub fn interpolate(text: &str, variables: &HashMap<String, i64>) -> String {
+    let mut result = text.to_string();
+    for (name, value) in variables.iter() {
+        result = result.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    result
+}
+
+// a script being filled, with the targets it jumps to and where
+type PendingScript = (String, Script, Vec<(String, usize)>);
+
+// file is only there for the error messages, which point at the line that's wrong
+#[profiling::function]
+pub fn parse(source: &str, file: &str) -> Result<HashMap<String, Script>, String> {
+    let mut scripts = HashMap::new();
+    let mut current: Option<PendingScript> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let error = |message: String| format!("{file}:{number}: {message}");
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with(COMMENT_MARK) {
+            continue;
+        }
+
+        if let Some(tag) = line
+            .strip_prefix(TAG_MARK)
+            .and_then(|rest| rest.strip_suffix(TAG_MARK))
+        {
+            let (tag, bark) = match tag.trim().split_once(' ') {
+                Some((BARK_MARK, tag)) => (tag.trim(), true),
+                _ => (tag.trim(), false),
+            };
+            if tag.is_empty() {
+                return Err(error("a tag without a name".to_string()));
+            }
+            if !is_name(tag) {
+                return Err(error(format!("\"{tag}\" can't be a tag")));
+            }
+            if let Some((tag, script, targets)) = current.take() {
+                check_targets(&script, &targets, file)?;
+                check_loops(&tag, &script, file)?;
+                scripts.insert(tag, script);
+            }
+            if scripts.contains_key(tag) {
+                return Err(error(format!("tag {tag} is used twice")));
+            }
+            let script = Script {
+                bark,
+                ..Default::default()
+            };
+            current = Some((tag.to_string(), script, Vec::new()));
+            continue;
+        }
+
+        let Some((_, script, targets)) = &mut current else {
+            return Err(error(format!(
+                "\"{line}\" isn't under any {TAG_MARK} tag {TAG_MARK}"
+            )));
+        };
+
+        if let Some(rest) = line.strip_prefix('[') {
+            let Some((speaker, text)) = rest.split_once(']') else {
+                return Err(error("a speaker without a closing ]".to_string()));
+            };
+            let (speaker, emotion) = match speaker.split_once(':') {
+                Some((speaker, emotion)) => (speaker.trim(), Some(emotion.trim().to_string())),
+                None => (speaker.trim(), None),
+            };
+            if speaker.is_empty() || emotion.as_ref().is_some_and(|e| e.is_empty()) {
+                return Err(error("an empty speaker or emotion".to_string()));
+            }
+            check_interpolation(text).map_err(error)?;
+
+            script.nodes.push(Node::Say {
+                speaker: speaker.to_string(),
+                emotion,
+                // \ is a line break the layout has to keep
+                text: text.trim().replace('\\', "\n"),
+                choices: Vec::new(),
+            });
+        } else if let Some(rest) = line.strip_prefix(JUMP_MARK) {
+            let (target, condition) = parse_target(rest).map_err(error)?;
+            targets.push((target.clone(), number));
+            script.nodes.push(Node::Jump { target, condition });
+        } else if let Some(rest) = line.strip_prefix(CHOICE_MARK) {
+            let Some((text, rest)) = rest.rsplit_once(JUMP_MARK) else {
+                return Err(error(format!("a choice without {JUMP_MARK} label")));
+            };
+            if script.bark {
+                return Err(error(
+                    "a bark can't have choices, nobody stops to pick one".to_string(),
+                ));
+            }
+            let (target, condition) = parse_target(rest).map_err(error)?;
+            check_interpolation(text).map_err(error)?;
+            let Some(Node::Say { choices, .. }) = script.nodes.last_mut() else {
+                return Err(error("a choice has to follow a phrase".to_string()));
+            };
+            targets.push((target.clone(), number));
+            choices.push(Choice {
+                text: text.trim().to_string(),
+                target,
+                condition,
+            });
+        } else if let Some(label) = line.strip_prefix(LABEL_MARK) {
+            let label = label.trim();
+            if !is_name(label) || label == END_LABEL {
+                return Err(error(format!("\"{label}\" can't be a label")));
+            }
+            if script
+                .labels
+                .insert(label.to_string(), script.nodes.len())
+                .is_some()
+            {
+                return Err(error(format!("label {label} is used twice")));
+            }
+        } else {
+            let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+            let argument = argument.trim();
+            let node = match command {
+                "set" | "unset" if is_name(argument) => Node::Set {
+                    flag: argument.to_string(),
+                    value: command == "set",
+                },
+                "end" if argument.is_empty() => Node::End,
+                _ => return Err(error(format!("don't know what \"{line}\" means"))),
+            };
+            script.nodes.push(node);
+        }
+    }
+
+    if let Some((tag, script, targets)) = current {
+        check_targets(&script, &targets, file)?;
+        check_loops(&tag, &script, file)?;
+        scripts.insert(tag, script);
+    }
+
+    Ok(scripts)
+}
+
+fn check_targets(script: &Script, targets: &[(String, usize)], file: &str) -> Result<(), String> {
+    for (target, number) in targets.iter() {
+        if target != END_LABEL && !script.labels.contains_key(target) {
+            return Err(format!("{file}:{number}: no label {target} to jump to"));
+        }
+    }
+    Ok(())
+}
+
+// jumps and flags that can go round forever without a phrase would hang the game mid dialogue,
+// a conditional jump counts as going both ways
+fn check_loops(tag: &str, script: &Script, file: &str) -> Result<(), String> {
+    // 0 - not seen, 1 - on the way there, 2 - gets to a phrase or the end
+    let mut state = vec![0u8; script.nodes.len()];
+
+    fn visit(position: usize, script: &Script, state: &mut [u8]) -> Option<String> {
+        let node = script.nodes.get(position)?;
+        match state[position] {
+            1 => return Some(String::new()),
+            2 => return None,
+            _ => {}
+        }
+        state[position] = 1;
+
+        let found = match node {
+            Node::Say { .. } | Node::End => None,
+            Node::Set { .. } => visit(position + 1, script, state),
+            Node::Jump { target, condition } => {
+                let jump = visit(script.target(target), script, state).map(|label| {
+                    if label.is_empty() {
+                        target.clone()
+                    } else {
+                        label
+                    }
+                });
+                match (jump, condition) {
+                    (None, Some(_)) => visit(position + 1, script, state),
+                    (jump, _) => jump,
+                }
+            }
+        };
+
+        state[position] = 2;
+        found
+    }
+
+    for position in 0..script.nodes.len() {
+        if let Some(label) = visit(position, script, &mut state) {
+            return Err(format!(
+                "{file}: {tag} can jump to {label} over and over without saying anything"
+            ));
+        }
+    }
+    Ok(())
+}
+
+// "label" or "label ? condition"
+fn parse_target(text: &str) -> Result<(String, Option<Condition>), String> {
+    let (target, condition) = match text.split_once(CONDITION_MARK) {
+        Some((target, condition)) => (target.trim(), Some(parse_condition(condition)?)),
+        None => (text.trim(), None),
+    };
+    if !is_name(target) {
+        return Err(format!("\"{target}\" can't be a label"));
+    }
+    Ok((target.to_string(), condition))
+}
+
+// "flag", "!flag" or "variable <= number"
+fn parse_condition(text: &str) -> Result<Condition, String> {
+    let text = text.trim();
+
+    for (mark, compare) in COMPARES {
+        let Some((variable, value)) = text.split_once(mark) else {
+            continue;
+        };
+        let variable = variable.trim();
+        if !VARIABLES.contains(&variable) {
+            return Err(format!("no variable {variable}"));
+        }
+        let Ok(value) = value.trim().parse() else {
+            return Err(format!("\"{}\" isn't a number", value.trim()));
+        };
+        return Ok(Condition::Compare {
+            variable: variable.to_string(),
+            compare,
+            value,
+        });
+    }
+
+    let (flag, negated) = match text.strip_prefix('!') {
+        Some(flag) => (flag.trim(), true),
+        None => (text, false),
+    };
+    if !is_name(flag) {
+        return Err(format!("\"{flag}\" can't be a flag"));
+    }
+    Ok(Condition::Flag {
+        flag: flag.to_string(),
+        negated,
+    })
+}
+
+// a misspelled variable would be shown as is, better to hear about it right away
+fn check_interpolation(text: &str) -> Result<(), String> {
+    for part in text.split('{').skip(1) {
+        let Some((name, _)) = part.split_once('}') else {
+            continue;
+        };
+        if is_name(name) && !VARIABLES.contains(&name) {
+            return Err(format!("no variable {name}"));
+        }
+    }
+    Ok(())
+}
+
+fn is_name(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phrase_free_loop_is_an_error() {
+        let source = "--- loop ---\n@top\nset seen\n-> top\n";
+        let Err(message) = parse(source, "test.dg") else {
+            panic!("A LOOP WITHOUT A PHRASE PARSED");
+        };
+        assert!(message.contains("top"), "{message}");
+    }
+
+    #[test]
+    fn conditional_loop_is_an_error() {
+        let source = "--- loop ---\n@top\n-> top ? !seen\n[veles] Hello\n";
+        assert!(parse(source, "test.dg").is_err());
+    }
+
+    #[test]
+    fn loop_through_a_phrase_is_fine() {
+        let source = "--- loop ---\n@top\n[veles] Again?\n> Yes -> top\n> No -> end\n-> top\n";
+        assert!(parse(source, "test.dg").is_ok());
+    }
+}
@@ -3,8 +3,12 @@ use std::collections::HashMap;
 use raylib::prelude::*;
 
 use crate::{
-    map::TILE_SIZE_PX, metadata_handler::MetadataHandler, settings::SettingsHandler,
-    spirits_handler::SpiritsHandler, texture_handler::TextureHandler,
+    animation::AnimationPlayer,
+    camera::WorldCamera,
+    map::{LEVEL_HEIGHT_TILES, LEVEL_WIDTH_TILES, Level, TileType},
+    metadata_handler::MetadataHandler,
+    spirits_handler::SpiritsHandler,
+    texture_handler::TextureHandler,
 };
 
 pub struct EnemiesHandler {
@@ -19,51 +23,86 @@ impl EnemiesHandler {
     }
 
     #[profiling::function]
-    pub fn spawn_enemies(
-        &mut self,
-        metadata_handler: &mut MetadataHandler,
-        settings_handler: &SettingsHandler,
-    ) {
+    pub fn spawn_enemies(&mut self, metadata_handler: &mut MetadataHandler) {
         self.enemies = HashMap::new();
         for i in 0..metadata_handler.enemies.len() {
             self.enemies.insert(
                 i as u8,
                 Enemy::new(Vector2::new(
-                    metadata_handler.enemies[i].position[0] as f32
-                        * (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32,
-                    metadata_handler.enemies[i].position[1] as f32
-                        * (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32,
+                    metadata_handler.enemies[i].position[0] as f32,
+                    metadata_handler.enemies[i].position[1] as f32,
                 )),
             );
         }
     }
-    pub fn rescale_ui(&mut self, prev_scale: f32, new_scale: f32) {
-        for (_, enemy) in self.enemies.iter_mut() {
-            enemy.rescale(prev_scale, new_scale);
-        }
-    }
 }
 
+// stands on one tile for the whole level, unless the modifier gives it a patrol tick
 pub struct Enemy {
     position: Vector2,
+    direction: Vector2,
+    timer: f32,
+    animation: AnimationPlayer,
 }
 
 impl Enemy {
     pub fn new(position: Vector2) -> Self {
-        Self { position }
+        Self {
+            position,
+            direction: Vector2::new(1., 0.),
+            timer: 0.,
+            animation: AnimationPlayer::new("enemy"),
+        }
     }
 
-    fn rescale(&mut self, prev_scale: f32, new_scale: f32) {
-        self.position = self.position / prev_scale * new_scale;
+    pub fn update_animation(&mut self, delta: f32) {
+        self.animation.update(delta);
     }
 
+    // back and forth along the row, turning at anything that isn't open ground
     #[profiling::function]
-    fn get_position(&self) -> Vector2 {
+    pub fn patrol(&mut self, level: &Level, delta: f32) {
+        let Some(tick) = level.modifier.enemy_tick() else {
+            return;
+        };
+        self.timer += delta;
+        if self.timer < tick {
+            return;
+        }
+        // what's left over counts toward the next step, so it keeps the solver's beat
+        self.timer -= tick;
+
+        let next = self.position + self.direction;
+        if Enemy::walkable(level, next) {
+            self.position = next;
+        } else {
+            self.direction = -self.direction;
+        }
+    }
+
+    fn walkable(level: &Level, position: Vector2) -> bool {
+        position.x >= 0.
+            && position.y >= 0.
+            && (position.x as usize) < LEVEL_WIDTH_TILES
+            && (position.y as usize) < LEVEL_HEIGHT_TILES
+            && matches!(
+                level.tiles[position.x as usize][position.y as usize],
+                TileType::Air { .. }
+            )
+    }
+
+    pub fn get_direction(&self) -> Vector2 {
+        self.direction
+    }
+
+    #[profiling::function]
+    pub fn get_position(&self) -> Vector2 {
         return self.position;
     }
 
     #[profiling::function]
-    pub fn collide_check(&mut self, spirits: &mut SpiritsHandler) {
+    pub fn collide_check(&mut self, spirits: &mut SpiritsHandler) -> usize {
+        let mut caught = 0;
         let near_spirits = spirits
             .spirits
             .iter_mut()
@@ -71,34 +110,18 @@ impl Enemy {
 
         for spirit in near_spirits {
             spirit.1.kill_spirit();
+            caught += 1;
         }
+
+        return caught;
     }
 
     #[profiling::function]
-    pub fn draw(
-        &self,
-        rl: &mut RaylibDrawHandle,
-        texture_handler: &TextureHandler,
-        settings_handler: &SettingsHandler,
-    ) {
-        let source = Rectangle::new(
-            ((rl.get_time() * 8.) % 4.).floor() as f32 * 16.,
-            16.,
-            16.,
-            16.,
-        );
-
-        rl.draw_texture_pro(
-            texture_handler.get("enemy"),
-            source,
-            Rectangle::new(
-                self.position.x as f32,
-                self.position.y as f32,
-                (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32,
-                (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32,
-            ),
-            Vector2::zero(),
-            0.0,
+    pub fn draw(&self, rl: &mut RaylibDrawHandle, texture_handler: &TextureHandler) {
+        texture_handler.draw_animation(
+            rl,
+            &self.animation,
+            WorldCamera.tile_rect(self.position),
             Color::WHITE,
         );
     }
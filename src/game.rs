@@ -0,0 +1,471 @@
+use raylib::prelude::*;
+
+use crate::{
+    achievements::GameEvent,
+    animation::AnimationPlayer,
+    app::App,
+    camera::WorldCamera,
+    daily::DailyHandler,
+    dialogue::DialogueHandler,
+    enemy_spirit::EnemiesHandler,
+    hint::{HintContext, HintHandler},
+    hotkey_handler::{HotkeyCategory, HotkeyHandler},
+    input::InputHandler,
+    level_generator::GeneratedLevel,
+    level_transition::LevelTransition,
+    locale::Locale,
+    map::Level,
+    metadata_handler::MetadataHandler,
+    music_handler::MusicHandler,
+    order::OrderHandler,
+    particle::Particle,
+    save_handler::SaveHandler,
+    scene::{SceneHandler, SceneState},
+    score::ScoreHandler,
+    settings::SettingsHandler,
+    spirits_handler::SpiritsHandler,
+    stats::StatsHandler,
+    texture_handler::TextureHandler,
+    ui::{self, UIHandler},
+};
+
+// anything that has to know when a level starts,
+// Game calls these so the call sites don't have to remember every handler
+pub trait Subsystem {
+    // a fresh start, restarting after a loss doesn't count
+    fn level_started(&mut self, _level_number: u8) {}
+}
+
+// what a level frame needs from outside of Game, handed over in one piece
+pub struct LevelContext<'a> {
+    pub scene_handler: &'a mut SceneHandler,
+    pub music_handler: &'a MusicHandler<'a>,
+    pub input_handler: &'a InputHandler,
+    pub hotkey_handler: &'a mut HotkeyHandler,
+    pub settings_handler: &'a mut SettingsHandler,
+    pub daily_handler: &'a mut DailyHandler,
+    pub locale: &'a Locale,
+    pub stats_key: &'a str,
+}
+
+// everything that lives and dies with the level being played
+pub struct Game {
+    pub level_number: u8,
+    pub level: Level,
+    pub metadata_handler: MetadataHandler,
+    pub spirits_handler: SpiritsHandler,
+    pub enemies_handler: EnemiesHandler,
+    pub order_handler: OrderHandler,
+    pub ui_handler: UIHandler,
+    pub dialogue_handler: DialogueHandler,
+    pub level_transition: LevelTransition,
+    pub hint_handler: HintHandler,
+    pub stats_handler: StatsHandler,
+    pub score_handler: ScoreHandler,
+    pub particles: Vec<Particle>,
+}
+
+impl Game {
+    #[profiling::function]
+    pub fn new(
+        level_number: u8,
+        rl: &mut RaylibHandle,
+        settings_handler: &SettingsHandler,
+    ) -> Self {
+        let mut game = Self {
+            level_number,
+            level: Level::new(),
+            metadata_handler: MetadataHandler::new(level_number),
+            spirits_handler: SpiritsHandler::new(),
+            enemies_handler: EnemiesHandler::new(),
+            order_handler: OrderHandler::new(),
+            ui_handler: UIHandler::new(level_number as usize),
+            dialogue_handler: DialogueHandler::new(
+                &settings_handler.settings.language,
+                settings_handler.settings.text_speed,
+            ),
+            level_transition: LevelTransition::new(),
+            hint_handler: HintHandler::new(),
+            stats_handler: StatsHandler::new(),
+            score_handler: ScoreHandler::new(),
+            particles: Vec::new(),
+        };
+        game.start_level(level_number, rl, settings_handler);
+
+        return game;
+    }
+
+    fn systems(&mut self) -> [&mut dyn Subsystem; 3] {
+        [
+            &mut self.ui_handler,
+            &mut self.dialogue_handler,
+            &mut self.level_transition,
+        ]
+    }
+
+    fn spawn(&mut self) {
+        self.spirits_handler
+            .spawn_spirits(&mut self.metadata_handler);
+        self.enemies_handler
+            .spawn_enemies(&mut self.metadata_handler);
+        self.particles.clear();
+    }
+
+    fn load_world(&mut self, rl: &mut RaylibHandle, settings_handler: &SettingsHandler) {
+        self.metadata_handler.load(self.level_number);
+        self.metadata_handler
+            .set_modifiers(&settings_handler.settings);
+        self.level = Level::new();
+        self.level
+            .load(self.level_number, &mut self.metadata_handler, rl);
+        self.spawn();
+    }
+
+    // the only way into a hand-made level, whether from the menu, the selector or a transition
+    #[profiling::function]
+    pub fn start_level(
+        &mut self,
+        level_number: u8,
+        rl: &mut RaylibHandle,
+        settings_handler: &SettingsHandler,
+    ) {
+        self.level_number = level_number;
+        self.load_world(rl, settings_handler);
+        self.dialogue_handler
+            .set_variables(&self.level, self.level_number);
+
+        for system in self.systems() {
+            system.level_started(level_number);
+        }
+    }
+
+    // same level from scratch, the build buttons and the dialogue stay as they were
+    #[profiling::function]
+    pub fn restart_level(&mut self, rl: &mut RaylibHandle, settings_handler: &SettingsHandler) {
+        self.load_world(rl, settings_handler);
+    }
+
+    #[profiling::function]
+    pub fn load_save(&mut self, rl: &mut RaylibHandle, settings_handler: &SettingsHandler) {
+        self.level_number = SaveHandler::get_level_number();
+        self.metadata_handler.load_save();
+        self.metadata_handler
+            .set_modifiers(&settings_handler.settings);
+        self.level = Level::new();
+        self.level
+            .load_save(self.level_number, &mut self.metadata_handler, rl);
+        self.spawn();
+
+        let level_number = self.level_number;
+        self.dialogue_handler.load_progress(level_number);
+        self.dialogue_handler
+            .set_variables(&self.level, level_number);
+        for system in self.systems() {
+            system.level_started(level_number);
+        }
+    }
+
+    // levels that never touch the disk, the caller sets up the ui it wants
+    #[profiling::function]
+    pub fn start_generated(&mut self, generated: &GeneratedLevel) {
+        self.level = Level::new();
+        // generated metadata has no modifiers, everyone plays the same forest on the same rules
+        self.metadata_handler = generated.metadata.clone();
+        self.level
+            .load_generated(generated.tiles, &mut self.metadata_handler);
+        self.spawn();
+    }
+
+    // true if the level should be restarted
+    #[profiling::function]
+    pub fn update(&mut self, rl: &mut RaylibHandle, context: LevelContext) -> bool {
+        let LevelContext {
+            scene_handler,
+            music_handler,
+            input_handler,
+            hotkey_handler,
+            settings_handler,
+            daily_handler,
+            locale,
+            stats_key,
+        } = context;
+        let delta = input_handler.frame_time(rl);
+
+        self.dialogue_handler
+            .set_variables(&self.level, self.level_number);
+        self.dialogue_handler
+            .update(delta, music_handler, settings_handler.get_settings());
+        self.ui_handler.update(
+            hotkey_handler,
+            scene_handler,
+            &mut self.dialogue_handler,
+            rl,
+            input_handler,
+        );
+
+        if self.dialogue_handler.pausing() {
+            return false;
+        }
+        let spare = self.spare();
+
+        let left_amount =
+            (self.spirits_handler.spirits.len() + self.spirits_handler.pending_spawns()) as u8;
+        if daily_handler.active {
+            daily_handler.update(
+                &mut self.level,
+                left_amount,
+                scene_handler,
+                music_handler,
+                delta,
+                settings_handler,
+            );
+        } else {
+            self.level.update(
+                self.level_number,
+                scene_handler,
+                &mut self.score_handler,
+                left_amount,
+                music_handler,
+                settings_handler,
+            );
+        }
+
+        if hotkey_handler.check_pressed(input_handler, HotkeyCategory::Reset) {
+            return true;
+        }
+
+        self.hint_handler.update(
+            delta,
+            hotkey_handler,
+            &mut self.stats_handler,
+            HintContext {
+                level: &self.level,
+                spirits_handler: &self.spirits_handler,
+                enemies_handler: &self.enemies_handler,
+                ui_handler: &self.ui_handler,
+                input_handler,
+                locale,
+                stats_key,
+            },
+        );
+        if self.hint_handler.searching() {
+            return false;
+        }
+
+        self.level.time += delta;
+        self.level.update_animations(delta);
+        for enemy in self.enemies_handler.enemies.values_mut() {
+            enemy.update_animation(delta);
+            enemy.patrol(&self.level, delta);
+        }
+
+        // a fire waiting on the cursor takes Select for itself
+        if !self.ui_handler.placing() {
+            self.order_handler.select_spirit(
+                &mut self.spirits_handler,
+                &mut self.level,
+                input_handler,
+                hotkey_handler,
+            );
+        }
+        self.order_handler
+            .update_line(&self.level, input_handler, hotkey_handler);
+
+        // the picked spirit holds still while it's being given an order
+        for (key, spirit) in self.spirits_handler.spirits.iter_mut() {
+            spirit.pause_animation(self.order_handler.selected() == Some(*key));
+        }
+
+        self.ui_handler.build(
+            &mut self.level,
+            input_handler,
+            hotkey_handler,
+            &mut self.dialogue_handler,
+        );
+
+        self.spirits_handler.update_portals(delta);
+
+        // particles are just an effect, they live on the screen and not in the world
+        let camera = WorldCamera;
+        for spirit in self.spirits_handler.spirits.values() {
+            if spirit.get_dead() {
+                self.level.spawn_effect(
+                    spirit.get_draw_position(),
+                    AnimationPlayer::with_clip("spirit", "die"),
+                );
+                self.particles.push(Particle::new(
+                    camera.to_screen(spirit.get_draw_position())
+                        + Vector2::one() * camera.tile_size() / 2.,
+                    16,
+                    10.,
+                    5.,
+                ));
+            }
+        }
+
+        // this is such a cool function fr fr tbh lowkey
+        self.spirits_handler
+            .spirits
+            .retain(|_, spirit| !spirit.get_dead());
+
+        // better than iter_mut() with (_, enemy)
+        for enemy in self.enemies_handler.enemies.values_mut() {
+            let caught = enemy.collide_check(&mut self.spirits_handler);
+            self.level.caught += caught;
+            for _ in 0..caught {
+                self.level.push_event(GameEvent::SpiritCaught);
+            }
+        }
+
+        for spirit in self.spirits_handler.spirits.values_mut() {
+            spirit.update_behaviour(&mut self.level, music_handler, delta, settings_handler);
+        }
+
+        if spare > 0 && self.spare() == 0 {
+            self.level.push_event(GameEvent::NearlyLost);
+        }
+        // the daily forest has nothing to say
+        if !daily_handler.active {
+            self.dialogue_handler.react(&self.level.events);
+        }
+
+        return false;
+    }
+
+    // how many more spirits the level can afford to lose
+    fn spare(&self) -> i64 {
+        let left = self.spirits_handler.spirits.len() + self.spirits_handler.pending_spawns();
+        (self.level.survived + left) as i64 - self.level.survive as i64
+    }
+
+    #[profiling::function]
+    pub fn draw_world(&mut self, texture_handler: &TextureHandler, rl: &mut RaylibDrawHandle) {
+        rl.clear_background(Color::from_hex("0b8a8f").unwrap());
+
+        self.level.draw(rl, texture_handler, self.level_number);
+        for spirit in self.spirits_handler.spirits.values() {
+            spirit.draw(rl, texture_handler);
+        }
+        for enemy in self.enemies_handler.enemies.values() {
+            enemy.draw(rl, texture_handler);
+        }
+
+        self.order_handler
+            .draw(&self.spirits_handler, texture_handler, rl);
+
+        for particle in self.particles.iter_mut() {
+            particle.draw(rl);
+        }
+    }
+
+    #[profiling::function]
+    pub fn draw_ui(
+        &mut self,
+        texture_handler: &TextureHandler,
+        locale: &Locale,
+        font: &Font,
+        rl: &mut RaylibDrawHandle,
+        input_handler: &InputHandler,
+    ) {
+        self.ui_handler.draw(
+            texture_handler,
+            &mut self.dialogue_handler,
+            &mut self.level,
+            locale,
+            font,
+            rl,
+            input_handler,
+        );
+        self.hint_handler.draw(font, &self.spirits_handler, rl);
+    }
+}
+
+// the level itself as a scene, the pause menu and settings get pushed over it
+pub struct LevelScene;
+
+impl SceneState for LevelScene {
+    #[profiling::function]
+    fn update(&mut self, app: &mut App, rl: &mut RaylibHandle) {
+        let stats_key = if app.daily_handler.active {
+            format!("daily_{}", app.daily_handler.seed)
+        } else {
+            format!("level_{}", app.game.level_number)
+        };
+
+        // choices are picked like menu buttons, otherwise the d-pad walks the level
+        let choices = ui::choice_rects(&app.game.dialogue_handler);
+        if choices.is_empty() {
+            app.gamepad_handler
+                .step_tiles(rl, &mut app.input_handler, &app.settings_handler);
+        } else {
+            app.gamepad_handler.navigate(
+                rl,
+                &mut app.input_handler,
+                &app.settings_handler,
+                &choices,
+            );
+        }
+
+        if app
+            .hotkey_handler
+            .check_pressed(&app.input_handler, HotkeyCategory::NextSpirit)
+        {
+            let camera = WorldCamera;
+            if let Some(spirit) = OrderHandler::next_spirit(
+                &app.game.spirits_handler,
+                camera.mouse_world(&app.input_handler),
+            ) {
+                app.input_handler
+                    .place(rl, &app.settings_handler, camera.to_screen(spirit));
+            }
+        }
+
+        let context = LevelContext {
+            scene_handler: &mut app.scene_handler,
+            music_handler: &app.music_handler,
+            input_handler: &app.input_handler,
+            hotkey_handler: &mut app.hotkey_handler,
+            settings_handler: &mut app.settings_handler,
+            daily_handler: &mut app.daily_handler,
+            locale: &app.locale,
+            stats_key: &stats_key,
+        };
+        if app.game.update(rl, context) {
+            app.restart_level(rl);
+        }
+    }
+
+    fn draw_world(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        app.game.draw_world(&app.texture_handler, rl);
+        app.gamepad_handler.draw_tile(rl, &app.input_handler);
+    }
+
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        app.game.draw_ui(
+            &app.texture_handler,
+            &app.locale,
+            &app.font,
+            rl,
+            &app.input_handler,
+        );
+    }
+
+    fn title(&self, app: &App, rl: &RaylibHandle) -> Option<String> {
+        if app.daily_handler.active {
+            return Some(app.locale.format("title.daily", &[&app.daily_handler.seed]));
+        }
+
+        if cfg!(debug_assertions) {
+            return Some(format!(
+                "[{}] {}",
+                rl.get_fps(),
+                app.locale
+                    .format("title.level", &[&(app.game.level_number + 1)])
+            ));
+        }
+        Some(
+            app.locale
+                .format("title.level", &[&(app.game.level_number + 1)]),
+        )
+    }
+}
@@ -2,10 +2,13 @@ use std::collections::HashMap;
 
 use crate::{
     FIRST_LEVEL, SCREEN_HEIGHT, SCREEN_WIDTH,
+    app::App,
     hotkey_handler::{HotkeyCategory, HotkeyHandler},
+    input::InputHandler,
+    locale::Locale,
     music_handler::MusicHandler,
-    scene::{Scene, SceneHandler},
-    settings::SettingsHandler,
+    scene::{Scene, SceneHandler, SceneState},
+    text_layout::{Align, TextBox},
     ui::Button,
 };
 use raylib::{ffi::CheckCollisionPointRec, prelude::*};
@@ -16,14 +19,13 @@ pub struct GameOverHandler {
     gameover_type: GameOverHandlerType,
 }
 
-const LABELS_RESTART: [&str; 2] = ["СДАТЬСЯ", "ЕЩЁ РАЗ"];
-const LABELS_ENDGAME: [&str; 2] = ["В МЕНЮ", "ВЫЙТИ"];
+const LABELS_RESTART: [&str; 2] = ["gameover.give_up", "gameover.retry"];
+const LABELS_ENDGAME: [&str; 2] = ["gameover.menu", "gameover.exit"];
 
-const LEVEL_LOSE: [&str; 1] = [
-    "Мы в canned meat studios хотим поблагодарить\nвас за игру в велесову ночь. Нам очень жаль,\nчто вы не добились успехов и надеемся,\nчто вы справитесь лучше в следующий раз.\nудачи!",
-];
-const GAME_END_TEXT: [&str; 1] =
-    ["Спасибо за игру в велесову ночь!\nБлагодаря Вам души предков обрели покой."];
+const LEVEL_LOSE: [&str; 1] = ["gameover.lose"];
+const GAME_END_TEXT: [&str; 1] = ["gameover.end"];
+
+const MAIN_TEXT_X: f32 = 43.;
 
 #[derive(Clone, PartialEq, Eq)]
 pub enum GameOverHandlerType {
@@ -32,7 +34,7 @@ pub enum GameOverHandlerType {
 }
 
 impl GameOverHandler {
-    pub fn new(window_type: GameOverHandlerType, scale: f32) -> Self {
+    pub fn new(window_type: GameOverHandlerType) -> Self {
         let mut restart_buttons = HashMap::new();
         let mut restart_text = HashMap::new();
 
@@ -41,15 +43,15 @@ impl GameOverHandler {
             GameOverHandlerType::Game => LABELS_ENDGAME,
         };
 
-        for i in 0..text.len() {
+        for (i, label) in text.iter().enumerate() {
             restart_buttons.insert(
-                text[i].to_string(),
+                label.to_string(),
                 Button {
                     rect: Rectangle::new(
-                        SCREEN_WIDTH as f32 * scale / 2. - 75.,
-                        SCREEN_HEIGHT as f32 / 2. * scale as f32 + (64 + i * 96) as f32,
-                        150.,
-                        64.,
+                        SCREEN_WIDTH as f32 / 2. - 25.,
+                        SCREEN_HEIGHT as f32 / 2. + (21 + i * 32) as f32,
+                        50.,
+                        21.,
                     ),
                     offset: 0.,
                     selected: false,
@@ -58,8 +60,8 @@ impl GameOverHandler {
             restart_text.insert(
                 text[i].to_string(),
                 Vector2::new(
-                    (SCREEN_WIDTH * scale as i32) as f32 / 2. - 70.,
-                    (SCREEN_HEIGHT * scale as i32 / 2 + 64) as f32 + i as f32 * 96. + 5.,
+                    SCREEN_WIDTH as f32 / 2. - 23.,
+                    (SCREEN_HEIGHT / 2 + 21) as f32 + i as f32 * 32. + 2.,
                 ),
             );
         }
@@ -74,26 +76,15 @@ impl GameOverHandler {
     #[profiling::function]
     pub fn draw_gameover(
         &self,
+        locale: &Locale,
         font: &Font,
         rl: &mut RaylibDrawHandle,
-        settings_handler: &mut SettingsHandler,
+        input_handler: &InputHandler,
     ) {
         rl.clear_background(Color::from_hex("0b8a8f").unwrap());
         for (name, button) in self.restart_buttons.iter() {
             let color = if unsafe {
-                CheckCollisionPointRec(
-                    (rl.get_mouse_position()
-                        - Vector2::new(
-                            rl.get_screen_width() as f32 / 2.
-                                - SCREEN_WIDTH as f32 / 2.
-                                    * settings_handler.settings.pixel_scale as f32,
-                            rl.get_screen_height() as f32 / 2.
-                                - SCREEN_HEIGHT as f32 / 2.
-                                    * settings_handler.settings.pixel_scale as f32,
-                        ))
-                    .into(),
-                    button.rect.into(),
-                )
+                CheckCollisionPointRec(input_handler.pointer().into(), button.rect.into())
             } {
                 Color::WHITE
             } else {
@@ -112,29 +103,31 @@ impl GameOverHandler {
                 GameOverHandlerType::Game => GAME_END_TEXT[0],
             };
 
-            rl.draw_text_pro(
+            let text_box = TextBox {
+                width: SCREEN_WIDTH as f32 - MAIN_TEXT_X * 2.,
+                lines_per_page: 0,
+                align: Align::Left,
+                size: 16.,
+                spacing: 0.66,
+                color: Color::RAYWHITE,
+            };
+            text_box.layout(font, locale.get(main_text)).draw(
+                rl,
                 font,
-                main_text,
-                Vector2::new(
-                    (128.) as f32,
-                    (SCREEN_HEIGHT / 6) as f32 * settings_handler.settings.pixel_scale as f32,
-                ),
-                Vector2::zero(),
-                0.0,
-                48.,
-                2.0,
-                Color::RAYWHITE,
+                0,
+                Vector2::new(MAIN_TEXT_X, (SCREEN_HEIGHT / 6) as f32),
+                usize::MAX,
             );
 
             rl.draw_rectangle_rec(button.rect, color);
             rl.draw_text_pro(
                 font,
-                name,
+                locale.get(name),
                 self.restart_text.get(name).unwrap(),
                 Vector2::zero(),
                 0.0,
-                48.,
-                2.0,
+                16.,
+                0.66,
                 color_text,
             );
         }
@@ -144,27 +137,22 @@ impl GameOverHandler {
     pub fn update_gameover(
         &mut self,
         level_number: &mut u8,
-        rl: &mut RaylibHandle,
+        input_handler: &InputHandler,
         scene_handler: &mut SceneHandler,
         music_handler: &MusicHandler,
         hotkeys: &mut HotkeyHandler,
         should_close: &mut bool,
-        settings_handler: &mut SettingsHandler,
     ) -> bool {
         let mut scene = crate::scene::Scene::Level;
         let mut check = false;
 
-        if self.gameover_type == GameOverHandlerType::Level {
-            music_handler.music_pause();
-        }
-
-        if hotkeys.check_pressed(rl, HotkeyCategory::Exit) {
+        if hotkeys.check_pressed(input_handler, HotkeyCategory::Exit) {
             scene = crate::scene::Scene::MainMenu;
             *level_number = FIRST_LEVEL;
             check = true;
         }
 
-        if hotkeys.check_pressed(rl, HotkeyCategory::Continue) {
+        if hotkeys.check_pressed(input_handler, HotkeyCategory::Continue) {
             if self.gameover_type == GameOverHandlerType::Game {
                 scene = Scene::MainMenu;
             }
@@ -178,33 +166,21 @@ impl GameOverHandler {
         }
 
         for (title, button) in self.restart_buttons.iter_mut() {
-            if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
-                if unsafe {
-                    CheckCollisionPointRec(
-                        (rl.get_mouse_position()
-                            - Vector2::new(
-                                rl.get_screen_width() as f32 / 2.
-                                    - SCREEN_WIDTH as f32 / 2.
-                                        * settings_handler.settings.pixel_scale as f32,
-                                rl.get_screen_height() as f32 / 2.
-                                    - SCREEN_HEIGHT as f32 / 2.
-                                        * settings_handler.settings.pixel_scale as f32,
-                            ))
-                        .into(),
-                        button.rect.into(),
-                    )
-                } {
-                    button.selected = true;
+            if hotkeys.check_click(input_handler)
+                && unsafe {
+                    CheckCollisionPointRec(input_handler.pointer().into(), button.rect.into())
                 }
+            {
+                button.selected = true;
             }
-            if rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) && button.selected {
+            if hotkeys.check_click_released(input_handler) && button.selected {
                 let scene = match title.as_str() {
-                    "СДАТЬСЯ" | "В МЕНЮ" => {
+                    "gameover.give_up" | "gameover.menu" => {
                         *level_number = FIRST_LEVEL;
                         crate::scene::Scene::MainMenu
                     }
-                    "ЕЩЁ РАЗ" => crate::Scene::Level,
-                    "ВЫЙТИ" => {
+                    "gameover.retry" => crate::Scene::Level,
+                    "gameover.exit" => {
                         *should_close = true;
                         return false;
                     }
@@ -221,3 +197,53 @@ impl GameOverHandler {
         return false;
     }
 }
+
+impl SceneState for GameOverHandler {
+    // the music stops for a lost level and comes back with whatever is next
+    fn enter(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        if self.gameover_type == GameOverHandlerType::Level {
+            app.music_handler.music_pause();
+        }
+    }
+
+    fn exit(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        app.music_handler.music_resume();
+    }
+
+    fn update(&mut self, app: &mut App, rl: &mut RaylibHandle) {
+        // the hashmap has them in any order, top to bottom is what the d-pad expects
+        let mut targets: Vec<Rectangle> = self
+            .restart_buttons
+            .values()
+            .map(|button| button.rect)
+            .collect();
+        targets.sort_by(|a, b| a.y.total_cmp(&b.y));
+        app.gamepad_handler
+            .navigate(rl, &mut app.input_handler, &app.settings_handler, &targets);
+
+        let restart = self.update_gameover(
+            &mut app.game.level_number,
+            &app.input_handler,
+            &mut app.scene_handler,
+            &app.music_handler,
+            &mut app.hotkey_handler,
+            &mut app.should_close,
+        );
+
+        if restart && self.gameover_type == GameOverHandlerType::Level {
+            app.game.restart_level(rl, &app.settings_handler);
+        }
+    }
+
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        self.draw_gameover(&app.locale, &app.font, rl, &app.input_handler);
+    }
+
+    fn title(&self, app: &App, _rl: &RaylibHandle) -> Option<String> {
+        let key = match self.gameover_type {
+            GameOverHandlerType::Level => "title.defeat",
+            GameOverHandlerType::Game => "title.victory",
+        };
+        Some(app.locale.get(key).to_string())
+    }
+}
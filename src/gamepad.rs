@@ -0,0 +1,162 @@
+use raylib::prelude::*;
+
+use crate::{
+    camera::WorldCamera,
+    hotkey_handler::{HotkeyCategory, HotkeyHandler},
+    input::InputHandler,
+    map::{LEVEL_HEIGHT_TILES, LEVEL_WIDTH_TILES},
+    settings::SettingsHandler,
+};
+
+const STICK_DEADZONE: f32 = 0.25;
+// game pixels a second with the stick all the way over
+const STICK_SPEED: f32 = 120.;
+// a held direction waits a bit and then keeps going
+const REPEAT_DELAY: f32 = 0.35;
+const REPEAT_TIME: f32 = 0.1;
+
+const CURSOR_COLOR: Color = Color::RAYWHITE;
+
+// the d-pad by default, arrows and wasd on the keyboard
+const DIRECTIONS: [(HotkeyCategory, Vector2); 4] = [
+    (HotkeyCategory::CursorUp, Vector2 { x: 0., y: -1. }),
+    (HotkeyCategory::CursorRight, Vector2 { x: 1., y: 0. }),
+    (HotkeyCategory::CursorDown, Vector2 { x: 0., y: 1. }),
+    (HotkeyCategory::CursorLeft, Vector2 { x: -1., y: 0. }),
+];
+
+// the gamepad and the cursor keys move the pointer around, so everything that looks at it
+// works with them as it is, menus jump it between their buttons and levels between tiles
+pub struct GamepadHandler {
+    held: Option<usize>,
+    repeat: f32,
+    step: Option<Vector2>,
+}
+
+impl GamepadHandler {
+    pub fn new() -> Self {
+        Self {
+            held: None,
+            repeat: 0.,
+            step: None,
+        }
+    }
+
+    // the cursor direction this frame, a held one comes back every REPEAT_TIME
+    pub fn step(&self) -> Option<Vector2> {
+        self.step
+    }
+
+    // once a frame, before any scene looks at the pointer
+    #[profiling::function]
+    pub fn update(
+        &mut self,
+        rl: &mut RaylibHandle,
+        input_handler: &mut InputHandler,
+        settings_handler: &SettingsHandler,
+        hotkey_handler: &mut HotkeyHandler,
+    ) {
+        self.step = None;
+        let held = DIRECTIONS
+            .iter()
+            .position(|(intent, _)| hotkey_handler.check_down(input_handler, *intent));
+        if held != self.held {
+            self.held = held;
+            self.repeat = REPEAT_DELAY;
+            self.step = held.map(|i| DIRECTIONS[i].1);
+        } else if let Some(i) = held {
+            self.repeat -= input_handler.frame_time(rl);
+            if self.repeat <= 0. {
+                self.repeat = REPEAT_TIME;
+                self.step = Some(DIRECTIONS[i].1);
+            }
+        }
+
+        let stick = input_handler.stick();
+        if stick.length() > STICK_DEADZONE {
+            let position =
+                input_handler.pointer() + stick * STICK_SPEED * input_handler.frame_time(rl);
+            input_handler.place(rl, settings_handler, position);
+        }
+    }
+
+    // a step jumps to the nearest target that way, the first one if nothing is under the cursor
+    #[profiling::function]
+    pub fn navigate(
+        &self,
+        rl: &mut RaylibHandle,
+        input_handler: &mut InputHandler,
+        settings_handler: &SettingsHandler,
+        targets: &[Rectangle],
+    ) {
+        let Some(direction) = self.step else {
+            return;
+        };
+        let position = input_handler.pointer();
+        let centre =
+            |rect: &Rectangle| Vector2::new(rect.x + rect.width / 2., rect.y + rect.height / 2.);
+
+        let target = match targets
+            .iter()
+            .find(|rect| rect.check_collision_point_rec(position))
+        {
+            None => targets.first(),
+            Some(current) => {
+                let from = centre(current);
+                // going sideways counts double, so the next row isn't skipped for a closer column
+                let cost = |rect: &&Rectangle| {
+                    let offset = centre(rect) - from;
+                    let along = offset.dot(direction);
+                    along + (offset - direction * along).length() * 2.
+                };
+                targets
+                    .iter()
+                    .filter(|rect| (centre(rect) - from).dot(direction) > 0.)
+                    .min_by(|a, b| cost(a).total_cmp(&cost(b)))
+            }
+        };
+
+        if let Some(target) = target {
+            input_handler.place(rl, settings_handler, centre(target));
+        }
+    }
+
+    // in a level the steps walk the cursor from the middle of one tile to the next
+    #[profiling::function]
+    pub fn step_tiles(
+        &self,
+        rl: &mut RaylibHandle,
+        input_handler: &mut InputHandler,
+        settings_handler: &SettingsHandler,
+    ) {
+        let Some(direction) = self.step else {
+            return;
+        };
+        let camera = WorldCamera;
+        let tile = camera.mouse_world(input_handler) + direction;
+        let tile = Vector2::new(
+            tile.x.floor().clamp(0., LEVEL_WIDTH_TILES as f32 - 1.),
+            tile.y.floor().clamp(0., LEVEL_HEIGHT_TILES as f32 - 1.),
+        );
+        let position = camera.to_screen(tile + Vector2::one() / 2.);
+        input_handler.place(rl, settings_handler, position);
+    }
+
+    // a frame around the tile the cursor is on, the pointer alone is hard to follow on a grid
+    #[profiling::function]
+    pub fn draw_tile(&self, rl: &mut RaylibDrawHandle, input_handler: &InputHandler) {
+        if !input_handler.placed() {
+            return;
+        }
+        let camera = WorldCamera;
+        let tile = camera.mouse_world(input_handler);
+        if tile.x >= LEVEL_WIDTH_TILES as f32 || tile.y >= LEVEL_HEIGHT_TILES as f32 {
+            return;
+        }
+        rl.draw_rectangle_lines_ex(
+            camera.tile_rect(Vector2::new(tile.x.floor(), tile.y.floor())),
+            1.,
+            CURSOR_COLOR.alpha(0.8),
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+
+use raylib::prelude::*;
+
+use crate::{
+    SCREEN_WIDTH,
+    camera::WorldCamera,
+    enemy_spirit::EnemiesHandler,
+    hotkey_handler::{HotkeyCategory, HotkeyHandler},
+    input::InputHandler,
+    locale::Locale,
+    map::{LEVEL_HEIGHT_TILES, LEVEL_WIDTH_TILES, Level},
+    puzzle::{Action, ActionKind, FireKind, Puzzle, SolveResult, Solver},
+    spirits_handler::SpiritsHandler,
+    stats::StatsHandler,
+    text_layout::{Align, TextBox},
+    ui::UIHandler,
+};
+
+// a couple of seconds at worst on the big maps, it runs next to the game anyway
+const HINT_NODE_BUDGET: usize = 200_000;
+const HINT_MAX_ACTIONS: usize = 8;
+const HINT_SHOW_TIME: f32 = 6.;
+
+// 1 - where to look, 2 - what to do there, 3 - exactly which tile and spirit
+const HINT_LEVEL_MAX: u8 = 3;
+
+const HINT_FONT_SIZE: f32 = 8.;
+const HINT_TEXT_Y: f32 = 4.;
+const HINT_TEXT_MARGIN: f32 = 16.;
+const HINT_COLOR: &str = "f9c22b";
+
+// the level as it is when the hint key gets pressed and what the message needs
+pub struct HintContext<'a> {
+    pub level: &'a Level,
+    pub spirits_handler: &'a SpiritsHandler,
+    pub enemies_handler: &'a EnemiesHandler,
+    pub ui_handler: &'a UIHandler,
+    pub input_handler: &'a InputHandler,
+    pub locale: &'a Locale,
+    pub stats_key: &'a str,
+}
+
+pub struct HintHandler {
+    hint_level: u8,
+    message: String,
+    region: Option<Rectangle>, // in tiles
+    tile: Option<(usize, usize)>,
+    spirit: Option<usize>,
+    timer: f32,
+    last_actions: usize,
+    last_key: String,
+    searching: Option<Receiver<SolveResult>>,
+    patrol_tick: f32, // modifiers change it, ticks in the solution get turned back into seconds
+}
+
+impl HintHandler {
+    pub fn new() -> Self {
+        Self {
+            hint_level: 0,
+            message: String::new(),
+            region: None,
+            tile: None,
+            spirit: None,
+            timer: 0.,
+            last_actions: 0,
+            last_key: String::new(),
+            searching: None,
+            patrol_tick: 0.,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.message = String::new();
+        self.region = None;
+        self.tile = None;
+        self.spirit = None;
+        self.timer = 0.;
+        self.searching = None;
+    }
+
+    // the level holds still while the solver works, so the ticks in its answer are still right
+    pub fn searching(&self) -> bool {
+        self.searching.is_some()
+    }
+
+    #[profiling::function]
+    pub fn update(
+        &mut self,
+        delta: f32,
+        hotkey_handler: &mut HotkeyHandler,
+        stats_handler: &mut StatsHandler,
+        context: HintContext,
+    ) {
+        let HintContext {
+            level,
+            spirits_handler,
+            enemies_handler,
+            ui_handler,
+            input_handler,
+            locale,
+            stats_key,
+        } = context;
+
+        // the player did something, the old hint is probably stale
+        if level.actions != self.last_actions || stats_key != self.last_key {
+            self.clear();
+            self.hint_level = 0;
+            self.last_actions = level.actions;
+            self.last_key = stats_key.to_string();
+        }
+
+        if let Some(receiver) = &self.searching {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    self.searching = None;
+                    self.show(result, locale);
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.clear(),
+            }
+        }
+
+        if self.timer > 0. {
+            self.timer -= delta;
+            if self.timer <= 0. {
+                self.clear();
+            }
+        }
+
+        if !hotkey_handler.check_pressed(input_handler, HotkeyCategory::Hint) {
+            return;
+        }
+        // the one already on its way is the answer, another press isn't another hint
+        if self.searching() {
+            return;
+        }
+
+        self.hint_level = (self.hint_level + 1).min(HINT_LEVEL_MAX);
+        stats_handler.add_hint(stats_key);
+
+        let puzzle = Puzzle::from_level(
+            level,
+            spirits_handler,
+            enemies_handler,
+            ui_handler.fires_unlocked(),
+        );
+
+        self.clear();
+        self.patrol_tick = level.modifier.patrol_tick();
+
+        if puzzle.is_hopeless() {
+            self.timer = HINT_SHOW_TIME;
+            self.message = locale.get("hint.hopeless").to_string();
+            return;
+        }
+
+        // the search can take a while on the big maps, the window keeps drawing meanwhile
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || {
+            let mut solver = Solver::new(HINT_NODE_BUDGET);
+            // nobody's listening if the player moved on already
+            let _ = sender.send(solver.solve(&puzzle, HINT_MAX_ACTIONS));
+        });
+        self.searching = Some(receiver);
+        self.message = locale.get("hint.searching").to_string();
+    }
+
+    // ticks in the result count from the moment the key was pressed
+    fn show(&mut self, result: SolveResult, locale: &Locale) {
+        self.timer = HINT_SHOW_TIME;
+
+        let solution = match result {
+            SolveResult::Solved(solution) => solution,
+            SolveResult::Unsolvable => {
+                self.message = locale.get("hint.unsolvable").to_string();
+                return;
+            }
+            SolveResult::Unknown => {
+                self.message = locale.get("hint.unknown").to_string();
+                return;
+            }
+        };
+
+        let Some(&Action { tick, kind }) = solution.first() else {
+            self.message = locale.get("hint.wait").to_string();
+            return;
+        };
+
+        let (spirit, x, y) = kind.target();
+        let (region, region_name) = HintHandler::region(x, y);
+        let region_name = locale.get(region_name);
+
+        let what = match kind {
+            ActionKind::Chop { .. } => locale.get("hint.chop").to_string(),
+            ActionKind::Light { .. } => locale.get("hint.light").to_string(),
+            ActionKind::BuildAndLight { fire, .. } => {
+                locale.format("hint.build", &[&locale.get(HintHandler::fire_name(fire))])
+            }
+        };
+
+        let exact = match kind {
+            ActionKind::Chop { .. } => locale.get("hint.chop_exact").to_string(),
+            ActionKind::Light { .. } => locale.get("hint.light_exact").to_string(),
+            ActionKind::BuildAndLight { fire, .. } => locale.format(
+                "hint.build_exact",
+                &[&locale.get(HintHandler::fire_name(fire))],
+            ),
+        };
+
+        match self.hint_level {
+            1 => {
+                self.message = locale.format("hint.look", &[&region_name]);
+                self.region = Some(region);
+            }
+            2 => {
+                self.message = locale.format("hint.what_look", &[&what, &region_name]);
+                self.region = Some(region);
+            }
+            _ => {
+                self.message = exact;
+                if tick > 0 {
+                    let delay = format!("{:.1}", tick as f32 * self.patrol_tick);
+                    self.message = locale.format("hint.delayed", &[&self.message, &delay]);
+                }
+                self.tile = Some((x, y));
+                self.spirit = Some(spirit);
+            }
+        }
+    }
+
+    fn fire_name(fire: FireKind) -> &'static str {
+        match fire {
+            FireKind::TopDown => "hint.fire.top_down",
+            FireKind::LeftRight => "hint.fire.left_right",
+            FireKind::Stop => "hint.fire.stop",
+        }
+    }
+
+    // the map split into a 3x3 grid, vague enough to still leave some thinking
+    fn region(x: usize, y: usize) -> (Rectangle, &'static str) {
+        let column = (x * 3 / LEVEL_WIDTH_TILES).min(2);
+        let row = (y * 3 / LEVEL_HEIGHT_TILES).min(2);
+
+        let name = match (column, row) {
+            (0, 0) => "hint.region.top_left",
+            (1, 0) => "hint.region.top",
+            (2, 0) => "hint.region.top_right",
+            (0, 1) => "hint.region.left",
+            (1, 1) => "hint.region.center",
+            (2, 1) => "hint.region.right",
+            (0, _) => "hint.region.bottom_left",
+            (1, _) => "hint.region.bottom",
+            _ => "hint.region.bottom_right",
+        };
+
+        let width = LEVEL_WIDTH_TILES as f32 / 3.;
+        let height = LEVEL_HEIGHT_TILES as f32 / 3.;
+        (
+            Rectangle::new(column as f32 * width, row as f32 * height, width, height),
+            name,
+        )
+    }
+
+    #[profiling::function]
+    pub fn draw(&self, font: &Font, spirits_handler: &SpiritsHandler, rl: &mut RaylibDrawHandle) {
+        if self.message.is_empty() {
+            return;
+        }
+
+        let camera = WorldCamera;
+        let tile_size = camera.tile_size();
+        let color = Color::from_hex(HINT_COLOR).unwrap();
+
+        if let Some(region) = self.region {
+            rl.draw_rectangle_rec(
+                Rectangle::new(
+                    region.x * tile_size,
+                    region.y * tile_size,
+                    region.width * tile_size,
+                    region.height * tile_size,
+                ),
+                color.alpha(0.15),
+            );
+        }
+
+        if let Some((x, y)) = self.tile {
+            rl.draw_rectangle_lines_ex(
+                camera.tile_rect(Vector2::new(x as f32, y as f32)),
+                1.,
+                color,
+            );
+        }
+
+        if let Some(spirit) = self
+            .spirit
+            .and_then(|key| spirits_handler.spirits.get(&key))
+        {
+            rl.draw_rectangle_lines_ex(camera.tile_rect(spirit.get_draw_position()), 1., color);
+        }
+
+        let text_box = TextBox {
+            width: SCREEN_WIDTH as f32 - HINT_TEXT_MARGIN * 2.,
+            lines_per_page: 0,
+            align: Align::Center,
+            size: HINT_FONT_SIZE,
+            spacing: 1.,
+            color,
+        };
+        let layout = text_box.layout(font, &self.message);
+        let (width, height) = (layout.width(0), layout.height(0));
+
+        rl.draw_rectangle_rec(
+            Rectangle::new(
+                (SCREEN_WIDTH as f32 - width) / 2. - 2.,
+                HINT_TEXT_Y - 1.,
+                width + 4.,
+                height + 2.,
+            ),
+            Color::BLACK.alpha(0.6),
+        );
+        layout.draw(
+            rl,
+            font,
+            0,
+            Vector2::new(HINT_TEXT_MARGIN, HINT_TEXT_Y),
+            usize::MAX,
+        );
+    }
+}
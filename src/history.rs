@@ -0,0 +1,155 @@
+use raylib::prelude::*;
+
+use crate::{
+    SCREEN_HEIGHT, SCREEN_WIDTH,
+    app::App,
+    hotkey_handler::{self, HotkeyCategory},
+    scene::{Cut, SceneChange, SceneState},
+    text_layout::{Align, TextBox},
+    ui::get_text_size,
+};
+
+const HISTORY_TITLE: &str = "history.title";
+const HISTORY_EMPTY: &str = "history.empty";
+const HISTORY_CLOSE: &str = "history.close";
+
+const TITLE_SIZE: f32 = 12.;
+const TITLE_SPACING: f32 = 1.25;
+const TITLE_Y: f32 = 6.;
+
+const TEXT_SIZE: f32 = 8.;
+const TEXT_SPACING: f32 = 0.5;
+const TEXT_MARGIN: f32 = 16.;
+const LINE_GAP: f32 = 4.;
+const TOP: f32 = 24.;
+const BOTTOM: f32 = 16.;
+
+const SPEAKER_COLOR: &str = "f9c22b";
+const CHOICE_COLOR: &str = "8fd3ff";
+
+// every line said since the game started, pushed over the level like the pause menu,
+// scroll is how many lines up from the newest one the bottom of the screen is
+pub struct HistoryScene {
+    scroll: usize,
+}
+
+impl HistoryScene {
+    pub fn new() -> Self {
+        Self { scroll: 0 }
+    }
+}
+
+impl SceneState for HistoryScene {
+    fn enter(&mut self, _app: &mut App, _rl: &mut RaylibHandle) {
+        self.scroll = 0;
+    }
+
+    #[profiling::function]
+    fn update(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        if app
+            .hotkey_handler
+            .check_pressed(&app.input_handler, HotkeyCategory::Exit)
+            || app
+                .hotkey_handler
+                .check_pressed(&app.input_handler, HotkeyCategory::History)
+        {
+            app.scene_handler.change(SceneChange::Pop, Box::new(Cut));
+            return;
+        }
+
+        let lines = app.game.dialogue_handler.history().len();
+        let wheel = app.input_handler.wheel();
+        let step = app.gamepad_handler.step().map_or(0., |step| step.y);
+        if wheel > 0. || step < 0. {
+            self.scroll = (self.scroll + 1).min(lines.saturating_sub(1));
+        }
+        if wheel < 0. || step > 0. {
+            self.scroll = self.scroll.saturating_sub(1);
+        }
+    }
+
+    #[profiling::function]
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        let font = &app.font;
+        let locale = &app.locale;
+
+        rl.draw_rectangle(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT, Color::BLACK.alpha(0.8));
+
+        let title = locale.get(HISTORY_TITLE);
+        let title_size = get_text_size(font, title, TITLE_SIZE, TITLE_SPACING);
+        rl.draw_text_ex(
+            font,
+            title,
+            Vector2::new((SCREEN_WIDTH as f32 - title_size.x) / 2., TITLE_Y),
+            TITLE_SIZE,
+            TITLE_SPACING,
+            Color::RAYWHITE,
+        );
+
+        let text_box = TextBox {
+            width: SCREEN_WIDTH as f32 - TEXT_MARGIN * 2.,
+            lines_per_page: 0,
+            align: Align::Left,
+            size: TEXT_SIZE,
+            spacing: TEXT_SPACING,
+            color: Color::RAYWHITE,
+        };
+
+        let history = app.game.dialogue_handler.history();
+        if history.is_empty() {
+            text_box.layout(font, locale.get(HISTORY_EMPTY)).draw(
+                rl,
+                font,
+                0,
+                Vector2::new(TEXT_MARGIN, TOP),
+                usize::MAX,
+            );
+        }
+
+        // newest at the bottom, going up until the screen runs out
+        let mut bottom = SCREEN_HEIGHT as f32 - BOTTOM;
+        for line in history.iter().rev().skip(self.scroll) {
+            let text = match &line.speaker {
+                Some(speaker) => format!(
+                    "{{#{SPEAKER_COLOR}}}{}:{{/}} {}",
+                    locale.get(&format!("speaker.{speaker}")),
+                    line.text
+                ),
+                None => format!("{{#{CHOICE_COLOR}}}> {}{{/}}", line.text),
+            };
+            let layout = text_box.layout(font, &text);
+
+            let top = bottom - layout.height(0);
+            if top < TOP {
+                break;
+            }
+            layout.draw(rl, font, 0, Vector2::new(TEXT_MARGIN, top), usize::MAX);
+            bottom = top - LINE_GAP;
+        }
+
+        let close = TextBox {
+            align: Align::Right,
+            color: Color::RAYWHITE.alpha(0.6),
+            ..text_box
+        };
+        // whatever the history hotkey is bound to now
+        let key = app
+            .hotkey_handler
+            .bindings(HotkeyCategory::History)
+            .first()
+            .map_or(String::new(), |key| hotkey_handler::key_label(*key));
+        close
+            .layout(font, &locale.format(HISTORY_CLOSE, &[&key]))
+            .draw(
+                rl,
+                font,
+                0,
+                Vector2::new(TEXT_MARGIN, SCREEN_HEIGHT as f32 - BOTTOM + LINE_GAP),
+                usize::MAX,
+            );
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
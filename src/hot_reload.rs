@@ -0,0 +1,220 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use raylib::prelude::*;
+
+use crate::{
+    SCREEN_WIDTH, app::App, game::Subsystem, level_transition::LevelTransition, locale::Locale,
+    post_processing::PostProcessing, scene::Scene, texture_handler::TextureHandler,
+};
+
+// veles_night --dev [level number], watches static/ and reloads whatever changed
+pub const DEV_ARG: &str = "--dev";
+
+const POLL_INTERVAL: f32 = 0.5;
+
+const ERROR_BAR_HEIGHT: i32 = 24;
+// validation can come back with a line per problem, the bar grows to fit them
+const ERROR_LINE_HEIGHT: i32 = 8;
+const ERROR_TEXT_SIZE: f32 = 6.;
+const ERROR_TEXT_SPACING: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Asset {
+    Level,
+    Locale,
+    Unlocks,
+    Textures,
+    Shaders,
+}
+
+// folders are watched file by file, all the way down
+const WATCHED: [(&str, Asset); 9] = [
+    ("static/maps/", Asset::Level),
+    ("static/metadata/", Asset::Level),
+    ("static/locales/", Asset::Locale),
+    ("static/unlocks.json", Asset::Unlocks),
+    ("static/textures/", Asset::Textures),
+    ("static/assets.json", Asset::Textures),
+    ("static/animations.json", Asset::Textures),
+    ("static/shaders/", Asset::Shaders),
+    ("static/post_processing.json", Asset::Shaders),
+];
+
+pub struct HotReload {
+    modified: HashMap<PathBuf, (Asset, SystemTime)>,
+    timer: f32,
+    // the last reload that went wrong, stays up until one goes right
+    error: Option<String>,
+}
+
+impl HotReload {
+    #[profiling::function]
+    pub fn new() -> Self {
+        Self {
+            modified: Self::scan(),
+            timer: 0.,
+            error: None,
+        }
+    }
+
+    fn scan() -> HashMap<PathBuf, (Asset, SystemTime)> {
+        let mut modified = HashMap::new();
+
+        for (path, asset) in WATCHED {
+            Self::scan_path(PathBuf::from(path), asset, &mut modified);
+        }
+
+        modified
+    }
+
+    fn scan_path(
+        path: PathBuf,
+        asset: Asset,
+        modified: &mut HashMap<PathBuf, (Asset, SystemTime)>,
+    ) {
+        if let Ok(entries) = fs::read_dir(&path) {
+            for entry in entries.flatten() {
+                Self::scan_path(entry.path(), asset, modified);
+            }
+            return;
+        }
+
+        if let Ok(time) = fs::metadata(&path).and_then(|m| m.modified()) {
+            modified.insert(path, (asset, time));
+        }
+    }
+
+    #[profiling::function]
+    pub fn update(
+        &mut self,
+        app: &mut App,
+        post_processing: &mut PostProcessing,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+    ) {
+        self.timer += rl.get_frame_time();
+        if self.timer < POLL_INTERVAL {
+            return;
+        }
+        self.timer = 0.;
+
+        let modified = Self::scan();
+
+        // a file that appeared or went away counts as a change too
+        let mut changed: Vec<Asset> = Vec::new();
+        for (path, (asset, _)) in modified.iter().chain(self.modified.iter()) {
+            let before = self.modified.get(path).map(|(_, t)| t);
+            let after = modified.get(path).map(|(_, t)| t);
+            if before != after && !changed.contains(asset) {
+                changed.push(*asset);
+            }
+        }
+        self.modified = modified;
+
+        if changed.is_empty() {
+            return;
+        }
+
+        let mut errors = Vec::new();
+        for asset in changed {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                Self::reload(asset, app, post_processing, rl, thread)
+            }));
+
+            match result {
+                Ok(Ok(())) => (),
+                Ok(Err(message)) => errors.push(message),
+                Err(payload) => errors.push(panic_message(payload)),
+            }
+        }
+
+        self.error = if errors.is_empty() {
+            None
+        } else {
+            Some(errors.join("\n"))
+        };
+    }
+
+    // everything new is built on the side first where it can be, so a bad file leaves the old one
+    fn reload(
+        asset: Asset,
+        app: &mut App,
+        post_processing: &mut PostProcessing,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+    ) -> Result<(), String> {
+        let game = &mut app.game;
+        match asset {
+            Asset::Level => {
+                // the daily forest isn't on disk and menus don't show a level
+                if app.scene_handler.get_base() != Scene::Level || app.daily_handler.active {
+                    return Ok(());
+                }
+                game.restart_level(rl, &app.settings_handler);
+            }
+            Asset::Locale => {
+                let locale = Locale::new(app.locale.language());
+                game.dialogue_handler.set_language(locale.language());
+                app.locale = locale;
+                app.reload_font(rl, thread);
+            }
+            Asset::Unlocks => {
+                let mut level_transition = LevelTransition::new();
+                level_transition.level_started(game.level_number);
+                game.level_transition = level_transition;
+            }
+            Asset::Textures => {
+                let texture_handler = TextureHandler::new(rl, thread);
+                app.validate_textures(&texture_handler)?;
+                app.texture_handler = texture_handler;
+                app.reload_font(rl, thread);
+            }
+            Asset::Shaders => {
+                *post_processing = PostProcessing::new(rl, thread);
+                if !post_processing.skipped().is_empty() {
+                    return Err(format!(
+                        "COULDN'T COMPILE {}",
+                        post_processing.skipped().join(", ")
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[profiling::function]
+    pub fn draw(&self, font: &Font, rl: &mut RaylibDrawHandle) {
+        let Some(error) = &self.error else {
+            return;
+        };
+
+        let height = ERROR_BAR_HEIGHT.max(error.lines().count() as i32 * ERROR_LINE_HEIGHT + 4);
+        rl.draw_rectangle(0, 0, SCREEN_WIDTH, height, Color::BLACK.alpha(0.8));
+        rl.draw_text_ex(
+            font,
+            error,
+            Vector2::new(2., 2.),
+            ERROR_TEXT_SIZE,
+            ERROR_TEXT_SPACING,
+            Color::RED,
+        );
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return message.clone();
+    }
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    "RELOAD FAILED".to_string()
+}
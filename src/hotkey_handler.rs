@@ -2,7 +2,9 @@ use raylib::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs};
 
-const HOTKEYS_PATH: &str = "dynamic/hotkeys.json";
+use crate::{input::InputHandler, profile};
+
+const HOTKEYS_PATH: &str = "hotkeys.json";
 
 #[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum HotkeyCategory {
@@ -23,31 +25,367 @@ pub enum HotkeyCategory {
     PickButton4 = 14,
     PickButton5 = 15,
     PickButton6 = 16,
+    Hint = 17,
+    History = 18,
+    CursorUp = 19,
+    CursorDown = 20,
+    CursorLeft = 21,
+    CursorRight = 22,
+    NextSpirit = 23,
+    Select = 24,
+    Fullscreen = 25,
     ERROR = 255,
 }
 
-#[derive(Deserialize, Serialize)]
-pub enum KeyboardKeyString {
-    KeyEnter,
-    KeyEsc,
-    KeySpace,
-    KeyS,
-    KeyQ,
-    KeyR,
-    KeyP,
-    KeyC,
-    Key1,
-    Key2,
-    Key3,
-    Key4,
-    Key5,
-    Key6,
-    KeyU,
-    KeyI,
-    KeyPlus,
-    KeyMinus,
-}
+// every key raylib knows, hotkeys.json stores them by the names raylib gives them
+pub const KEYS: [KeyboardKey; 109] = [
+    KeyboardKey::KEY_APOSTROPHE,
+    KeyboardKey::KEY_COMMA,
+    KeyboardKey::KEY_MINUS,
+    KeyboardKey::KEY_PERIOD,
+    KeyboardKey::KEY_SLASH,
+    KeyboardKey::KEY_ZERO,
+    KeyboardKey::KEY_ONE,
+    KeyboardKey::KEY_TWO,
+    KeyboardKey::KEY_THREE,
+    KeyboardKey::KEY_FOUR,
+    KeyboardKey::KEY_FIVE,
+    KeyboardKey::KEY_SIX,
+    KeyboardKey::KEY_SEVEN,
+    KeyboardKey::KEY_EIGHT,
+    KeyboardKey::KEY_NINE,
+    KeyboardKey::KEY_SEMICOLON,
+    KeyboardKey::KEY_EQUAL,
+    KeyboardKey::KEY_A,
+    KeyboardKey::KEY_B,
+    KeyboardKey::KEY_C,
+    KeyboardKey::KEY_D,
+    KeyboardKey::KEY_E,
+    KeyboardKey::KEY_F,
+    KeyboardKey::KEY_G,
+    KeyboardKey::KEY_H,
+    KeyboardKey::KEY_I,
+    KeyboardKey::KEY_J,
+    KeyboardKey::KEY_K,
+    KeyboardKey::KEY_L,
+    KeyboardKey::KEY_M,
+    KeyboardKey::KEY_N,
+    KeyboardKey::KEY_O,
+    KeyboardKey::KEY_P,
+    KeyboardKey::KEY_Q,
+    KeyboardKey::KEY_R,
+    KeyboardKey::KEY_S,
+    KeyboardKey::KEY_T,
+    KeyboardKey::KEY_U,
+    KeyboardKey::KEY_V,
+    KeyboardKey::KEY_W,
+    KeyboardKey::KEY_X,
+    KeyboardKey::KEY_Y,
+    KeyboardKey::KEY_Z,
+    KeyboardKey::KEY_LEFT_BRACKET,
+    KeyboardKey::KEY_BACKSLASH,
+    KeyboardKey::KEY_RIGHT_BRACKET,
+    KeyboardKey::KEY_GRAVE,
+    KeyboardKey::KEY_SPACE,
+    KeyboardKey::KEY_ESCAPE,
+    KeyboardKey::KEY_ENTER,
+    KeyboardKey::KEY_TAB,
+    KeyboardKey::KEY_BACKSPACE,
+    KeyboardKey::KEY_INSERT,
+    KeyboardKey::KEY_DELETE,
+    KeyboardKey::KEY_RIGHT,
+    KeyboardKey::KEY_LEFT,
+    KeyboardKey::KEY_DOWN,
+    KeyboardKey::KEY_UP,
+    KeyboardKey::KEY_PAGE_UP,
+    KeyboardKey::KEY_PAGE_DOWN,
+    KeyboardKey::KEY_HOME,
+    KeyboardKey::KEY_END,
+    KeyboardKey::KEY_CAPS_LOCK,
+    KeyboardKey::KEY_SCROLL_LOCK,
+    KeyboardKey::KEY_NUM_LOCK,
+    KeyboardKey::KEY_PRINT_SCREEN,
+    KeyboardKey::KEY_PAUSE,
+    KeyboardKey::KEY_F1,
+    KeyboardKey::KEY_F2,
+    KeyboardKey::KEY_F3,
+    KeyboardKey::KEY_F4,
+    KeyboardKey::KEY_F5,
+    KeyboardKey::KEY_F6,
+    KeyboardKey::KEY_F7,
+    KeyboardKey::KEY_F8,
+    KeyboardKey::KEY_F9,
+    KeyboardKey::KEY_F10,
+    KeyboardKey::KEY_F11,
+    KeyboardKey::KEY_F12,
+    KeyboardKey::KEY_LEFT_SHIFT,
+    KeyboardKey::KEY_LEFT_CONTROL,
+    KeyboardKey::KEY_LEFT_ALT,
+    KeyboardKey::KEY_LEFT_SUPER,
+    KeyboardKey::KEY_RIGHT_SHIFT,
+    KeyboardKey::KEY_RIGHT_CONTROL,
+    KeyboardKey::KEY_RIGHT_ALT,
+    KeyboardKey::KEY_RIGHT_SUPER,
+    KeyboardKey::KEY_KB_MENU,
+    KeyboardKey::KEY_KP_0,
+    KeyboardKey::KEY_KP_1,
+    KeyboardKey::KEY_KP_2,
+    KeyboardKey::KEY_KP_3,
+    KeyboardKey::KEY_KP_4,
+    KeyboardKey::KEY_KP_5,
+    KeyboardKey::KEY_KP_6,
+    KeyboardKey::KEY_KP_7,
+    KeyboardKey::KEY_KP_8,
+    KeyboardKey::KEY_KP_9,
+    KeyboardKey::KEY_KP_DECIMAL,
+    KeyboardKey::KEY_KP_DIVIDE,
+    KeyboardKey::KEY_KP_MULTIPLY,
+    KeyboardKey::KEY_KP_SUBTRACT,
+    KeyboardKey::KEY_KP_ADD,
+    KeyboardKey::KEY_KP_ENTER,
+    KeyboardKey::KEY_KP_EQUAL,
+    KeyboardKey::KEY_BACK,
+    KeyboardKey::KEY_MENU,
+    KeyboardKey::KEY_VOLUME_UP,
+    KeyboardKey::KEY_VOLUME_DOWN,
+];
+
+// names hotkeys.json used before it took raylib's, older files still load
+const LEGACY_KEYS: [(&str, KeyboardKey); 19] = [
+    ("KeyEnter", KeyboardKey::KEY_ENTER),
+    ("KeyEsc", KeyboardKey::KEY_ESCAPE),
+    ("KeySpace", KeyboardKey::KEY_SPACE),
+    ("KeyS", KeyboardKey::KEY_S),
+    ("KeyQ", KeyboardKey::KEY_Q),
+    ("KeyR", KeyboardKey::KEY_R),
+    ("KeyP", KeyboardKey::KEY_P),
+    ("KeyC", KeyboardKey::KEY_C),
+    ("Key1", KeyboardKey::KEY_ONE),
+    ("Key2", KeyboardKey::KEY_TWO),
+    ("Key3", KeyboardKey::KEY_THREE),
+    ("Key4", KeyboardKey::KEY_FOUR),
+    ("Key5", KeyboardKey::KEY_FIVE),
+    ("Key6", KeyboardKey::KEY_SIX),
+    ("KeyU", KeyboardKey::KEY_U),
+    ("KeyI", KeyboardKey::KEY_I),
+    ("KeyPlus", KeyboardKey::KEY_EQUAL), // + is shift and =, the key itself is =
+    ("KeyMinus", KeyboardKey::KEY_MINUS),
+    ("KeyH", KeyboardKey::KEY_H),
+];
+
+// everything the hotkeys.json shipped before the controls menu had, in those names,
+// a profile still carrying them would keep the old S on skip and cancel over the new defaults
+const LEGACY_HOTKEYS: [(HotkeyCategory, &[&str]); 19] = [
+    (HotkeyCategory::Exit, &["KeyEsc", "KeyQ"]),
+    (HotkeyCategory::Continue, &["KeyEnter", "KeySpace"]),
+    (HotkeyCategory::PickNearest, &["KeyP"]),
+    (HotkeyCategory::PickBuilding1, &["Key1"]),
+    (HotkeyCategory::PickBuilding2, &["Key2"]),
+    (HotkeyCategory::PickBuilding3, &["Key3"]),
+    (HotkeyCategory::Reset, &["KeyR"]),
+    (HotkeyCategory::Skip, &["KeyS"]),
+    (HotkeyCategory::Cancel, &["KeyC", "KeyS"]),
+    (HotkeyCategory::VolumeUp, &["KeyPlus", "KeyU"]),
+    (HotkeyCategory::VolumeDown, &["KeyMinus", "KeyI"]),
+    (HotkeyCategory::PickButton1, &["Key1"]),
+    (HotkeyCategory::PickButton2, &["Key2"]),
+    (HotkeyCategory::PickButton3, &["Key3"]),
+    (HotkeyCategory::PickButton4, &["Key4"]),
+    (HotkeyCategory::PickButton5, &["Key5"]),
+    (HotkeyCategory::PickButton6, &["Key6"]),
+    (HotkeyCategory::Hint, &["KeyH"]),
+    (HotkeyCategory::History, &["KeyL"]),
+];
+
+// every gamepad button raylib knows, with what's printed on an xbox pad
+pub const BUTTONS: [(GamepadButton, &str); 17] = [
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP, "DPAD UP"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT, "DPAD RIGHT"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN, "DPAD DOWN"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT, "DPAD LEFT"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP, "Y"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT, "B"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN, "A"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT, "X"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_1, "LB"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_2, "LT"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_1, "RB"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_2, "RT"),
+    (GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT, "SELECT"),
+    (GamepadButton::GAMEPAD_BUTTON_MIDDLE, "HOME"),
+    (GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT, "START"),
+    (GamepadButton::GAMEPAD_BUTTON_LEFT_THUMB, "LS"),
+    (GamepadButton::GAMEPAD_BUTTON_RIGHT_THUMB, "RS"),
+];
+
+// what every category starts with, hotkeys.json only keeps what the player changed
+const DEFAULT_HOTKEYS: [(HotkeyCategory, &[KeyboardKey]); 26] = [
+    (
+        HotkeyCategory::Exit,
+        &[KeyboardKey::KEY_ESCAPE, KeyboardKey::KEY_Q],
+    ),
+    (
+        HotkeyCategory::Continue,
+        &[KeyboardKey::KEY_ENTER, KeyboardKey::KEY_SPACE],
+    ),
+    (HotkeyCategory::Reset, &[KeyboardKey::KEY_R]),
+    (HotkeyCategory::PickNearest, &[KeyboardKey::KEY_P]),
+    (HotkeyCategory::PickBuilding1, &[KeyboardKey::KEY_ONE]),
+    (HotkeyCategory::PickBuilding2, &[KeyboardKey::KEY_TWO]),
+    (HotkeyCategory::PickBuilding3, &[KeyboardKey::KEY_THREE]),
+    // s walks the cursor down now, and space is taken by continue
+    (HotkeyCategory::Skip, &[KeyboardKey::KEY_X]),
+    (
+        HotkeyCategory::VolumeUp,
+        &[KeyboardKey::KEY_EQUAL, KeyboardKey::KEY_U],
+    ),
+    (
+        HotkeyCategory::VolumeDown,
+        &[KeyboardKey::KEY_MINUS, KeyboardKey::KEY_I],
+    ),
+    // backspace stands in for the s it used to have
+    (
+        HotkeyCategory::Cancel,
+        &[KeyboardKey::KEY_C, KeyboardKey::KEY_BACKSPACE],
+    ),
+    (HotkeyCategory::PickButton1, &[KeyboardKey::KEY_ONE]),
+    (HotkeyCategory::PickButton2, &[KeyboardKey::KEY_TWO]),
+    (HotkeyCategory::PickButton3, &[KeyboardKey::KEY_THREE]),
+    (HotkeyCategory::PickButton4, &[KeyboardKey::KEY_FOUR]),
+    (HotkeyCategory::PickButton5, &[KeyboardKey::KEY_FIVE]),
+    (HotkeyCategory::PickButton6, &[KeyboardKey::KEY_SIX]),
+    (HotkeyCategory::Hint, &[KeyboardKey::KEY_H]),
+    (HotkeyCategory::History, &[KeyboardKey::KEY_L]),
+    (
+        HotkeyCategory::CursorUp,
+        &[KeyboardKey::KEY_UP, KeyboardKey::KEY_W],
+    ),
+    (
+        HotkeyCategory::CursorDown,
+        &[KeyboardKey::KEY_DOWN, KeyboardKey::KEY_S],
+    ),
+    (
+        HotkeyCategory::CursorLeft,
+        &[KeyboardKey::KEY_LEFT, KeyboardKey::KEY_A],
+    ),
+    (
+        HotkeyCategory::CursorRight,
+        &[KeyboardKey::KEY_RIGHT, KeyboardKey::KEY_D],
+    ),
+    (HotkeyCategory::NextSpirit, &[KeyboardKey::KEY_TAB]),
+    (HotkeyCategory::Select, &[KeyboardKey::KEY_E]),
+    (HotkeyCategory::Fullscreen, &[KeyboardKey::KEY_F]),
+];
+
+// A is the left mouse button, so nothing here uses it, and no two categories share a button,
+// the menu buttons have none, A on whatever the d-pad picked does their job
+const DEFAULT_BUTTONS: [(HotkeyCategory, &[GamepadButton]); 26] = [
+    (
+        HotkeyCategory::Exit,
+        &[GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT],
+    ),
+    (
+        HotkeyCategory::Continue,
+        &[GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT],
+    ),
+    (
+        HotkeyCategory::Reset,
+        &[GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT],
+    ),
+    (
+        HotkeyCategory::PickNearest,
+        &[GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_2],
+    ),
+    (
+        HotkeyCategory::PickBuilding1,
+        &[GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT],
+    ),
+    (
+        HotkeyCategory::PickBuilding2,
+        &[GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP],
+    ),
+    (
+        HotkeyCategory::PickBuilding3,
+        &[GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_1],
+    ),
+    (
+        HotkeyCategory::Skip,
+        &[GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_2],
+    ),
+    // the pad runs out of buttons, the volume is in the settings anyway
+    (HotkeyCategory::VolumeUp, &[]),
+    (HotkeyCategory::VolumeDown, &[]),
+    (
+        HotkeyCategory::Cancel,
+        &[GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_1],
+    ),
+    (HotkeyCategory::PickButton1, &[]),
+    (HotkeyCategory::PickButton2, &[]),
+    (HotkeyCategory::PickButton3, &[]),
+    (HotkeyCategory::PickButton4, &[]),
+    (HotkeyCategory::PickButton5, &[]),
+    (HotkeyCategory::PickButton6, &[]),
+    (
+        HotkeyCategory::Hint,
+        &[GamepadButton::GAMEPAD_BUTTON_LEFT_THUMB],
+    ),
+    (
+        HotkeyCategory::History,
+        &[GamepadButton::GAMEPAD_BUTTON_RIGHT_THUMB],
+    ),
+    (
+        HotkeyCategory::CursorUp,
+        &[GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP],
+    ),
+    (
+        HotkeyCategory::CursorDown,
+        &[GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN],
+    ),
+    (
+        HotkeyCategory::CursorLeft,
+        &[GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT],
+    ),
+    (
+        HotkeyCategory::CursorRight,
+        &[GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT],
+    ),
+    // the stick gets to a spirit fast enough, and A already picks and sends
+    (HotkeyCategory::NextSpirit, &[]),
+    (HotkeyCategory::Select, &[]),
+    (HotkeyCategory::Fullscreen, &[]),
+];
+
 impl HotkeyCategory {
+    // in the order the controls menu lists them
+    pub const ALL: [HotkeyCategory; 26] = [
+        HotkeyCategory::Exit,
+        HotkeyCategory::Continue,
+        HotkeyCategory::Reset,
+        HotkeyCategory::Skip,
+        HotkeyCategory::Cancel,
+        HotkeyCategory::CursorUp,
+        HotkeyCategory::CursorDown,
+        HotkeyCategory::CursorLeft,
+        HotkeyCategory::CursorRight,
+        HotkeyCategory::Select,
+        HotkeyCategory::NextSpirit,
+        HotkeyCategory::PickNearest,
+        HotkeyCategory::PickBuilding1,
+        HotkeyCategory::PickBuilding2,
+        HotkeyCategory::PickBuilding3,
+        HotkeyCategory::Hint,
+        HotkeyCategory::History,
+        HotkeyCategory::VolumeUp,
+        HotkeyCategory::VolumeDown,
+        HotkeyCategory::Fullscreen,
+        HotkeyCategory::PickButton1,
+        HotkeyCategory::PickButton2,
+        HotkeyCategory::PickButton3,
+        HotkeyCategory::PickButton4,
+        HotkeyCategory::PickButton5,
+        HotkeyCategory::PickButton6,
+    ];
+
     #[profiling::function]
     pub fn from_bonfire(value: &str) -> HotkeyCategory {
         match value {
@@ -57,6 +395,53 @@ impl HotkeyCategory {
             _ => HotkeyCategory::ERROR,
         }
     }
+
+    // a string key, the controls menu looks it up in the locale
+    pub fn name(self) -> &'static str {
+        match self {
+            HotkeyCategory::Exit => "hotkey.exit",
+            HotkeyCategory::Continue => "hotkey.continue",
+            HotkeyCategory::Reset => "hotkey.reset",
+            HotkeyCategory::PickNearest => "hotkey.pick_nearest",
+            HotkeyCategory::PickBuilding1 => "hotkey.pick_building_1",
+            HotkeyCategory::PickBuilding2 => "hotkey.pick_building_2",
+            HotkeyCategory::PickBuilding3 => "hotkey.pick_building_3",
+            HotkeyCategory::Skip => "hotkey.skip",
+            HotkeyCategory::VolumeUp => "hotkey.volume_up",
+            HotkeyCategory::VolumeDown => "hotkey.volume_down",
+            HotkeyCategory::Cancel => "hotkey.cancel",
+            HotkeyCategory::PickButton1 => "hotkey.pick_button_1",
+            HotkeyCategory::PickButton2 => "hotkey.pick_button_2",
+            HotkeyCategory::PickButton3 => "hotkey.pick_button_3",
+            HotkeyCategory::PickButton4 => "hotkey.pick_button_4",
+            HotkeyCategory::PickButton5 => "hotkey.pick_button_5",
+            HotkeyCategory::PickButton6 => "hotkey.pick_button_6",
+            HotkeyCategory::Hint => "hotkey.hint",
+            HotkeyCategory::History => "hotkey.history",
+            HotkeyCategory::CursorUp => "hotkey.cursor_up",
+            HotkeyCategory::CursorDown => "hotkey.cursor_down",
+            HotkeyCategory::CursorLeft => "hotkey.cursor_left",
+            HotkeyCategory::CursorRight => "hotkey.cursor_right",
+            HotkeyCategory::NextSpirit => "hotkey.next_spirit",
+            HotkeyCategory::Select => "hotkey.select",
+            HotkeyCategory::Fullscreen => "hotkey.fullscreen",
+            HotkeyCategory::ERROR => "hotkey.error",
+        }
+    }
+
+    fn default_keys(self) -> &'static [KeyboardKey] {
+        DEFAULT_HOTKEYS
+            .iter()
+            .find(|(category, _)| *category == self)
+            .map_or(&[], |(_, keys)| *keys)
+    }
+
+    fn default_buttons(self) -> &'static [GamepadButton] {
+        DEFAULT_BUTTONS
+            .iter()
+            .find(|(category, _)| *category == self)
+            .map_or(&[], |(_, buttons)| *buttons)
+    }
 }
 impl From<u8> for HotkeyCategory {
     #[profiling::function]
@@ -79,74 +464,238 @@ impl From<u8> for HotkeyCategory {
             x if x == HotkeyCategory::PickButton4 as u8 => HotkeyCategory::PickButton4,
             x if x == HotkeyCategory::PickButton5 as u8 => HotkeyCategory::PickButton5,
             x if x == HotkeyCategory::PickButton6 as u8 => HotkeyCategory::PickButton6,
+            x if x == HotkeyCategory::Hint as u8 => HotkeyCategory::Hint,
+            x if x == HotkeyCategory::History as u8 => HotkeyCategory::History,
+            x if x == HotkeyCategory::CursorUp as u8 => HotkeyCategory::CursorUp,
+            x if x == HotkeyCategory::CursorDown as u8 => HotkeyCategory::CursorDown,
+            x if x == HotkeyCategory::CursorLeft as u8 => HotkeyCategory::CursorLeft,
+            x if x == HotkeyCategory::CursorRight as u8 => HotkeyCategory::CursorRight,
+            x if x == HotkeyCategory::NextSpirit as u8 => HotkeyCategory::NextSpirit,
+            x if x == HotkeyCategory::Select as u8 => HotkeyCategory::Select,
+            x if x == HotkeyCategory::Fullscreen as u8 => HotkeyCategory::Fullscreen,
             _ => HotkeyCategory::ERROR,
         }
     }
 }
 
-#[derive(Deserialize, Serialize)]
+// raylib's name for a key, the way hotkeys.json has it
+pub fn key_name(key: KeyboardKey) -> String {
+    format!("{key:?}")
+}
+
+// without the KEY_ and the underscores, for showing to the player
+pub fn key_label(key: KeyboardKey) -> String {
+    let name = key_name(key);
+    name.strip_prefix("KEY_").unwrap_or(&name).replace('_', " ")
+}
+
+pub fn button_name(button: GamepadButton) -> String {
+    format!("{button:?}")
+}
+
+pub fn button_label(button: GamepadButton) -> &'static str {
+    BUTTONS
+        .iter()
+        .find(|(other, _)| *other == button)
+        .map_or("?", |(_, label)| *label)
+}
+
+pub fn parse_button(name: &str) -> Option<GamepadButton> {
+    BUTTONS
+        .iter()
+        .map(|(button, _)| *button)
+        .find(|button| button_name(*button) == name)
+}
+
+pub fn parse_key(name: &str) -> Option<KeyboardKey> {
+    KEYS.iter()
+        .copied()
+        .find(|key| key_name(*key) == name)
+        .or_else(|| {
+            LEGACY_KEYS
+                .iter()
+                .find(|(legacy, _)| *legacy == name)
+                .map(|(_, key)| *key)
+        })
+}
+
+// only the categories the player changed, the rest come from DEFAULT_HOTKEYS
+#[derive(Deserialize, Serialize, Default)]
 pub struct HotkeyLoaderStruct {
-    hotkeys: HashMap<HotkeyCategory, Vec<KeyboardKeyString>>,
+    #[serde(default)]
+    hotkeys: HashMap<HotkeyCategory, Vec<String>>,
+    #[serde(default)]
+    gamepad: HashMap<HotkeyCategory, Vec<String>>,
 }
 
 pub struct HotkeyHandler {
     hotkeys: HashMap<HotkeyCategory, Vec<KeyboardKey>>,
+    buttons: HashMap<HotkeyCategory, Vec<GamepadButton>>,
     last_pressed_hotkey: Option<KeyboardKey>,
 }
 
 impl HotkeyLoaderStruct {
     #[profiling::function]
     pub fn new() -> Self {
-        let path = HOTKEYS_PATH.to_string();
+        let path = profile::path(HOTKEYS_PATH);
         let Ok(string_hotkeys) = fs::read_to_string(path) else {
-            panic!("COULDN'T LOAD HOTKEYS");
+            return Self::default();
         };
-        let Ok(hotkeys) = serde_json::from_str(&string_hotkeys) else {
+        let Ok(mut hotkeys) = serde_json::from_str::<Self>(&string_hotkeys) else {
             panic!("COULDN'T PARSE HOTKEYS JSON");
         };
 
+        hotkeys.drop_legacy();
         return hotkeys;
     }
+
+    // the old file listed every category, the ones it left as they shipped go back to
+    // today's defaults, the menu writes raylib's names so nothing it saved matches these
+    fn drop_legacy(&mut self) {
+        self.hotkeys.retain(|category, names| {
+            !LEGACY_HOTKEYS
+                .iter()
+                .any(|(legacy, legacy_names)| legacy == category && names == legacy_names)
+        });
+    }
 }
 
 impl HotkeyHandler {
     #[profiling::function]
     pub fn new(hotkeys_raw: HotkeyLoaderStruct) -> Self {
-        let mut hotkeys = HashMap::new();
+        let mut hotkeys: HashMap<HotkeyCategory, Vec<KeyboardKey>> = DEFAULT_HOTKEYS
+            .iter()
+            .map(|(category, keys)| (*category, keys.to_vec()))
+            .collect();
 
-        for (target, key_type) in hotkeys_raw.hotkeys.iter() {
+        for (target, names) in hotkeys_raw.hotkeys.iter() {
             let mut vec = vec![];
-            for i in key_type {
-                let key = match i {
-                    KeyboardKeyString::KeyEsc => KeyboardKey::KEY_ESCAPE,
-                    KeyboardKeyString::KeyEnter => KeyboardKey::KEY_ENTER,
-                    KeyboardKeyString::KeySpace => KeyboardKey::KEY_SPACE,
-                    KeyboardKeyString::KeyQ => KeyboardKey::KEY_Q,
-                    KeyboardKeyString::KeyR => KeyboardKey::KEY_R,
-                    KeyboardKeyString::KeyP => KeyboardKey::KEY_P,
-                    KeyboardKeyString::Key1 => KeyboardKey::KEY_ONE,
-                    KeyboardKeyString::Key2 => KeyboardKey::KEY_TWO,
-                    KeyboardKeyString::Key3 => KeyboardKey::KEY_THREE,
-                    KeyboardKeyString::Key4 => KeyboardKey::KEY_FOUR,
-                    KeyboardKeyString::Key5 => KeyboardKey::KEY_FIVE,
-                    KeyboardKeyString::Key6 => KeyboardKey::KEY_SIX,
-                    KeyboardKeyString::KeyS => KeyboardKey::KEY_S,
-                    KeyboardKeyString::KeyMinus => KeyboardKey::KEY_MINUS,
-                    KeyboardKeyString::KeyPlus => KeyboardKey::KEY_EQUAL,
-                    KeyboardKeyString::KeyU => KeyboardKey::KEY_U,
-                    KeyboardKeyString::KeyC => KeyboardKey::KEY_C,
-                    KeyboardKeyString::KeyI => KeyboardKey::KEY_I,
+            for name in names {
+                // a typo in a hand edited file shouldn't keep the game from starting
+                let Some(key) = parse_key(name) else {
+                    println!("unknown key {name} in hotkeys, it's skipped");
+                    continue;
                 };
                 vec.push(key);
             }
-            hotkeys.insert(target.clone(), vec.clone());
+            hotkeys.insert(*target, vec);
+        }
+
+        let mut buttons: HashMap<HotkeyCategory, Vec<GamepadButton>> = DEFAULT_BUTTONS
+            .iter()
+            .map(|(category, buttons)| (*category, buttons.to_vec()))
+            .collect();
+
+        for (target, names) in hotkeys_raw.gamepad.iter() {
+            let mut vec = vec![];
+            for name in names {
+                let Some(button) = parse_button(name) else {
+                    println!("unknown gamepad button {name} in hotkeys, it's skipped");
+                    continue;
+                };
+                vec.push(button);
+            }
+            buttons.insert(*target, vec);
         }
 
         Self {
-            hotkeys: hotkeys,
+            hotkeys,
+            buttons,
             last_pressed_hotkey: None,
         }
     }
+
+    #[profiling::function]
+    pub fn save(&self) {
+        let mut hotkeys_raw = HotkeyLoaderStruct::default();
+        for category in HotkeyCategory::ALL {
+            let keys = self.bindings(category);
+            if keys != category.default_keys() {
+                hotkeys_raw
+                    .hotkeys
+                    .insert(category, keys.iter().map(|key| key_name(*key)).collect());
+            }
+            let buttons = self.button_bindings(category);
+            if buttons != category.default_buttons() {
+                hotkeys_raw.gamepad.insert(
+                    category,
+                    buttons.iter().map(|button| button_name(*button)).collect(),
+                );
+            }
+        }
+
+        let Ok(s) = serde_json::to_string_pretty(&hotkeys_raw) else {
+            panic!("COULDN'T SERIALIZE HOTKEYS TO JSON");
+        };
+        fs::write(profile::path(HOTKEYS_PATH), s).expect("COULDN'T WRITE HOTKEYS TO FILE");
+    }
+
+    // a category nobody bound anything to just never fires
+    pub fn bindings(&self, category: HotkeyCategory) -> &[KeyboardKey] {
+        self.hotkeys
+            .get(&category)
+            .map_or(&[], |keys| keys.as_slice())
+    }
+
+    pub fn button_bindings(&self, category: HotkeyCategory) -> &[GamepadButton] {
+        self.buttons
+            .get(&category)
+            .map_or(&[], |buttons| buttons.as_slice())
+    }
+
+    // replaces one binding and keeps the rest, a slot past the end adds one
+    pub fn bind(&mut self, category: HotkeyCategory, slot: usize, key: KeyboardKey) {
+        let keys = self.hotkeys.entry(category).or_default();
+        match keys.get_mut(slot) {
+            Some(old) => *old = key,
+            None => keys.push(key),
+        }
+    }
+
+    pub fn bind_button(&mut self, category: HotkeyCategory, slot: usize, button: GamepadButton) {
+        let buttons = self.buttons.entry(category).or_default();
+        match buttons.get_mut(slot) {
+            Some(old) => *old = button,
+            None => buttons.push(button),
+        }
+    }
+
+    pub fn reset(&mut self, category: HotkeyCategory) {
+        self.hotkeys
+            .insert(category, category.default_keys().to_vec());
+        self.buttons
+            .insert(category, category.default_buttons().to_vec());
+    }
+
+    pub fn reset_all(&mut self) {
+        for category in HotkeyCategory::ALL {
+            self.reset(category);
+        }
+    }
+
+    // categories sharing a key or a gamepad button with this one, a shared key is only fine
+    // when both defaults have that very key, like 1 picking a fire in the level and the first button everywhere else
+    pub fn conflicts(&self, category: HotkeyCategory) -> Vec<HotkeyCategory> {
+        let keys = self.bindings(category);
+        let buttons = self.button_bindings(category);
+        HotkeyCategory::ALL
+            .into_iter()
+            .filter(|other| {
+                let keys_clash = self.bindings(*other).iter().any(|key| {
+                    keys.contains(key)
+                        && !(category.default_keys().contains(key)
+                            && other.default_keys().contains(key))
+                });
+                let buttons_clash = self.button_bindings(*other).iter().any(|button| {
+                    buttons.contains(button)
+                        && !(category.default_buttons().contains(button)
+                            && other.default_buttons().contains(button))
+                });
+                *other != category && (keys_clash || buttons_clash)
+            })
+            .collect()
+    }
+
     #[profiling::function]
     pub fn get_last_key(&self) -> KeyboardKey {
         return self
@@ -159,38 +708,162 @@ impl HotkeyHandler {
         self.last_pressed_hotkey = None;
     }
 
+    // a left click, A or Select, for buttons that get pressed wherever the cursor is
+    #[profiling::function]
+    pub fn check_click(&mut self, input_handler: &InputHandler) -> bool {
+        input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            || self.check_pressed(input_handler, HotkeyCategory::Select)
+    }
+
+    #[profiling::function]
+    pub fn check_click_down(&mut self, input_handler: &InputHandler) -> bool {
+        input_handler.pointer_down(MouseButton::MOUSE_BUTTON_LEFT)
+            || self.check_down(input_handler, HotkeyCategory::Select)
+    }
+
+    #[profiling::function]
+    pub fn check_click_released(&mut self, input_handler: &InputHandler) -> bool {
+        input_handler.pointer_released(MouseButton::MOUSE_BUTTON_LEFT)
+            || self.check_released(input_handler, HotkeyCategory::Select)
+    }
+
     #[profiling::function]
-    pub fn check_down(&mut self, rl: &RaylibHandle, target_intent: HotkeyCategory) -> bool {
-        for key in self.hotkeys.get(&target_intent).unwrap().iter() {
-            if rl.is_key_down(*key) {
+    pub fn check_down(
+        &mut self,
+        input_handler: &InputHandler,
+        target_intent: HotkeyCategory,
+    ) -> bool {
+        // through the field, so last_pressed_hotkey can still be set
+        let keys = self
+            .hotkeys
+            .get(&target_intent)
+            .map_or(&[][..], |keys| keys.as_slice());
+        for key in keys.iter() {
+            if input_handler.key_down(*key) {
                 self.last_pressed_hotkey = Some(*key);
                 return true;
             }
         }
 
-        return false;
+        return self
+            .button_bindings(target_intent)
+            .iter()
+            .any(|button| input_handler.button_down(*button));
     }
 
     #[profiling::function]
-    pub fn check_released(&mut self, rl: &RaylibHandle, target_intent: HotkeyCategory) -> bool {
-        for key in self.hotkeys.get(&target_intent).unwrap().iter() {
-            if rl.is_key_released(*key) {
+    pub fn check_released(
+        &mut self,
+        input_handler: &InputHandler,
+        target_intent: HotkeyCategory,
+    ) -> bool {
+        for key in self.bindings(target_intent).iter() {
+            if input_handler.key_released(*key) {
                 return true;
             }
         }
 
-        return false;
+        return self
+            .button_bindings(target_intent)
+            .iter()
+            .any(|button| input_handler.button_released(*button));
     }
 
     #[profiling::function]
-    pub fn check_pressed(&mut self, rl: &RaylibHandle, target_intent: HotkeyCategory) -> bool {
-        for key in self.hotkeys.get(&target_intent).unwrap().iter() {
-            if rl.is_key_pressed(*key) {
+    pub fn check_pressed(
+        &mut self,
+        input_handler: &InputHandler,
+        target_intent: HotkeyCategory,
+    ) -> bool {
+        let keys = self
+            .hotkeys
+            .get(&target_intent)
+            .map_or(&[][..], |keys| keys.as_slice());
+        for key in keys.iter() {
+            if input_handler.key_pressed(*key) {
                 self.last_pressed_hotkey = Some(*key);
                 return true;
             }
         }
 
-        return false;
+        return self
+            .button_bindings(target_intent)
+            .iter()
+            .any(|button| input_handler.button_pressed(*button));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_buttons_are_unique() {
+        let buttons: Vec<GamepadButton> = DEFAULT_BUTTONS
+            .iter()
+            .flat_map(|(_, buttons)| buttons.iter().copied())
+            .collect();
+        for (i, button) in buttons.iter().enumerate() {
+            assert!(
+                !buttons[i + 1..].contains(button),
+                "{button:?} IS TAKEN TWICE"
+            );
+            // A clicks
+            assert!(*button != GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN);
+        }
+    }
+
+    #[test]
+    fn legacy_file_goes_back_to_defaults() {
+        let mut hotkeys_raw = HotkeyLoaderStruct::default();
+        for (category, names) in LEGACY_HOTKEYS {
+            hotkeys_raw.hotkeys.insert(
+                category,
+                names.iter().map(|name| name.to_string()).collect(),
+            );
+        }
+        // changed by hand back then, that one stays
+        hotkeys_raw
+            .hotkeys
+            .insert(HotkeyCategory::Reset, vec!["KeyQ".to_string()]);
+        hotkeys_raw.drop_legacy();
+
+        let handler = HotkeyHandler::new(hotkeys_raw);
+        assert_eq!(
+            handler.bindings(HotkeyCategory::Skip),
+            HotkeyCategory::Skip.default_keys()
+        );
+        assert!(
+            !handler
+                .bindings(HotkeyCategory::Cancel)
+                .contains(&KeyboardKey::KEY_S)
+        );
+        assert_eq!(
+            handler.bindings(HotkeyCategory::Reset),
+            [KeyboardKey::KEY_Q]
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_skipped() {
+        let mut hotkeys_raw = HotkeyLoaderStruct::default();
+        hotkeys_raw.hotkeys.insert(
+            HotkeyCategory::Hint,
+            vec!["KEY_NOPE".to_string(), "KEY_J".to_string()],
+        );
+        let handler = HotkeyHandler::new(hotkeys_raw);
+        assert_eq!(handler.bindings(HotkeyCategory::Hint), [KeyboardKey::KEY_J]);
+    }
+
+    #[test]
+    fn defaults_dont_conflict() {
+        let handler = HotkeyHandler::new(HotkeyLoaderStruct::default());
+        for category in HotkeyCategory::ALL {
+            assert!(
+                handler.conflicts(category).is_empty(),
+                "{} CONFLICTS",
+                category.name()
+            );
+        }
     }
 }
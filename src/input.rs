@@ -0,0 +1,394 @@
+use raylib::prelude::*;
+use serde::Deserialize;
+use std::{collections::HashSet, fs};
+
+use crate::{
+    SCREEN_HEIGHT, SCREEN_WIDTH, camera,
+    hotkey_handler::{self, BUTTONS, KEYS},
+    scene::Scene,
+    settings::SettingsHandler,
+};
+
+// veles_night --script path/to/script.json, plays the script instead of the keyboard and mouse
+pub const SCRIPT_ARG: &str = "--script";
+
+// the first one plugged in, nobody plays this with two
+const GAMEPAD: i32 = 0;
+
+// the left mouse button is A, the right one has no stand-in, LB cancels instead
+const CLICK_BUTTON: GamepadButton = GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN;
+
+const MOUSE_BUTTONS: [MouseButton; 2] = [
+    MouseButton::MOUSE_BUTTON_LEFT,
+    MouseButton::MOUSE_BUTTON_RIGHT,
+];
+
+// a script plays at a steady 60 fps, so it goes the same way on every machine
+const SCRIPT_FRAME_TIME: f32 = 1. / 60.;
+
+// a step waiting for a scene without saying how long gives up after a minute at 60 fps
+const UNTIL_FRAMES: u32 = 3600;
+
+// what was held this frame, pressed and released come from comparing it with the last one
+#[derive(Clone, Default)]
+struct InputFrame {
+    // in game pixels, outside of the screen when it's over the bars
+    pointer: Vector2,
+    wheel: f32,
+    keys: HashSet<KeyboardKey>,
+    mouse: HashSet<MouseButton>,
+    buttons: HashSet<GamepadButton>,
+    stick: Vector2,
+    typed: String, // characters typed this frame, for the name entry
+}
+
+// one entry of a script, the names are the ones hotkeys.json uses,
+// held for `frames` frames, or with `until` for as long as it takes that scene to come up
+#[derive(Deserialize)]
+struct ScriptStepLoader {
+    frames: Option<u32>,
+    pointer: Option<(f32, f32)>,
+    #[serde(default)]
+    keys: Vec<String>,
+    #[serde(default)]
+    buttons: Vec<String>,
+    #[serde(default)]
+    mouse: Vec<String>,
+    #[serde(default)]
+    wheel: f32,
+    #[serde(default)]
+    text: String, // typed on the first frame of the step
+    until: Option<Scene>,
+}
+
+struct ScriptStep {
+    frames: u32,
+    pointer: Option<Vector2>,
+    held: InputFrame,
+    text: String,
+    until: Option<Scene>,
+}
+
+struct Script {
+    steps: Vec<ScriptStep>,
+    step: usize,
+    // how long the current step has been going
+    frame: u32,
+}
+
+enum InputSource {
+    Devices,
+    Script(Script),
+}
+
+// the only place that asks raylib what the player is doing, everything else reads the frame from here,
+// so a script can stand in for the devices and drive the game from the menu to a win
+pub struct InputHandler {
+    source: InputSource,
+    current: InputFrame,
+    previous: InputFrame,
+    // the pointer was put somewhere on purpose and the mouse hasn't moved since
+    placed: bool,
+    // where the real mouse was last seen, in window pixels
+    window: Vector2,
+    finished: bool,
+    // a scene is reading the keyboard as text, the global hotkeys keep off it
+    capturing: bool,
+}
+
+impl InputHandler {
+    pub fn new() -> Self {
+        Self {
+            source: InputSource::Devices,
+            current: InputFrame::default(),
+            previous: InputFrame::default(),
+            placed: false,
+            window: Vector2::zero(),
+            finished: false,
+            capturing: false,
+        }
+    }
+
+    #[profiling::function]
+    pub fn from_script(path: &str) -> Self {
+        let Ok(script_str) = fs::read_to_string(path) else {
+            panic!("COULDN'T LOAD SCRIPT {path}");
+        };
+        let loaders = match serde_json::from_str::<Vec<ScriptStepLoader>>(&script_str) {
+            Ok(loaders) => loaders,
+            Err(e) => panic!("COULDN'T PARSE SCRIPT {path} - {e}"),
+        };
+
+        let steps = loaders
+            .into_iter()
+            .map(|loader| ScriptStep {
+                frames: loader.frames.unwrap_or(if loader.until.is_some() {
+                    UNTIL_FRAMES
+                } else {
+                    1
+                }),
+                pointer: loader.pointer.map(Vector2::from),
+                held: InputFrame {
+                    pointer: Vector2::zero(),
+                    wheel: loader.wheel,
+                    keys: loader
+                        .keys
+                        .iter()
+                        .map(|name| {
+                            let Some(key) = hotkey_handler::parse_key(name) else {
+                                panic!("UNKNOWN KEY {name} IN SCRIPT");
+                            };
+                            key
+                        })
+                        .collect(),
+                    mouse: loader
+                        .mouse
+                        .iter()
+                        .map(|name| match name.as_str() {
+                            "left" => MouseButton::MOUSE_BUTTON_LEFT,
+                            "right" => MouseButton::MOUSE_BUTTON_RIGHT,
+                            _ => panic!("UNKNOWN MOUSE BUTTON {name} IN SCRIPT"),
+                        })
+                        .collect(),
+                    buttons: loader
+                        .buttons
+                        .iter()
+                        .map(|name| {
+                            let Some(button) = hotkey_handler::parse_button(name) else {
+                                panic!("UNKNOWN GAMEPAD BUTTON {name} IN SCRIPT");
+                            };
+                            button
+                        })
+                        .collect(),
+                    stick: Vector2::zero(),
+                    typed: String::new(),
+                },
+                text: loader.text,
+                until: loader.until,
+            })
+            .collect();
+
+        Self {
+            source: InputSource::Script(Script {
+                steps,
+                step: 0,
+                frame: 0,
+            }),
+            ..Self::new()
+        }
+    }
+
+    // the script ran out, the game closes after it
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    // once a frame before anything reads it, the scene is for steps waiting on one
+    #[profiling::function]
+    pub fn update(
+        &mut self,
+        rl: &mut RaylibHandle,
+        settings_handler: &SettingsHandler,
+        scene: Scene,
+    ) {
+        self.previous = std::mem::take(&mut self.current);
+
+        match &mut self.source {
+            InputSource::Devices => {
+                let window = rl.get_mouse_position();
+                if window.distance_to(self.window) > 1. {
+                    self.window = window;
+                    self.placed = false;
+                }
+
+                self.current = InputFrame {
+                    // the window only has whole pixels, a placed pointer keeps its exact spot
+                    pointer: if self.placed {
+                        self.previous.pointer
+                    } else {
+                        camera::mouse_position(rl, settings_handler)
+                    },
+                    wheel: rl.get_mouse_wheel_move(),
+                    keys: KEYS
+                        .iter()
+                        .copied()
+                        .filter(|key| rl.is_key_down(*key))
+                        .collect(),
+                    mouse: MOUSE_BUTTONS
+                        .iter()
+                        .copied()
+                        .filter(|button| rl.is_mouse_button_down(*button))
+                        .collect(),
+                    buttons: HashSet::new(),
+                    stick: Vector2::zero(),
+                    typed: std::iter::from_fn(|| rl.get_char_pressed()).collect(),
+                };
+
+                if rl.is_gamepad_available(GAMEPAD) {
+                    self.current.buttons = BUTTONS
+                        .iter()
+                        .map(|(button, _)| *button)
+                        .filter(|button| rl.is_gamepad_button_down(GAMEPAD, *button))
+                        .collect();
+                    self.current.stick = Vector2::new(
+                        rl.get_gamepad_axis_movement(GAMEPAD, GamepadAxis::GAMEPAD_AXIS_LEFT_X),
+                        rl.get_gamepad_axis_movement(GAMEPAD, GamepadAxis::GAMEPAD_AXIS_LEFT_Y),
+                    );
+                }
+            }
+            InputSource::Script(script) => {
+                let Some(step) = script.steps.get(script.step) else {
+                    self.finished = true;
+                    self.current.pointer = self.previous.pointer;
+                    return;
+                };
+
+                self.current = step.held.clone();
+                self.current.pointer = self.previous.pointer;
+                if script.frame == 0
+                    && let Some(pointer) = step.pointer
+                {
+                    self.current.pointer = pointer;
+                    self.placed = false;
+                }
+                if script.frame == 0 {
+                    self.current.typed = step.text.clone();
+                }
+
+                script.frame += 1;
+                let done = match step.until {
+                    Some(until) if until == scene => true,
+                    Some(until) if script.frame >= step.frames => {
+                        panic!(
+                            "SCRIPT STEP {} GAVE UP WAITING FOR {until:?}, GOT {scene:?}",
+                            script.step
+                        );
+                    }
+                    Some(_) => false,
+                    None => script.frame >= step.frames,
+                };
+                if done {
+                    script.step += 1;
+                    script.frame = 0;
+                }
+            }
+        }
+    }
+
+    // what everything that moves should step by this frame
+    pub fn frame_time(&self, rl: &RaylibHandle) -> f32 {
+        match self.source {
+            InputSource::Devices => rl.get_frame_time(),
+            InputSource::Script(_) => SCRIPT_FRAME_TIME,
+        }
+    }
+
+    // the pointer in game pixels
+    pub fn pointer(&self) -> Vector2 {
+        self.current.pointer
+    }
+
+    // the pointer was put where it is by the game, not moved there by the mouse
+    pub fn placed(&self) -> bool {
+        self.placed
+    }
+
+    // moves the pointer for everything after this, the real mouse follows it when there is one
+    #[profiling::function]
+    pub fn place(
+        &mut self,
+        rl: &mut RaylibHandle,
+        settings_handler: &SettingsHandler,
+        position: Vector2,
+    ) {
+        self.current.pointer = Vector2::new(
+            position.x.clamp(0., SCREEN_WIDTH as f32 - 1.),
+            position.y.clamp(0., SCREEN_HEIGHT as f32 - 1.),
+        );
+        self.placed = true;
+
+        if let InputSource::Devices = self.source {
+            let viewport = camera::viewport(rl, settings_handler);
+            let window = Vector2::new(viewport.x, viewport.y)
+                + self.current.pointer * viewport.width / SCREEN_WIDTH as f32;
+            self.window = Vector2::new(window.x.floor(), window.y.floor());
+            rl.set_mouse_position(self.window);
+        }
+    }
+
+    pub fn wheel(&self) -> f32 {
+        self.current.wheel
+    }
+
+    pub fn stick(&self) -> Vector2 {
+        self.current.stick
+    }
+
+    pub fn typed(&self) -> &str {
+        &self.current.typed
+    }
+
+    pub fn capturing(&self) -> bool {
+        self.capturing
+    }
+
+    pub fn set_capturing(&mut self, capturing: bool) {
+        self.capturing = capturing;
+    }
+
+    pub fn key_down(&self, key: KeyboardKey) -> bool {
+        self.current.keys.contains(&key)
+    }
+
+    pub fn key_pressed(&self, key: KeyboardKey) -> bool {
+        self.current.keys.contains(&key) && !self.previous.keys.contains(&key)
+    }
+
+    pub fn key_released(&self, key: KeyboardKey) -> bool {
+        !self.current.keys.contains(&key) && self.previous.keys.contains(&key)
+    }
+
+    pub fn button_down(&self, button: GamepadButton) -> bool {
+        self.current.buttons.contains(&button)
+    }
+
+    pub fn button_pressed(&self, button: GamepadButton) -> bool {
+        self.current.buttons.contains(&button) && !self.previous.buttons.contains(&button)
+    }
+
+    pub fn button_released(&self, button: GamepadButton) -> bool {
+        !self.current.buttons.contains(&button) && self.previous.buttons.contains(&button)
+    }
+
+    // the first key pressed this frame, for rebinding
+    pub fn pressed_key(&self) -> Option<KeyboardKey> {
+        KEYS.iter().copied().find(|key| self.key_pressed(*key))
+    }
+
+    pub fn pressed_button(&self) -> Option<GamepadButton> {
+        BUTTONS
+            .iter()
+            .map(|(button, _)| *button)
+            .find(|button| self.button_pressed(*button))
+    }
+
+    // the mouse button or A, so the gamepad can click
+    pub fn pointer_down(&self, button: MouseButton) -> bool {
+        self.current.mouse.contains(&button)
+            || (button == MouseButton::MOUSE_BUTTON_LEFT && self.button_down(CLICK_BUTTON))
+    }
+
+    pub fn pointer_pressed(&self, button: MouseButton) -> bool {
+        (self.current.mouse.contains(&button) && !self.previous.mouse.contains(&button))
+            || (button == MouseButton::MOUSE_BUTTON_LEFT && self.button_pressed(CLICK_BUTTON))
+    }
+
+    pub fn pointer_released(&self, button: MouseButton) -> bool {
+        (!self.current.mouse.contains(&button) && self.previous.mouse.contains(&button))
+            || (button == MouseButton::MOUSE_BUTTON_LEFT && self.button_released(CLICK_BUTTON))
+    }
+
+    pub fn pointer_up(&self, button: MouseButton) -> bool {
+        !self.pointer_down(button)
+    }
+}
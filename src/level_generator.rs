@@ -0,0 +1,357 @@
+use raylib::prelude::*;
+use std::fs;
+
+use crate::{
+    map::{LEVEL_HEIGHT_TILES, LEVEL_WIDTH_TILES, TileType},
+    map_loader::MapLoader,
+    metadata_handler::{EnemyMetadata, MetadataHandler, SpiritMetadata, SwampsMetadata},
+    modifier::Modifier,
+    puzzle::{Action, ActionKind, Puzzle, SolveResult, Solver},
+};
+
+pub const GENERATE_ARG: &str = "--generate";
+
+const ATTEMPTS: usize = 300;
+const NODE_BUDGET: usize = 20_000;
+
+const MAX_EXITS: usize = 2;
+const MAX_SPIRIT_GROUPS: usize = 3;
+const MAX_GROUP_SIZE: usize = 3;
+const MAX_ENEMIES: usize = 2;
+
+pub struct Difficulty {
+    pub min_actions: usize,
+    pub max_actions: usize,
+    pub min_wood: usize, // fires the shortest solution has to build
+    pub fires_available: usize,
+}
+
+impl Difficulty {
+    // roughly what the hand-made levels ask for at that point of the game
+    pub fn for_level(level_number: u8) -> Self {
+        let fires_available = (level_number as usize).min(3);
+        let min_actions = 1 + level_number as usize / 4;
+        Self {
+            min_actions,
+            max_actions: min_actions + 3,
+            min_wood: if fires_available > 0 && level_number >= 3 {
+                1
+            } else {
+                0
+            },
+            fires_available,
+        }
+    }
+}
+
+pub struct GeneratedLevel {
+    pub tiles: [[TileType; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES],
+    pub metadata: MetadataHandler,
+    pub solution: Vec<Action>,
+}
+
+impl GeneratedLevel {
+    pub fn actions(&self) -> usize {
+        self.solution.iter().map(|action| action.kind.cost()).sum()
+    }
+
+    pub fn wood(&self) -> usize {
+        self.solution
+            .iter()
+            .filter(|action| matches!(action.kind, ActionKind::BuildAndLight { .. }))
+            .count()
+    }
+
+    // both files or neither, half a level is worse than none
+    #[profiling::function]
+    pub fn export(&self, level_number: u8) -> Result<(), String> {
+        for path in [
+            MapLoader::export_path(level_number),
+            MetadataHandler::export_path(level_number),
+        ] {
+            if fs::exists(&path).unwrap_or(true) {
+                return Err(format!("{path} already exists, pick another level number"));
+            }
+        }
+
+        MapLoader::export_map(level_number, &self.tiles)?;
+        self.metadata.export(level_number)
+    }
+}
+
+// xorshift, the same seed has to give the same level on every machine
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // zero would get stuck
+        Self(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // min..max, max excluded
+    fn range(&mut self, min: usize, max: usize) -> usize {
+        min + (self.next() % (max - min) as u64) as usize
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.range(0, 100) < percent
+    }
+}
+
+pub struct LevelGenerator {
+    rng: Rng,
+    difficulty: Difficulty,
+}
+
+impl LevelGenerator {
+    pub fn new(seed: u64, difficulty: Difficulty) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            difficulty,
+        }
+    }
+
+    #[profiling::function]
+    pub fn generate(&mut self) -> Option<GeneratedLevel> {
+        for _ in 0..ATTEMPTS {
+            if let Some(level) = self.attempt() {
+                return Some(level);
+            }
+        }
+        None
+    }
+
+    fn attempt(&mut self) -> Option<GeneratedLevel> {
+        let mut tiles =
+            [[TileType::Air { selected: false }; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES];
+
+        let tree_density = self.rng.range(10, 30);
+        for (x, column) in tiles.iter_mut().enumerate() {
+            for (y, tile) in column.iter_mut().enumerate() {
+                let border =
+                    x == 0 || y == 0 || x == LEVEL_WIDTH_TILES - 1 || y == LEVEL_HEIGHT_TILES - 1;
+                if border || self.rng.chance(tree_density) {
+                    *tile = TileType::Tree {
+                        chance: self.rng.range(0, 100) as i32,
+                        selected: false,
+                    };
+                }
+            }
+        }
+
+        for _ in 0..self.rng.range(1, MAX_EXITS + 1) {
+            self.place_exit(&mut tiles);
+        }
+
+        let mut swamps = Vec::new();
+        if self.rng.chance(40) {
+            let first = self.free_cell(&tiles)?;
+            tiles[first.0][first.1] = TileType::Swamp {
+                teleport_position: Vector2::zero(),
+            };
+            let second = self.free_cell(&tiles)?;
+            tiles[first.0][first.1] = TileType::Swamp {
+                teleport_position: Vector2::new(second.0 as f32, second.1 as f32),
+            };
+            tiles[second.0][second.1] = TileType::Swamp {
+                teleport_position: Vector2::new(first.0 as f32, first.1 as f32),
+            };
+            swamps.push(SwampsMetadata {
+                swamp: [first.0 as u8, first.1 as u8],
+                teleport: [second.0 as u8, second.1 as u8],
+            });
+            swamps.push(SwampsMetadata {
+                swamp: [second.0 as u8, second.1 as u8],
+                teleport: [first.0 as u8, first.1 as u8],
+            });
+        }
+
+        let mut spirits = Vec::new();
+        for _ in 0..self.rng.range(1, MAX_SPIRIT_GROUPS + 1) {
+            let position = self.free_cell(&tiles)?;
+            let direction = match self.rng.range(0, 4) {
+                0 => [1, 0],
+                1 => [-1, 0],
+                2 => [0, 1],
+                _ => [0, -1],
+            };
+            spirits.push(SpiritMetadata {
+                position: [position.0 as u8, position.1 as u8],
+                amount: self.rng.range(1, MAX_GROUP_SIZE + 1) as u8,
+                direction,
+            });
+        }
+
+        let mut enemies = Vec::new();
+        for _ in 0..self.rng.range(0, MAX_ENEMIES + 1) {
+            let position = self.free_cell(&tiles)?;
+            if spirits
+                .iter()
+                .any(|spirit| spirit.position == [position.0 as u8, position.1 as u8])
+            {
+                continue;
+            }
+            enemies.push(EnemyMetadata {
+                position: [position.0 as u8, position.1 as u8],
+            });
+        }
+
+        let mut metadata = MetadataHandler {
+            survive: 0,
+            spirits,
+            swamps,
+            enemies,
+            bonfires: Vec::new(),
+            portals: Vec::new(),
+            modifier: Modifier::Standard,
+            safe_border: false,
+        };
+
+        // how many walk out with no help, the level has to ask for more than that
+        let mut idle = Puzzle::new(tiles, &metadata, self.difficulty.fires_available);
+        while !idle.is_finished() && idle.tick < LEVEL_WIDTH_TILES * LEVEL_HEIGHT_TILES {
+            idle.tick();
+        }
+        let total = Puzzle::new(tiles, &metadata, 0).spirits.len();
+
+        for survive in (idle.survived + 1..=total).rev() {
+            metadata.survive = survive;
+            let puzzle = Puzzle::new(tiles, &metadata, self.difficulty.fires_available);
+
+            let mut solver = Solver::new(NODE_BUDGET);
+            let SolveResult::Solved(solution) = solver.solve(&puzzle, self.difficulty.max_actions)
+            else {
+                continue;
+            };
+
+            let level = GeneratedLevel {
+                tiles,
+                metadata,
+                solution,
+            };
+            if level.actions() < self.difficulty.min_actions
+                || level.wood() < self.difficulty.min_wood
+            {
+                return None;
+            }
+            return Some(level);
+        }
+
+        None
+    }
+
+    fn place_exit(&mut self, tiles: &mut [[TileType; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES]) {
+        // same arrows the hand-made maps use: they point back into the level
+        let (x, y, inner, arrow) = match self.rng.range(0, 4) {
+            0 => {
+                let y = self.rng.range(1, LEVEL_HEIGHT_TILES - 1);
+                (0, y, (1, y), '>')
+            }
+            1 => {
+                let y = self.rng.range(1, LEVEL_HEIGHT_TILES - 1);
+                (LEVEL_WIDTH_TILES - 1, y, (LEVEL_WIDTH_TILES - 2, y), '<')
+            }
+            2 => {
+                let x = self.rng.range(1, LEVEL_WIDTH_TILES - 1);
+                (x, 0, (x, 1), 'v')
+            }
+            _ => {
+                let x = self.rng.range(1, LEVEL_WIDTH_TILES - 1);
+                (x, LEVEL_HEIGHT_TILES - 1, (x, LEVEL_HEIGHT_TILES - 2), '^')
+            }
+        };
+        tiles[x][y] = TileType::Exit(arrow);
+        tiles[inner.0][inner.1] = TileType::Air { selected: false };
+    }
+
+    fn free_cell(
+        &mut self,
+        tiles: &[[TileType; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES],
+    ) -> Option<(usize, usize)> {
+        for _ in 0..LEVEL_WIDTH_TILES * LEVEL_HEIGHT_TILES {
+            let x = self.rng.range(1, LEVEL_WIDTH_TILES - 1);
+            let y = self.rng.range(1, LEVEL_HEIGHT_TILES - 1);
+            if let TileType::Air { selected: _ } = tiles[x][y] {
+                return Some((x, y));
+            }
+        }
+        None
+    }
+}
+
+// veles_night --generate <seed> <level number>
+// writes static/maps/<n> and static/metadata/<n>.json for designers to look over
+#[profiling::function]
+pub fn run_from_args(args: &[String]) {
+    if args.len() < 2 {
+        panic!("USAGE: {GENERATE_ARG} <seed> <level number>");
+    }
+    let Ok(seed) = args[0].parse::<u64>() else {
+        panic!("wrong seed arg")
+    };
+    let Ok(level_number) = args[1].parse::<u8>() else {
+        panic!("wrong level number arg")
+    };
+
+    let mut generator = LevelGenerator::new(seed, Difficulty::for_level(level_number));
+    let Some(level) = generator.generate() else {
+        println!("no solvable level for seed {seed}, try another one");
+        return;
+    };
+
+    if let Err(e) = level.export(level_number) {
+        println!("couldn't export level {level_number}: {e}");
+        return;
+    }
+
+    println!(
+        "level {level_number}: survive {}, {} actions, {} wood",
+        level.metadata.survive,
+        level.actions(),
+        level.wood()
+    );
+    for action in level.solution.iter() {
+        println!("  tick {} - {:?}", action.tick, action.kind);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(seed: u64) -> Option<GeneratedLevel> {
+        LevelGenerator::new(seed, Difficulty::for_level(2)).generate()
+    }
+
+    #[test]
+    fn same_seed_same_level() {
+        let (Some(first), Some(second)) = (generate(7), generate(7)) else {
+            panic!("SEED 7 SHOULD GIVE A LEVEL");
+        };
+        assert!(first.tiles == second.tiles);
+        assert_eq!(first.metadata.survive, second.metadata.survive);
+        assert_eq!(
+            format!("{:?}", first.solution),
+            format!("{:?}", second.solution)
+        );
+    }
+
+    #[test]
+    fn generated_level_is_solvable() {
+        let Some(level) = generate(7) else {
+            panic!("SEED 7 SHOULD GIVE A LEVEL");
+        };
+        let puzzle = Puzzle::new(level.tiles, &level.metadata, 2);
+        assert!(matches!(
+            Solver::new(NODE_BUDGET).solve(&puzzle, level.actions()),
+            SolveResult::Solved(_)
+        ));
+    }
+}
@@ -4,31 +4,34 @@ use raylib::prelude::*;
 
 use crate::{
     SCREEN_HEIGHT, SCREEN_WIDTH,
-    dialogue::DialogueHandler,
-    enemy_spirit::EnemiesHandler,
+    app::App,
+    game::Game,
     hotkey_handler::{HotkeyCategory, HotkeyHandler},
-    level_transition::LevelTransition,
-    map::Level,
-    metadata_handler::MetadataHandler,
+    input::InputHandler,
+    locale::Locale,
     save_handler::SaveHandler,
-    scene::{Scene, SceneHandler},
+    scene::{Scene, SceneHandler, SceneState},
+    score::ScoreHandler,
     settings::SettingsHandler,
-    spirits_handler::SpiritsHandler,
     texture_handler::TextureHandler,
-    ui::{UIHandler, get_text_size},
+    ui::get_text_size,
 };
 
 const LEVEL_DIR: &str = "static/maps/";
 const BACK_BUTTON_TEXTURE: &str = "main_menu_buttons";
 
-const MENU_TEXT: &str = "Выбор уровня";
-const BACK_BUTTON_TEXT: &str = "Назад в меню";
+pub const TEXTURES: [&str; 1] = [BACK_BUTTON_TEXTURE];
+
+const MENU_TEXT: &str = "levels.title";
+const BACK_BUTTON_TEXT: &str = "levels.back";
 
 const BUTTON_SIZE: f32 = 16.;
 
 const TEXT_SIZE: f32 = 12.;
 const TEXT_SPACING: f32 = 1.05;
 const MENU_NAME_Y_OFFSET: f32 = 20.;
+const RECORD_TEXT_SIZE: f32 = 8.;
+const RECORD_Y_OFFSET: f32 = 40.;
 
 const BUTTON_X_OFFSET: f32 = BUTTON_SIZE / 4.;
 const BUTTON_Y_OFFSET: f32 = BUTTON_SIZE / 2.;
@@ -52,7 +55,7 @@ pub struct LevelSelector {
 
 impl LevelSelector {
     #[profiling::function]
-    pub fn new(pixel_scale: i32) -> Self {
+    pub fn new() -> Self {
         let dir = fs::read_dir(LEVEL_DIR);
 
         let level_count;
@@ -71,7 +74,7 @@ impl LevelSelector {
         }
 
         let mut back_button = Rectangle::default();
-        Self::set_to_default(&mut buttons, &mut back_button, pixel_scale as f32);
+        Self::set_to_default(&mut buttons, &mut back_button);
 
         Self {
             buttons,
@@ -79,11 +82,7 @@ impl LevelSelector {
         }
     }
 
-    pub fn rescale_ui(&mut self, scale: f32) {
-        Self::set_to_default(&mut self.buttons, &mut self.back_button_rect, scale);
-    }
-
-    fn set_to_default(buttons: &mut Vec<Button>, rect: &mut Rectangle, scale: f32) {
+    fn set_to_default(buttons: &mut [Button], rect: &mut Rectangle) {
         let row_count = ((buttons.len() + COLUMNS_AMOUNT / 2 - 1) as f32 / COLUMNS_AMOUNT as f32)
             .round() as f32;
 
@@ -92,54 +91,40 @@ impl LevelSelector {
         let height_px = (BUTTON_SIZE + BUTTON_Y_OFFSET) * row_count - BUTTON_Y_OFFSET;
 
         for i in 0..buttons.len() {
-            let x = ((SCREEN_WIDTH as f32 - WIDTH_PX) / 2.
-                + (i % COLUMNS_AMOUNT) as f32 * (BUTTON_SIZE + BUTTON_X_OFFSET))
-                * scale;
+            let x = (SCREEN_WIDTH as f32 - WIDTH_PX) / 2.
+                + (i % COLUMNS_AMOUNT) as f32 * (BUTTON_SIZE + BUTTON_X_OFFSET);
 
-            let y = ((SCREEN_HEIGHT as f32 - height_px) / 2.
-                + (i / COLUMNS_AMOUNT) as f32 * (BUTTON_SIZE + BUTTON_Y_OFFSET))
-                * scale;
+            let y = (SCREEN_HEIGHT as f32 - height_px) / 2.
+                + (i / COLUMNS_AMOUNT) as f32 * (BUTTON_SIZE + BUTTON_Y_OFFSET);
 
             buttons[i].rec.x = x;
             buttons[i].rec.y = y;
-            buttons[i].rec.width = BUTTON_SIZE * scale;
-            buttons[i].rec.height = BUTTON_SIZE * scale;
+            buttons[i].rec.width = BUTTON_SIZE;
+            buttons[i].rec.height = BUTTON_SIZE;
 
-            rect.x = SCREEN_WIDTH as f32 / 2. * scale - BACK_BUTTON_SIZE_WIDTH / 2. * scale as f32;
-            rect.y = SCREEN_HEIGHT as f32 * scale - BACK_BUTTON_Y_OFFSET * scale as f32;
-            rect.width = BACK_BUTTON_SIZE_WIDTH * scale as f32;
-            rect.height = BACK_BUTTON_SIZE_HEIGHT * scale as f32;
+            rect.x = SCREEN_WIDTH as f32 / 2. - BACK_BUTTON_SIZE_WIDTH / 2.;
+            rect.y = SCREEN_HEIGHT as f32 - BACK_BUTTON_Y_OFFSET;
+            rect.width = BACK_BUTTON_SIZE_WIDTH;
+            rect.height = BACK_BUTTON_SIZE_HEIGHT;
         }
     }
 
     #[profiling::function]
     pub fn update(
         &self,
-        level_number: &mut u8,
-        metadata_handler: &mut MetadataHandler,
-        level: &mut Level,
-        spirits_handler: &mut SpiritsHandler,
-        enemies_handler: &mut EnemiesHandler,
-        ui_handler: &mut UIHandler,
-        level_transition: &mut LevelTransition,
+        game: &mut Game,
         scene_handler: &mut SceneHandler,
-        dialogue_handler: &mut DialogueHandler,
         rl: &mut RaylibHandle,
+        input_handler: &InputHandler,
         settings_handler: &mut SettingsHandler,
         hotkey_handler: &mut HotkeyHandler,
     ) {
-        let mouse_pos = rl.get_mouse_position()
-            - Vector2::new(
-                rl.get_screen_width() as f32 / 2.
-                    - SCREEN_WIDTH as f32 / 2. * settings_handler.settings.pixel_scale as f32,
-                rl.get_screen_height() as f32 / 2.
-                    - SCREEN_HEIGHT as f32 / 2. * settings_handler.settings.pixel_scale as f32,
-            );
+        let mouse_pos = input_handler.pointer();
 
-        if hotkey_handler.check_pressed(rl, HotkeyCategory::PickButton1)
-            || hotkey_handler.check_pressed(rl, HotkeyCategory::Exit)
+        if hotkey_handler.check_pressed(input_handler, HotkeyCategory::PickButton1)
+            || hotkey_handler.check_pressed(input_handler, HotkeyCategory::Exit)
             || (self.back_button_rect.check_collision_point_rec(mouse_pos)
-                && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT))
+                && hotkey_handler.check_click(input_handler))
         {
             scene_handler.set(Scene::MainMenu);
         }
@@ -150,16 +135,9 @@ impl LevelSelector {
             }
 
             if self.buttons[i].rec.check_collision_point_rec(mouse_pos) {
-                if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
-                    *level_number = i as u8;
-                    metadata_handler.load(*level_number);
-                    level.load(*level_number, metadata_handler, rl);
-                    spirits_handler.spawn_spirits(metadata_handler, settings_handler);
-                    enemies_handler.spawn_enemies(metadata_handler, settings_handler);
-                    *ui_handler = UIHandler::new(i, settings_handler.settings.pixel_scale as f32);
-                    *level_transition = LevelTransition::new();
+                if hotkey_handler.check_click(input_handler) {
+                    game.start_level(i as u8, rl, settings_handler);
                     scene_handler.set(Scene::Level);
-                    dialogue_handler.load_dialogue(&format!("level_{}", *level_number + 1));
                 }
             }
         }
@@ -169,46 +147,44 @@ impl LevelSelector {
     pub fn draw(
         &mut self,
         font: &Font,
+        locale: &Locale,
         texture_handler: &TextureHandler,
+        score_handler: &ScoreHandler,
         rl: &mut RaylibDrawHandle,
-        settings_handler: &mut SettingsHandler,
+        input_handler: &InputHandler,
     ) {
         rl.clear_background(Color::from_hex("0b5e65").unwrap());
 
-        let text_dimensions = get_text_size(
-            font,
-            MENU_TEXT,
-            TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-            TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-        );
+        let menu_text = locale.get(MENU_TEXT);
+        let text_dimensions = get_text_size(font, menu_text, TEXT_SIZE, TEXT_SPACING);
 
         rl.draw_text_pro(
             font,
-            MENU_TEXT,
+            menu_text,
             Vector2::new(
-                (SCREEN_WIDTH as f32 * settings_handler.settings.pixel_scale as f32
-                    - text_dimensions.x)
-                    / 2.,
-                MENU_NAME_Y_OFFSET * settings_handler.settings.pixel_scale as f32,
+                (SCREEN_WIDTH as f32 - text_dimensions.x) / 2.,
+                MENU_NAME_Y_OFFSET,
             ),
             Vector2::zero(),
             0.0,
-            TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-            TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
+            TEXT_SIZE,
+            TEXT_SPACING,
             Color::RAYWHITE,
         );
 
-        let mouse_pos = rl.get_mouse_position()
-            - Vector2::new(
-                rl.get_screen_width() as f32 / 2.
-                    - SCREEN_WIDTH as f32 / 2. * settings_handler.settings.pixel_scale as f32,
-                rl.get_screen_height() as f32 / 2.
-                    - SCREEN_HEIGHT as f32 / 2. * settings_handler.settings.pixel_scale as f32,
-            );
+        let mouse_pos = input_handler.pointer();
+
+        let mut hovered = None;
 
         for i in 0..self.buttons.len() {
             let button = &mut self.buttons[i];
 
+            if button.rec.check_collision_point_rec(mouse_pos)
+                && i <= SaveHandler::get_level_number().into()
+            {
+                hovered = Some(i);
+            }
+
             let offset = if button.rec.check_collision_point_rec(mouse_pos)
                 && i <= SaveHandler::get_level_number().into()
             {
@@ -257,12 +233,8 @@ impl LevelSelector {
                 color,
             );
 
-            let level_number_dimensions = get_text_size(
-                font,
-                format!("{}", i + 1).as_str(),
-                TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-            );
+            let level_number_dimensions =
+                get_text_size(font, format!("{}", i + 1).as_str(), TEXT_SIZE, TEXT_SPACING);
 
             rl.draw_text_pro(
                 font,
@@ -274,39 +246,57 @@ impl LevelSelector {
                 ),
                 Vector2::zero(),
                 0.0,
-                TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
+                TEXT_SIZE,
+                TEXT_SPACING,
                 Color::WHITE,
             );
         }
 
+        if let Some(i) = hovered {
+            let record = match score_handler.board(i as u8).first() {
+                Some(best) => locale.format(
+                    "levels.best",
+                    &[&best.score, &best.player, &best.modifier.tag(locale)],
+                ),
+                None => locale.get("levels.no_best").to_string(),
+            };
+            let record_dimensions = get_text_size(font, &record, RECORD_TEXT_SIZE, TEXT_SPACING);
+
+            rl.draw_text_ex(
+                font,
+                &record,
+                Vector2::new(
+                    (SCREEN_WIDTH as f32 - record_dimensions.x) / 2.,
+                    RECORD_Y_OFFSET,
+                ),
+                RECORD_TEXT_SIZE,
+                TEXT_SPACING,
+                Color::RAYWHITE,
+            );
+        }
+
         let (offset, text_offset) = if self.back_button_rect.check_collision_point_rec(mouse_pos)
-            && rl.is_mouse_button_up(MouseButton::MOUSE_BUTTON_LEFT)
+            && input_handler.pointer_up(MouseButton::MOUSE_BUTTON_LEFT)
         {
-            (0., settings_handler.settings.pixel_scale as f32)
+            (0., 1.)
         } else {
             (BACK_BUTTON_SIZE_HEIGHT, 0.)
         };
 
-        rl.draw_texture_pro(
-            texture_handler.get(BACK_BUTTON_TEXTURE),
+        texture_handler.draw_pro(
+            rl,
+            BACK_BUTTON_TEXTURE,
             Rectangle::new(0.0, offset, BACK_BUTTON_SIZE_WIDTH, BACK_BUTTON_SIZE_HEIGHT),
             self.back_button_rect,
-            Vector2::zero(),
-            0.0,
             Color::WHITE,
         );
 
-        let text_dimensions = get_text_size(
-            font,
-            BACK_BUTTON_TEXT,
-            TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-            TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-        );
+        let back_text = locale.get(BACK_BUTTON_TEXT);
+        let text_dimensions = get_text_size(font, back_text, TEXT_SIZE, TEXT_SPACING);
 
         rl.draw_text_pro(
             font,
-            BACK_BUTTON_TEXT,
+            back_text,
             Vector2::new(
                 self.back_button_rect.x + (self.back_button_rect.width - text_dimensions.x) / 2.,
                 self.back_button_rect.y + (self.back_button_rect.height - text_dimensions.y) / 2.
@@ -314,9 +304,47 @@ impl LevelSelector {
             ),
             Vector2::zero(),
             0.0,
-            TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-            TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
+            TEXT_SIZE,
+            TEXT_SPACING,
             Color::RAYWHITE,
         );
     }
 }
+
+impl SceneState for LevelSelector {
+    fn update(&mut self, app: &mut App, rl: &mut RaylibHandle) {
+        // only the levels that are open, and back under them
+        let open = SaveHandler::get_level_number() as usize + 1;
+        let targets: Vec<Rectangle> = self
+            .buttons
+            .iter()
+            .take(open)
+            .map(|button| button.rec)
+            .chain([self.back_button_rect])
+            .collect();
+        app.gamepad_handler
+            .navigate(rl, &mut app.input_handler, &app.settings_handler, &targets);
+
+        LevelSelector::update(
+            self,
+            &mut app.game,
+            &mut app.scene_handler,
+            rl,
+            &app.input_handler,
+            &mut app.settings_handler,
+            &mut app.hotkey_handler,
+        );
+    }
+
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        LevelSelector::draw(
+            self,
+            &app.font,
+            &app.locale,
+            &app.texture_handler,
+            &app.game.score_handler,
+            rl,
+            &app.input_handler,
+        );
+    }
+}
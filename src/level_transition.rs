@@ -4,26 +4,45 @@ use raylib::prelude::*;
 use serde::Deserialize;
 
 use crate::{
-    SCREEN_HEIGHT, SCREEN_WIDTH, map::TILE_SIZE_PX, map_loader::MAP_PATH,
-    settings::SettingsHandler, texture_handler::TextureHandler,
+    SCREEN_HEIGHT, SCREEN_WIDTH,
+    animation::AnimationPlayer,
+    app::App,
+    game::Subsystem,
+    hotkey_handler::HotkeyCategory,
+    locale::Locale,
+    map::TILE_SIZE_PX,
+    map_loader::MAP_PATH,
+    scene::{Scene, SceneState},
+    score::ScoreHandler,
+    text_layout::{Align, TextBox},
+    texture_handler::TextureHandler,
+    ui::get_text_size,
 };
-
 const CARD_SIZE_DEFAULT: f32 = 64.;
+const CARD_TEXT_SIZE: f32 = 6.;
+const CARD_TEXT_MARGIN: f32 = 4.;
+
+const SCORE_TEXT_SIZE: f32 = 8.;
+const BOARD_TEXT_SIZE: f32 = 6.;
+const SCORE_TEXT_Y: f32 = 6.;
+
+const MAX_NAME_CHARS: usize = 16;
+const NAME_TEXT_SIZE: f32 = 8.;
 
 pub enum CardContentType {
-    Image(String),
+    Image(AnimationPlayer),
     Text(String),
 }
 
 pub struct TransitionCard {
-    pub stage: usize,
+    flip: AnimationPlayer,
     pub content: CardContentType,
 }
 
 impl TransitionCard {
     fn new(content: CardContentType) -> Self {
         Self {
-            stage: 0,
+            flip: AnimationPlayer::new("card"),
             content: content,
         }
     }
@@ -62,7 +81,7 @@ impl LevelTransition {
         let mut new_transition = Self {
             unlock_wrapper,
             cards: [
-                TransitionCard::new(CardContentType::Image("".to_string())),
+                TransitionCard::new(CardContentType::Text("".to_string())),
                 TransitionCard::new(CardContentType::Text("".to_string())),
                 TransitionCard::new(CardContentType::Text("".to_string())),
             ],
@@ -79,161 +98,330 @@ impl LevelTransition {
 
         new_transition
     }
-    fn reset_stage(&mut self) {
-        for i in self.cards.iter_mut() {
-            i.stage = 0;
-        }
+
+    // unlock pictures are animation sheets
+    pub fn sheets(&self) -> Vec<String> {
+        self.unlock_wrapper
+            .unlocks
+            .iter()
+            .map(|unlock| unlock.texture.clone())
+            .collect()
     }
 
+    // new cards start face down again
     #[profiling::function]
     pub fn set_cards(&mut self, level_completed: usize) {
-        self.reset_stage();
-        self.cards[0].content =
-            CardContentType::Image(self.unlock_wrapper.unlocks[level_completed].texture.clone());
-        self.cards[1].content =
-            CardContentType::Text(self.unlock_wrapper.unlocks[level_completed].name.clone());
-        self.cards[2].content = CardContentType::Text(
-            self.unlock_wrapper.unlocks[level_completed]
-                .description
-                .clone(),
-        );
+        let unlock = &self.unlock_wrapper.unlocks[level_completed];
+        self.cards = [
+            TransitionCard::new(CardContentType::Image(AnimationPlayer::new(
+                &unlock.texture,
+            ))),
+            TransitionCard::new(CardContentType::Text(unlock.name.clone())),
+            TransitionCard::new(CardContentType::Text(unlock.description.clone())),
+        ];
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        for card in self.cards.iter_mut() {
+            card.flip.update(delta);
+            if let CardContentType::Image(animation) = &mut card.content {
+                animation.update(delta);
+            }
+        }
     }
 
     #[profiling::function]
     pub fn draw(
         &mut self,
         texture_handler: &TextureHandler,
+        score_handler: &ScoreHandler,
+        level_number: u8,
+        locale: &Locale,
         font: &Font,
         rl: &mut RaylibDrawHandle,
-        settings_handler: &SettingsHandler,
     ) {
         rl.clear_background(Color::BROWN);
 
         let cards = [
             Rectangle::new(
-                ((SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32 / 2.
-                    - CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32 / 2.
-                    - CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32
-                    - 20. * settings_handler.settings.pixel_scale as f32) as f32,
-                ((SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32 / 2.
-                    - CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32 / 2.)
-                    as f32,
-                CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32,
-                CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32,
+                SCREEN_WIDTH as f32 / 2. - CARD_SIZE_DEFAULT / 2. - CARD_SIZE_DEFAULT - 20.,
+                SCREEN_HEIGHT as f32 / 2. - CARD_SIZE_DEFAULT / 2.,
+                CARD_SIZE_DEFAULT,
+                CARD_SIZE_DEFAULT,
             ),
             Rectangle::new(
-                ((SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32 / 2.
-                    - CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32 / 2.)
-                    as f32,
-                ((SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32 / 2.
-                    - CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32 / 2.)
-                    as f32,
-                CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32,
-                CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32,
+                SCREEN_WIDTH as f32 / 2. - CARD_SIZE_DEFAULT / 2.,
+                SCREEN_HEIGHT as f32 / 2. - CARD_SIZE_DEFAULT / 2.,
+                CARD_SIZE_DEFAULT,
+                CARD_SIZE_DEFAULT,
             ),
             Rectangle::new(
-                ((SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32 / 2.
-                    - CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32 / 2.
-                    + CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32
-                    + 20. * settings_handler.settings.pixel_scale as f32) as f32,
-                ((SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32 / 2.
-                    - CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32 / 2.)
-                    as f32,
-                CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32,
-                CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32,
+                SCREEN_WIDTH as f32 / 2. - CARD_SIZE_DEFAULT / 2. + CARD_SIZE_DEFAULT + 20.,
+                SCREEN_HEIGHT as f32 / 2. - CARD_SIZE_DEFAULT / 2.,
+                CARD_SIZE_DEFAULT,
+                CARD_SIZE_DEFAULT,
             ),
         ];
 
         for i in 0..3 {
-            let offset;
-            if self.cards[i].stage >= 5 {
-                offset = 64. * 5.;
-            } else {
-                offset = self.cards[i].stage as f32 * 64.;
-                self.cards[i].stage = ((rl.get_time() * 8.) % 6.).floor() as usize; //wtf this shit
-                //is
-                //how it works
-                //if i understand it correctly, this is pitfall where level transition cards are
-                //buged
-            };
-
-            rl.draw_texture_pro(
-                texture_handler.get_safe("card"),
-                Rectangle::new(offset, 0.0, 64., 64.),
-                cards[i],
-                Vector2::zero(),
-                0.0,
-                Color::WHITE,
-            );
+            texture_handler.draw_animation(rl, &self.cards[i].flip, cards[i], Color::WHITE);
 
-            if self.cards[i].stage < 5 {
+            if !texture_handler.animations().finished(&self.cards[i].flip) {
                 continue;
             }
 
             match &self.cards[i].content {
-                CardContentType::Image(img) => rl.draw_texture_pro(
-                    texture_handler.get_safe(img.as_str()),
+                CardContentType::Image(animation) => texture_handler.draw_animation(
+                    rl,
+                    animation,
                     Rectangle::new(
-                        ((rl.get_time() * 8.) % 4.).floor() as f32 * TILE_SIZE_PX as f32,
+                        cards[i].x + CARD_SIZE_DEFAULT / 2. - (TILE_SIZE_PX / 2) as f32,
+                        cards[i].y + CARD_SIZE_DEFAULT / 2. - (TILE_SIZE_PX / 2) as f32,
                         TILE_SIZE_PX as f32,
                         TILE_SIZE_PX as f32,
-                        TILE_SIZE_PX as f32,
-                    ),
-                    Rectangle::new(
-                        cards[i].x
-                            + CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32 / 2.
-                            - (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32 / 2)
-                                as f32,
-                        cards[i].y
-                            + CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32 / 2.
-                            - (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32 / 2)
-                                as f32,
-                        (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32,
-                        (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32,
                     ),
-                    Vector2::zero(),
-                    0.0,
                     Color::WHITE,
                 ),
-                CardContentType::Text(text) => {
-                    let line_count = text.chars().filter(|&c| c == '\n').count() as f32;
-
-                    rl.draw_text_pro(
+                CardContentType::Text(key) => {
+                    let text_box = TextBox {
+                        width: CARD_SIZE_DEFAULT - CARD_TEXT_MARGIN * 2.,
+                        lines_per_page: 0,
+                        align: Align::Center,
+                        size: CARD_TEXT_SIZE,
+                        spacing: 0.,
+                        color: Color::BLACK,
+                    };
+                    let layout = text_box.layout(font, locale.get(key));
+                    layout.draw(
+                        rl,
                         font,
-                        text.as_str(),
+                        0,
                         Vector2::new(
-                            cards[i].x + 8. * settings_handler.settings.pixel_scale as f32,
-                            cards[i].y
-                                + CARD_SIZE_DEFAULT * settings_handler.settings.pixel_scale as f32
-                                    / 2.
-                                - (line_count + 1.)
-                                    * 3.
-                                    * settings_handler.settings.pixel_scale as f32,
+                            cards[i].x + CARD_TEXT_MARGIN,
+                            cards[i].y + (CARD_SIZE_DEFAULT - layout.height(0)) / 2.,
                         ),
-                        Vector2::zero(),
-                        0.0,
-                        6. * settings_handler.settings.pixel_scale as f32,
-                        0.0,
-                        Color::BLACK,
-                    )
+                        usize::MAX,
+                    );
                 }
             }
         }
 
-        let text = "Нажмите для продолжения";
-        rl.draw_text_ex(
-            &font,
-            text,
-            Vector2::new(
-                ((SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) / 2
-                    - text.chars().count() as i32 * 3 / 2
-                        * settings_handler.settings.pixel_scale as i32) as f32,
-                ((SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32)
-                    - 24 * settings_handler.settings.pixel_scale as i32) as f32,
-            ),
-            8. * settings_handler.settings.pixel_scale as f32,
-            0.,
-            Color::RAYWHITE.alpha((rl.get_time() * 2.).sin().abs() as f32),
+        self.draw_scores(score_handler, level_number, locale, font, rl);
+    }
+
+    // the run that was just finished and the top of the level's leaderboard above the cards
+    fn draw_scores(
+        &self,
+        score_handler: &ScoreHandler,
+        level_number: u8,
+        locale: &Locale,
+        font: &Font,
+        rl: &mut RaylibDrawHandle,
+    ) {
+        let Some(last) = &score_handler.last else {
+            return;
+        };
+
+        let mut lines = vec![(
+            match score_handler.last_place {
+                Some(place) => {
+                    locale.format("transition.score_place", &[&last.score, &(place + 1)])
+                }
+                None => locale.format("transition.score", &[&last.score]),
+            },
+            SCORE_TEXT_SIZE,
+        )];
+        for (i, entry) in score_handler.board(level_number).iter().enumerate() {
+            lines.push((
+                format!(
+                    "{}. {} {} {}{}",
+                    i + 1,
+                    entry.player,
+                    entry.score,
+                    entry.date,
+                    entry.modifier.tag(locale)
+                ),
+                BOARD_TEXT_SIZE,
+            ));
+        }
+
+        let mut y = SCORE_TEXT_Y;
+        for (line, size) in lines.iter() {
+            let text_size = get_text_size(font, line, *size, 0.);
+            rl.draw_text_ex(
+                font,
+                line,
+                Vector2::new((SCREEN_WIDTH as f32 - text_size.x) / 2., y),
+                *size,
+                0.,
+                Color::RAYWHITE,
+            );
+            y += size + 1.;
+        }
+    }
+}
+
+impl Subsystem for LevelTransition {
+    fn level_started(&mut self, level_number: u8) {
+        self.set_cards(level_number as usize);
+    }
+}
+
+// the cards between two levels, the next one starts from here
+pub struct TransitionScene {
+    naming: Option<String>, // the name being typed in, the first time a run makes the board
+}
+
+impl TransitionScene {
+    pub fn new() -> Self {
+        Self { naming: None }
+    }
+
+    // under the cards, where it otherwise says to press something
+    fn draw_bottom(&self, locale: &Locale, font: &Font, rl: &mut RaylibDrawHandle) {
+        if let Some(name) = &self.naming {
+            let cursor = if (rl.get_time() * 2.).sin() > 0. {
+                "_"
+            } else {
+                " "
+            };
+            let text_box = TextBox {
+                width: SCREEN_WIDTH as f32,
+                lines_per_page: 0,
+                align: Align::Center,
+                size: NAME_TEXT_SIZE,
+                spacing: 0.,
+                color: Color::RAYWHITE,
+            };
+            text_box
+                .layout(
+                    font,
+                    &format!(
+                        "{}\n{}{cursor}",
+                        locale.get("transition.name_prompt"),
+                        name.replace('*', "**")
+                    ),
+                )
+                .draw(
+                    rl,
+                    font,
+                    0,
+                    Vector2::new(0., (SCREEN_HEIGHT - 32) as f32),
+                    usize::MAX,
+                );
+            return;
+        }
+
+        let text_box = TextBox {
+            width: SCREEN_WIDTH as f32,
+            lines_per_page: 0,
+            align: Align::Center,
+            size: 8.,
+            spacing: 0.,
+            color: Color::RAYWHITE.alpha((rl.get_time() * 2.).sin().abs() as f32),
+        };
+        text_box.layout(font, locale.get("press_to_continue")).draw(
+            rl,
+            font,
+            0,
+            Vector2::new(0., (SCREEN_HEIGHT - 24) as f32),
+            usize::MAX,
+        );
+    }
+
+    // enter or a click takes it, nothing typed in gets the stand-in from the locale
+    fn update_naming(&mut self, app: &mut App) {
+        let Some(name) = &mut self.naming else {
+            return;
+        };
+
+        for c in app.input_handler.typed().chars() {
+            if !c.is_control() && name.chars().count() < MAX_NAME_CHARS {
+                name.push(c);
+            }
+        }
+        if app.input_handler.key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            name.pop();
+        }
+
+        if !app.input_handler.key_pressed(KeyboardKey::KEY_ENTER)
+            && !app
+                .input_handler
+                .pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+        {
+            return;
+        }
+
+        let name = match name.trim() {
+            "" => app.locale.get("player.default_name").to_string(),
+            name => name.to_string(),
+        };
+        app.settings_handler.settings.player_name = name.clone();
+        app.settings_handler.save();
+        app.game
+            .score_handler
+            .rename_last(app.game.level_number, &name);
+        self.naming = None;
+        app.input_handler.set_capturing(false);
+    }
+}
+
+impl SceneState for TransitionScene {
+    fn enter(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        self.naming = (app.settings_handler.settings.player_name.is_empty()
+            && app.game.score_handler.last_place.is_some())
+        .then(String::new);
+        app.input_handler.set_capturing(self.naming.is_some());
+    }
+
+    fn exit(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        app.input_handler.set_capturing(false);
+    }
+
+    #[profiling::function]
+    fn update(&mut self, app: &mut App, rl: &mut RaylibHandle) {
+        app.game
+            .level_transition
+            .update(app.input_handler.frame_time(rl));
+
+        // the same enter that takes the name shouldn't skip the cards too
+        if self.naming.is_some() {
+            self.update_naming(app);
+            return;
+        }
+
+        if !app
+            .hotkey_handler
+            .check_pressed(&app.input_handler, HotkeyCategory::Continue)
+            && !app
+                .input_handler
+                .pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+        {
+            return;
+        }
+
+        let game = &mut app.game;
+        let next_level = game.level_number + 1;
+        if next_level >= game.level_transition.max_level {
+            game.level_number = next_level;
+            app.scene_handler.set(Scene::GameEnd);
+            return;
+        }
+
+        game.start_level(next_level, rl, &app.settings_handler);
+        app.scene_handler.set(Scene::Level);
+    }
+
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        let game = &mut app.game;
+        game.level_transition.draw(
+            &app.texture_handler,
+            &game.score_handler,
+            game.level_number,
+            &app.locale,
+            &app.font,
+            rl,
         );
+        self.draw_bottom(&app.locale, &app.font, rl);
     }
 }
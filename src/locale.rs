@@ -0,0 +1,135 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    ops::RangeInclusive,
+};
+
+use crate::dialogue::DIALOGUES_FILE;
+
+const LOCALES_PATH: &str = "static/locales/";
+const STRINGS_FILE: &str = "strings.json";
+// every table names its own language under this key, that's what the settings show
+const LANGUAGE_NAME_KEY: &str = "language";
+
+pub const DEFAULT_LANGUAGE: &str = "ru";
+
+// printable ascii is always there, scores, dates and player names need it whatever the language
+const BASE_GLYPHS: RangeInclusive<char> = ' '..='~';
+
+// one folder per language, with a string table and whatever else gets translated whole
+pub struct Locale {
+    language: String,
+    strings: HashMap<String, String>,
+    // the default language, for keys a translation hasn't got to yet
+    fallback: HashMap<String, String>,
+}
+
+impl Locale {
+    #[profiling::function]
+    pub fn new(language: &str) -> Self {
+        let fallback = Self::load_strings(DEFAULT_LANGUAGE)
+            .unwrap_or_else(|| panic!("COULDN'T LOAD STRINGS FOR {DEFAULT_LANGUAGE}"));
+
+        // settings can name a language that isn't shipped anymore, it's kept as the one asked for
+        // and everything comes from the fallback
+        let strings = Self::load_strings(language).unwrap_or_default();
+
+        Self {
+            language: language.to_string(),
+            strings,
+            fallback,
+        }
+    }
+
+    fn load_strings(language: &str) -> Option<HashMap<String, String>> {
+        let string_json = std::fs::read_to_string(file_path(language, STRINGS_FILE)).ok()?;
+        let Ok(strings) = serde_json::from_str(&string_json) else {
+            panic!("COULDN'T PARSE STRINGS FOR {language}");
+        };
+        Some(strings)
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    // a key nobody translated shows up as itself, that's easier to spot than an empty line
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(key, |s| s.as_str())
+    }
+
+    // fills the {} in the string one by one
+    pub fn format(&self, key: &str, args: &[&dyn Display]) -> String {
+        let mut parts = self.get(key).split("{}");
+        let mut result = parts.next().unwrap_or_default().to_string();
+        for (i, part) in parts.enumerate() {
+            if let Some(arg) = args.get(i) {
+                result += &arg.to_string();
+            }
+            result += part;
+        }
+        result
+    }
+
+    // every character the font needs for this language: both string tables, the dialogues
+    // and the names in the language picker, extra is for whatever the player typed in
+    #[profiling::function]
+    pub fn glyphs(&self, extra: &[&str]) -> String {
+        let mut glyphs: BTreeSet<char> = BASE_GLYPHS.collect();
+
+        let dialogues: Vec<String> = [self.language.as_str(), DEFAULT_LANGUAGE]
+            .iter()
+            .filter_map(|language| {
+                std::fs::read_to_string(file_path(language, DIALOGUES_FILE)).ok()
+            })
+            .collect();
+        let names: Vec<String> = languages().into_iter().map(|(_, name)| name).collect();
+
+        for text in self
+            .strings
+            .values()
+            .chain(self.fallback.values())
+            .chain(dialogues.iter())
+            .chain(names.iter())
+        {
+            glyphs.extend(text.chars());
+        }
+        for text in extra {
+            glyphs.extend(text.chars());
+        }
+
+        glyphs.retain(|c| !c.is_control());
+        glyphs.into_iter().collect()
+    }
+}
+
+pub fn file_path(language: &str, file: &str) -> String {
+    format!("{LOCALES_PATH}{language}/{file}")
+}
+
+// (code, name in that language), the default one first
+#[profiling::function]
+pub fn languages() -> Vec<(String, String)> {
+    let Ok(folders) = std::fs::read_dir(LOCALES_PATH) else {
+        panic!("COULDN'T READ {LOCALES_PATH}");
+    };
+
+    let mut languages: Vec<(String, String)> = folders
+        .flatten()
+        .filter_map(|folder| {
+            let code = folder.file_name().into_string().ok()?;
+            let strings = Locale::load_strings(&code)?;
+            let name = strings
+                .get(LANGUAGE_NAME_KEY)
+                .cloned()
+                .unwrap_or(code.clone());
+            Some((code, name))
+        })
+        .collect();
+
+    languages.sort_by_key(|(code, _)| (code != DEFAULT_LANGUAGE, code.clone()));
+    return languages;
+}
@@ -3,18 +3,17 @@ use std::collections::HashMap;
 
 use crate::{
     FIRST_LEVEL, SCREEN_HEIGHT, SCREEN_WIDTH,
-    enemy_spirit::EnemiesHandler,
+    app::App,
+    daily::DailyHandler,
+    game::Game,
     hotkey_handler::{HotkeyCategory, HotkeyHandler},
-    level_transition::LevelTransition,
-    map::Level,
-    metadata_handler::MetadataHandler,
+    input::InputHandler,
+    locale::Locale,
     save_handler::SaveHandler,
-    scene::{Scene, SceneHandler},
+    scene::{Scene, SceneHandler, SceneState},
     settings::SettingsHandler,
-    settings_menu::SettingsMenuHandler,
-    spirits_handler::SpiritsHandler,
     texture_handler::TextureHandler,
-    ui::{Button, UIHandler, get_text_size},
+    ui::{Button, get_text_size},
 };
 
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
@@ -27,29 +26,40 @@ pub struct MainMenuHandler {
 const BUTTON_TEXTURE_WIDTH: f32 = 64.;
 const BUTTON_TEXTURE_HEIGHT: f32 = 16.;
 const BUTTON_OFFSET_Y: f32 = 16.;
+const BUTTON_START_Y: f32 = 80.; // right under the logo, six buttons don't fit from the middle
 const LOGO_WIDTH: f32 = 96.;
 const LOGO_HEIGHT: f32 = 64.;
 
 const BACKGROUND_IMAGE_NAME: &str = "main_menu_bg";
 const LOGO_NAME: &str = "logo";
+const BUTTONS_TEXTURE: &str = "main_menu_buttons";
 
-const BUTTON_LABELS: [&str; 5] = ["Продолжить", "Начать", "Настройки", "Выйти", "Уровни"];
+pub const TEXTURES: [&str; 3] = [BACKGROUND_IMAGE_NAME, LOGO_NAME, BUTTONS_TEXTURE];
+
+const BUTTON_LABELS: [&str; 7] = [
+    "menu.continue",
+    "menu.start",
+    "menu.settings",
+    "menu.daily",
+    "menu.achievements",
+    "menu.exit",
+    "menu.levels",
+];
 
 impl MainMenuHandler {
     #[profiling::function]
-    pub fn new(scale: f32) -> Self {
+    pub fn new() -> Self {
         let mut buttons = HashMap::new();
-        for i in 0..4 {
+        for i in 0..BUTTON_LABELS.len() as u8 - 1 {
             buttons.insert(
                 i,
                 Button {
                     selected: false,
                     rect: Rectangle::new(
-                        (SCREEN_WIDTH as f32 - BUTTON_TEXTURE_WIDTH) / 2. * scale,
-                        ((SCREEN_HEIGHT * scale as i32) / 2) as f32
-                            + BUTTON_OFFSET_Y * (i as f32 * scale) as f32,
-                        BUTTON_TEXTURE_WIDTH * scale,
-                        BUTTON_TEXTURE_HEIGHT * scale,
+                        (SCREEN_WIDTH as f32 - BUTTON_TEXTURE_WIDTH) / 2.,
+                        BUTTON_START_Y + BUTTON_OFFSET_Y * i as f32,
+                        BUTTON_TEXTURE_WIDTH,
+                        BUTTON_TEXTURE_HEIGHT,
                     ),
                     offset: 0.,
                 },
@@ -61,49 +71,27 @@ impl MainMenuHandler {
 
         Self { buttons, labels }
     }
-    pub fn rescale_ui(&mut self, new_scale: f32) {
-        for i in 0..BUTTON_LABELS.len() - 1 {
-            let button = self.buttons.get_mut(&(i as u8)).unwrap();
-            button.rect.x = (SCREEN_WIDTH as f32 - BUTTON_TEXTURE_WIDTH) / 2. * new_scale;
-            button.rect.y = ((SCREEN_HEIGHT * new_scale as i32) / 2) as f32
-                + BUTTON_OFFSET_Y * (i as f32 * new_scale) as f32;
-            button.rect.width = BUTTON_TEXTURE_WIDTH * new_scale;
-            button.rect.height = BUTTON_TEXTURE_HEIGHT * new_scale;
-        }
-    }
     #[profiling::function]
     pub fn update(
         &mut self,
         scene_handler: &mut SceneHandler,
         should_close: &mut bool,
         rl: &mut RaylibHandle,
+        input_handler: &InputHandler,
         save_handler: &mut SaveHandler,
         settings_handler: &SettingsHandler,
-        level_number: &mut u8,
-        metadata_handler: &mut MetadataHandler,
-        level: &mut Level,
-        spirits_handler: &mut SpiritsHandler,
-        enemies_handler: &mut EnemiesHandler,
-        ui_handler: &mut UIHandler,
-        level_transition: &mut LevelTransition,
-        settings_menu: &mut SettingsMenuHandler,
+        game: &mut Game,
         hotkey_handler: &mut HotkeyHandler,
+        daily_handler: &mut DailyHandler,
     ) {
         for (key, button) in self.buttons.iter_mut() {
-            if hotkey_handler.check_pressed(rl, (HotkeyCategory::PickButton1 as u8 + key).into())
-                || (button.rect.check_collision_point_rec(
-                    rl.get_mouse_position()
-                        - Vector2::new(
-                            rl.get_screen_width() as f32 / 2.
-                                - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32)
-                                    as f32
-                                    / 2.,
-                            rl.get_screen_height() as f32 / 2.
-                                - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32)
-                                    as f32
-                                    / 2.,
-                        ),
-                ) && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT))
+            if hotkey_handler.check_pressed(
+                input_handler,
+                (HotkeyCategory::PickButton1 as u8 + key).into(),
+            ) || (button
+                .rect
+                .check_collision_point_rec(input_handler.pointer())
+                && hotkey_handler.check_click(input_handler))
             {
                 match key {
                     0 => {
@@ -116,24 +104,21 @@ impl MainMenuHandler {
                             scene_handler.set(Scene::LevelSelection);
                             return;
                         }
-                        *level_number = FIRST_LEVEL;
-                        metadata_handler.load(*level_number);
-                        level.load(*level_number, metadata_handler, rl);
-                        spirits_handler.spawn_spirits(metadata_handler, settings_handler);
-                        enemies_handler.spawn_enemies(metadata_handler, settings_handler);
-                        *ui_handler = UIHandler::new(
-                            FIRST_LEVEL as usize,
-                            settings_handler.settings.pixel_scale as f32,
-                        );
-                        *level_transition = LevelTransition::new();
+                        game.start_level(FIRST_LEVEL, rl, settings_handler);
                         scene_handler.set(Scene::Level);
                     }
                     2 => {
-                        settings_menu.set_scene(Scene::MainMenu);
-                        settings_menu.align_settings(settings_handler.get_settings());
-                        scene_handler.set(Scene::Settings);
+                        scene_handler.push(Scene::Settings);
                     }
                     3 => {
+                        daily_handler.start(DailyHandler::today_seed(), game, scene_handler);
+                        return;
+                    }
+                    4 => {
+                        scene_handler.set(Scene::Achievements);
+                        return;
+                    }
+                    5 => {
                         *should_close = true;
                     }
                     _ => {}
@@ -146,10 +131,11 @@ impl MainMenuHandler {
     pub fn draw(
         &mut self,
         font: &Font,
+        locale: &Locale,
         save_handler: &SaveHandler,
         texture_handler: &TextureHandler,
         rl: &mut RaylibDrawHandle,
-        settings_handler: &SettingsHandler,
+        input_handler: &InputHandler,
     ) {
         rl.clear_background(Color::from_hex("0b8a8f").unwrap());
 
@@ -161,27 +147,18 @@ impl MainMenuHandler {
             }
         }
 
-        let logo_width: f32 = LOGO_WIDTH * settings_handler.settings.pixel_scale as f32;
-        let logo_height: f32 = LOGO_HEIGHT * settings_handler.settings.pixel_scale as f32;
+        let logo_width: f32 = LOGO_WIDTH;
+        let logo_height: f32 = LOGO_HEIGHT;
 
-        rl.draw_texture_ex(
-            texture_handler.get_safe(BACKGROUND_IMAGE_NAME),
-            Vector2::zero(),
-            0.0,
-            settings_handler.settings.pixel_scale as f32,
-            Color::WHITE,
-        );
+        texture_handler.draw(rl, BACKGROUND_IMAGE_NAME, Vector2::zero(), Color::WHITE);
 
-        rl.draw_texture_ex(
-            texture_handler.get_safe(LOGO_NAME),
+        texture_handler.draw(
+            rl,
+            LOGO_NAME,
             Vector2::new(
-                ((SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) / 2) as f32
-                    - logo_width / 2.,
-                ((SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) / 4) as f32
-                    - logo_height / 2.,
+                (SCREEN_WIDTH / 2) as f32 - logo_width / 2.,
+                (SCREEN_HEIGHT / 4) as f32 - logo_height / 2.,
             ),
-            0.0,
-            settings_handler.settings.pixel_scale as f32,
             Color::WHITE,
         );
 
@@ -193,19 +170,12 @@ impl MainMenuHandler {
             let mut label_num = i;
             let button = self.buttons.get_mut(&(i as u8)).unwrap();
 
-            let text_offset_y = if button.rect.check_collision_point_rec(
-                rl.get_mouse_position()
-                    - Vector2::new(
-                        rl.get_screen_width() as f32 / 2.
-                            - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                        rl.get_screen_height() as f32 / 2.
-                            - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                    ),
-            ) && rl.is_mouse_button_up(MouseButton::MOUSE_BUTTON_LEFT)
+            let text_offset_y = if button
+                .rect
+                .check_collision_point_rec(input_handler.pointer())
+                && input_handler.pointer_up(MouseButton::MOUSE_BUTTON_LEFT)
             {
-                settings_handler.settings.pixel_scale as f32
+                1.
             } else {
                 0.
             };
@@ -214,25 +184,22 @@ impl MainMenuHandler {
                 label_num = self.labels.len() - 1;
             }
 
-            let text_dimensions = get_text_size(
-                font,
-                self.labels[label_num],
-                12. * settings_handler.settings.pixel_scale as f32,
-                1.25 * settings_handler.settings.pixel_scale as f32,
-            );
+            let label = locale.get(self.labels[label_num]);
+            let text_dimensions = get_text_size(font, label, 12., 1.25);
 
             let texture_offset = if text_offset_y == 0. { 16. } else { 0. };
 
             button.draw_with_text_middle(
                 rl,
-                self.labels[label_num],
+                label,
                 font,
-                texture_handler.get_safe("main_menu_buttons"),
+                texture_handler,
+                BUTTONS_TEXTURE,
                 &Rectangle::new(0., texture_offset, 64., 16.),
                 text_dimensions,
                 &Color::RAYWHITE,
-                12. * settings_handler.settings.pixel_scale as f32,
-                1.25 * settings_handler.settings.pixel_scale as f32,
+                12.,
+                1.25,
                 Vector2::new(0., -text_offset_y),
                 Vector2::new(0., 0.),
             );
@@ -240,11 +207,54 @@ impl MainMenuHandler {
 
         rl.draw_text_ex(
             font,
-            format!("Версия {}", VERSION).as_str(),
-            Vector2::one() * settings_handler.settings.pixel_scale as f32,
-            6. * settings_handler.settings.pixel_scale as f32,
+            &locale.format("menu.version", &[&VERSION]),
+            Vector2::one(),
+            6.,
             0.,
             Color::RAYWHITE,
         );
     }
 }
+
+impl SceneState for MainMenuHandler {
+    fn update(&mut self, app: &mut App, rl: &mut RaylibHandle) {
+        if app.input_handler.key_pressed(KeyboardKey::KEY_L) {
+            app.scene_handler.set(Scene::LevelSelection);
+        }
+        // continue is hidden without saves, the d-pad shouldn't land on it either
+        let targets: Vec<Rectangle> = (0..self.buttons.len() as u8)
+            .filter(|key| *key != 0 || app.save_handler.is_there_saves)
+            .map(|key| self.buttons[&key].rect)
+            .collect();
+        app.gamepad_handler
+            .navigate(rl, &mut app.input_handler, &app.settings_handler, &targets);
+        MainMenuHandler::update(
+            self,
+            &mut app.scene_handler,
+            &mut app.should_close,
+            rl,
+            &app.input_handler,
+            &mut app.save_handler,
+            &app.settings_handler,
+            &mut app.game,
+            &mut app.hotkey_handler,
+            &mut app.daily_handler,
+        );
+    }
+
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        MainMenuHandler::draw(
+            self,
+            &app.font,
+            &app.locale,
+            &app.save_handler,
+            &app.texture_handler,
+            rl,
+            &app.input_handler,
+        );
+    }
+
+    fn title(&self, app: &App, _rl: &RaylibHandle) -> Option<String> {
+        Some(app.locale.get("title").to_string())
+    }
+}
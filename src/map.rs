@@ -1,20 +1,48 @@
+use std::collections::HashMap;
+
 use raylib::{color::Color, prelude::*};
 
 use crate::{
+    achievements::GameEvent,
+    animation::AnimationPlayer,
+    camera::WorldCamera,
     map_loader,
     metadata_handler::MetadataHandler,
+    modifier::Modifier,
     music_handler::MusicHandler,
     scene::{Scene, SceneHandler},
+    score::{ReplayAction, ReplayStep, ScoreHandler},
     settings::SettingsHandler,
     texture_handler::TextureHandler,
 };
 
-pub const TILE_SCALE_DEFAULT: i32 = 3;
-
 pub const LEVEL_WIDTH_TILES: usize = 16;
 pub const LEVEL_HEIGHT_TILES: usize = 9;
 pub const TILE_SIZE_PX: i32 = 16;
 
+// portals reuse the exit sprite, tinted so they don't get mixed up
+const PORTAL_TINT: &str = "30e1b9";
+
+const GRASS_TEXTURE: &str = "grass";
+const TREES_TEXTURE: &str = "trees";
+
+pub const TEXTURES: [&str; 2] = [GRASS_TEXTURE, TREES_TEXTURE];
+
+// a one-shot animation left in the world, like a tree falling, gone once it's over
+pub struct Effect {
+    position: Vector2,
+    animation: AnimationPlayer,
+}
+
+// a fire tile the way it's drawn, which sheet, in what state and where on screen
+struct FireTile<'a> {
+    tile: (usize, usize),
+    sheet: &'a str,
+    active: bool,
+    selected: bool,
+    dest: Rectangle,
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum TileType {
     Air { selected: bool },
@@ -23,6 +51,7 @@ pub enum TileType {
     FireStop { active: bool, selected: bool },
     Tree { chance: i32, selected: bool },
     Swamp { teleport_position: Vector2 },
+    Portal { direction: Vector2 },
     Exit(char),
 }
 
@@ -31,6 +60,18 @@ pub struct Level {
     pub wood: usize,
     pub survived: usize,
     pub survive: usize,
+    pub actions: usize, // builds and orders the player made
+    pub bonfires_built: usize,
+    pub time: f32,
+    pub replay: Vec<ReplayStep>,
+    pub events: Vec<GameEvent>, // picked up by the achievement and dialogue handlers every frame
+    pub enemies: usize,
+    pub caught: usize, // spirits the enemies got
+    pub modifier: Modifier,
+    pub safe_border: bool,
+    animations: HashMap<(usize, usize), AnimationPlayer>, // made on the first draw of a tile
+    effects: Vec<Effect>,
+    finished: bool,
 }
 
 impl Level {
@@ -41,15 +82,39 @@ impl Level {
             wood: 0,
             survived: 0,
             survive: 0,
+            actions: 0,
+            bonfires_built: 0,
+            time: 0.,
+            replay: Vec::new(),
+            events: Vec::new(),
+            enemies: 0,
+            caught: 0,
+            modifier: Modifier::Standard,
+            safe_border: false,
+            animations: HashMap::new(),
+            effects: Vec::new(),
+            finished: false,
         }
     }
 
     fn set_load_data(&mut self, metadata_handler: &mut MetadataHandler) {
-        self.survive = metadata_handler.get_survive();
+        self.modifier = metadata_handler.modifier;
+        self.safe_border = metadata_handler.safe_border;
+        self.survive = self.modifier.survive(metadata_handler.get_survive());
         self.survived = 0;
-        self.wood = 0;
+        self.wood = self.modifier.start_wood();
+        self.actions = 0;
+        self.bonfires_built = 0;
+        self.time = 0.;
+        self.replay = Vec::new();
+        self.enemies = metadata_handler.enemies.len();
+        self.caught = 0;
+        self.finished = false;
+        self.animations.clear();
+        self.effects.clear();
         self.connect_swamps(metadata_handler);
         self.light_bonfires(metadata_handler);
+        self.open_portals(metadata_handler);
     }
 
     #[profiling::function]
@@ -74,6 +139,17 @@ impl Level {
         self.set_load_data(metadata_handler);
     }
 
+    // for levels that never touch the disk, like the daily forest
+    #[profiling::function]
+    pub fn load_generated(
+        &mut self,
+        tiles: [[TileType; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES],
+        metadata_handler: &mut MetadataHandler,
+    ) {
+        self.tiles = tiles;
+        self.set_load_data(metadata_handler);
+    }
+
     pub fn completed(&self) -> bool {
         return self.survived >= self.survive;
     }
@@ -88,12 +164,82 @@ impl Level {
 
     pub fn remove_wood(&mut self) {
         self.wood -= 1;
+        if self.wood == 0 {
+            self.push_event(GameEvent::WoodRanOut);
+        }
     }
 
     pub fn survive(&mut self) {
         self.survived += 1;
     }
 
+    pub fn push_event(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    // a tile that changed into something else gets a fresh player
+    fn tile_animation(&mut self, x: usize, y: usize, sheet: &str) -> &mut AnimationPlayer {
+        let animation = self
+            .animations
+            .entry((x, y))
+            .or_insert_with(|| AnimationPlayer::new(sheet));
+        if animation.sheet() != sheet {
+            *animation = AnimationPlayer::new(sheet);
+        }
+        animation
+    }
+
+    pub fn play_tile_animation(&mut self, x: usize, y: usize, sheet: &str, clip: &str) {
+        self.tile_animation(x, y, sheet).play(clip);
+    }
+
+    pub fn spawn_effect(&mut self, position: Vector2, animation: AnimationPlayer) {
+        self.effects.push(Effect {
+            position,
+            animation,
+        });
+    }
+
+    #[profiling::function]
+    pub fn update_animations(&mut self, delta: f32) {
+        for animation in self.animations.values_mut() {
+            animation.update(delta);
+        }
+        for effect in self.effects.iter_mut() {
+            effect.animation.update(delta);
+        }
+    }
+
+    // true only the first time, update keeps getting called while the scene fades out
+    pub fn finish(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+        self.finished = true;
+
+        if self.completed() {
+            if self.bonfires_built == 0 {
+                self.push_event(GameEvent::LevelWithoutBuilding);
+            }
+            if self.enemies > 0 && self.caught == 0 {
+                self.push_event(GameEvent::EnemiesAvoided);
+            }
+        }
+
+        true
+    }
+
+    pub fn record(&mut self, action: ReplayAction) {
+        if let ReplayAction::Build { .. } = action {
+            self.bonfires_built += 1;
+        }
+        self.actions += 1;
+        self.replay.push(ReplayStep {
+            time: self.time,
+            action,
+        });
+    }
+
     #[profiling::function]
     pub fn connect_swamps(&mut self, metadata_handler: &mut MetadataHandler) {
         for i in metadata_handler.swamps.iter() {
@@ -120,6 +266,27 @@ impl Level {
         }
     }
 
+    #[profiling::function]
+    pub fn open_portals(&mut self, metadata_handler: &mut MetadataHandler) {
+        for portal in metadata_handler.portals.iter() {
+            match self.tiles[portal.position[0] as usize][portal.position[1] as usize] {
+                TileType::Portal { direction: _ } => {
+                    self.tiles[portal.position[0] as usize][portal.position[1] as usize] =
+                        TileType::Portal {
+                            direction: Vector2::new(
+                                portal.direction[0] as f32,
+                                portal.direction[1] as f32,
+                            ),
+                        };
+                }
+                _ => panic!(
+                    "ERROR WITH PORTALS BINDING, METADATA POSITION - {} {}",
+                    portal.position[0], portal.position[1]
+                ),
+            };
+        }
+    }
+
     #[profiling::function]
     pub fn light_bonfires(&mut self, metadata_handler: &mut MetadataHandler) {
         for bonfire in metadata_handler.bonfires.iter_mut() {
@@ -164,15 +331,22 @@ impl Level {
 
     #[profiling::function]
     pub fn update(
-        &self,
+        &mut self,
+        level_number: u8,
         scene_handler: &mut SceneHandler,
-        left_amount: u8,
+        score_handler: &mut ScoreHandler,
+        left_amount: u8, // alive spirits plus the ones portals haven't released yet
         music_handler: &MusicHandler,
         settings_handler: &SettingsHandler,
     ) {
-        if self.completed() && left_amount == 0 {
+        if left_amount != 0 || !self.finish() {
+            return;
+        }
+
+        if self.completed() {
+            score_handler.submit(level_number, self, &settings_handler.settings.player_name);
             scene_handler.set(Scene::Transition);
-        } else if left_amount == 0 {
+        } else {
             music_handler.play("death", &settings_handler.get_settings());
             scene_handler.set(Scene::GameOver);
         }
@@ -184,17 +358,12 @@ impl Level {
         rl: &mut RaylibDrawHandle,
         texture_handler: &TextureHandler,
         level_number: u8,
-        settings_handler: &SettingsHandler,
     ) {
+        let camera = WorldCamera;
         for x in 0..LEVEL_WIDTH_TILES {
             for y in 0..LEVEL_HEIGHT_TILES {
-                let stage_offset = if level_number < 10 {
-                    0.
-                } else if level_number < 20 {
-                    TILE_SIZE_PX as f32
-                } else {
-                    TILE_SIZE_PX as f32 * 2.
-                };
+                let dest = camera.tile_rect(Vector2::new(x as f32, y as f32));
+                let stage_offset = Self::stage_offset(level_number);
                 // let source = Rectangle::new(((x + y) % 3) as f32 * 16., 0., 16., 16.);
                 let selection_offset = match &mut self.tiles[x][y] {
                     TileType::Air { selected: val } => {
@@ -215,56 +384,18 @@ impl Level {
                     16.,
                 );
 
-                rl.draw_texture_pro(
-                    texture_handler.get_safe("grass"),
-                    source,
-                    Rectangle::new(
-                        (x as i32 * TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32)
-                            as f32,
-                        (y as i32 * TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32)
-                            as f32,
-                        TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                        TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                    ),
-                    Vector2::zero(),
-                    0.0,
-                    Color::WHITE,
-                );
+                texture_handler.draw_pro(rl, GRASS_TEXTURE, source, dest, Color::WHITE);
 
                 match self.tiles[x][y] {
                     TileType::FireStop { active, selected } => {
-                        let source = if active {
-                            Rectangle::new(
-                                ((rl.get_time() * 8.) % 4.).floor() as f32 * 16.,
-                                16.,
-                                16.,
-                                16.,
-                            )
-                        } else if selected {
-                            Rectangle::new(16., 0., 16., 16.)
-                        } else {
-                            Rectangle::new(0., 0., 16., 16.)
+                        let fire = FireTile {
+                            tile: (x, y),
+                            sheet: "fire_stop",
+                            active,
+                            selected,
+                            dest,
                         };
-
-                        rl.draw_texture_pro(
-                            texture_handler.get_safe("fire_stop"),
-                            source,
-                            Rectangle::new(
-                                (x as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                (y as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                            ),
-                            Vector2::zero(),
-                            0.0,
-                            Color::WHITE,
-                        );
+                        self.draw_fire(&fire, texture_handler, rl);
                     }
                     TileType::Tree { chance, selected } => {
                         let offset = if chance >= 50 {
@@ -288,165 +419,131 @@ impl Level {
                             TILE_SIZE_PX as f32,
                         );
 
-                        rl.draw_texture_pro(
-                            texture_handler.get_safe("trees"),
-                            source,
-                            Rectangle::new(
-                                (x as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                (y as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                            ),
-                            Vector2::zero(),
-                            0.0,
-                            Color::WHITE,
-                        );
+                        texture_handler.draw_pro(rl, TREES_TEXTURE, source, dest, Color::WHITE);
                     }
                     TileType::FireTD { active, selected } => {
-                        let source = if active {
-                            Rectangle::new(
-                                ((rl.get_time() * 8.) % 4.).floor() as f32 * 16.,
-                                16.,
-                                16.,
-                                16.,
-                            )
-                        } else if selected {
-                            Rectangle::new(16., 0., 16., 16.)
-                        } else {
-                            Rectangle::new(0., 0., 16., 16.)
+                        let fire = FireTile {
+                            tile: (x, y),
+                            sheet: "fire_td",
+                            active,
+                            selected,
+                            dest,
                         };
-
-                        rl.draw_texture_pro(
-                            texture_handler.get_safe("fire_td"),
-                            source,
-                            Rectangle::new(
-                                (x as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                (y as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                            ),
-                            Vector2::zero(),
-                            0.0,
-                            Color::WHITE,
-                        );
+                        self.draw_fire(&fire, texture_handler, rl);
                     }
                     TileType::FireLR { active, selected } => {
-                        let source = if active {
-                            Rectangle::new(
-                                ((rl.get_time() * 8.) % 4.).floor() as f32 * 16.,
-                                16.,
-                                16.,
-                                16.,
-                            )
-                        } else if selected {
-                            Rectangle::new(16., 0., 16., 16.)
-                        } else {
-                            Rectangle::new(0., 0., 16., 16.)
+                        let fire = FireTile {
+                            tile: (x, y),
+                            sheet: "fire_lr",
+                            active,
+                            selected,
+                            dest,
                         };
-
-                        rl.draw_texture_pro(
-                            texture_handler.get_safe("fire_lr"),
-                            source,
-                            Rectangle::new(
-                                (x as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                (y as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                            ),
-                            Vector2::zero(),
-                            0.0,
-                            Color::WHITE,
-                        );
+                        self.draw_fire(&fire, texture_handler, rl);
                     }
                     TileType::Exit(rotation) => {
-                        let offset = match rotation {
-                            '^' => TILE_SIZE_PX as f32,
-                            'v' => TILE_SIZE_PX as f32 * 3.,
-                            '<' => 0.0,
-                            '>' => TILE_SIZE_PX as f32 * 2.,
+                        let clip = match rotation {
+                            '^' => "up",
+                            'v' => "down",
+                            '<' => "left",
+                            '>' => "right",
                             _ => {
                                 panic!("impossible exit rotation")
                             }
                         };
 
-                        let source = Rectangle::new(
-                            offset,
-                            ((rl.get_time() * 8.) % 4.).floor() as f32 * 16.,
-                            16.,
-                            16.,
-                        );
+                        let animation = self.tile_animation(x, y, "exit");
+                        if animation.clip() != Some(clip) {
+                            animation.play(clip);
+                        }
+                        texture_handler.draw_animation(rl, animation, dest, Color::WHITE);
+                    }
+                    TileType::Portal { direction } => {
+                        let clip = if direction.x < 0. {
+                            "left"
+                        } else if direction.y < 0. {
+                            "up"
+                        } else if direction.x > 0. {
+                            "right"
+                        } else {
+                            "down"
+                        };
 
-                        rl.draw_texture_pro(
-                            texture_handler.get_safe("exit"),
-                            source,
-                            Rectangle::new(
-                                (x as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                (y as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                            ),
-                            Vector2::zero(),
-                            0.0,
-                            Color::WHITE,
+                        let animation = self.tile_animation(x, y, "exit");
+                        if animation.clip() != Some(clip) {
+                            animation.play(clip);
+                        }
+                        texture_handler.draw_animation(
+                            rl,
+                            animation,
+                            dest,
+                            Color::from_hex(PORTAL_TINT).unwrap(),
                         );
                     }
                     TileType::Swamp {
                         teleport_position: _,
                     } => {
-                        let source = Rectangle::new(
-                            ((rl.get_time() * 2.) % 2.).floor() as f32 * 16.,
-                            16.,
-                            16.,
-                            16.,
-                        );
-
-                        rl.draw_texture_pro(
-                            texture_handler.get_safe("swamp"),
-                            source,
-                            Rectangle::new(
-                                (x as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                (y as i32
-                                    * TILE_SIZE_PX
-                                    * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                                TILE_SIZE_PX as f32 * settings_handler.settings.pixel_scale as f32,
-                            ),
-                            Vector2::zero(),
-                            0.0,
-                            Color::WHITE,
-                        );
+                        let animation = self.tile_animation(x, y, "swamp");
+                        texture_handler.draw_animation(rl, animation, dest, Color::WHITE);
                     }
                     _ => {}
                 }
             }
         }
+
+        // effects on trees follow the season the same way the trees do
+        self.effects
+            .retain(|effect| !texture_handler.animations().finished(&effect.animation));
+        for effect in self.effects.iter() {
+            let mut source = texture_handler.animations().source(&effect.animation);
+            if effect.animation.sheet() == TREES_TEXTURE {
+                source.y += Self::stage_offset(level_number) * 2.;
+            }
+
+            texture_handler.draw_pro(
+                rl,
+                effect.animation.sheet(),
+                source,
+                camera.tile_rect(effect.position),
+                Color::WHITE,
+            );
+        }
+    }
+
+    fn stage_offset(level_number: u8) -> f32 {
+        if level_number < 10 {
+            0.
+        } else if level_number < 20 {
+            TILE_SIZE_PX as f32
+        } else {
+            TILE_SIZE_PX as f32 * 2.
+        }
+    }
+
+    // unlit ones are a single frame, lit ones keep their player so lighting can play first
+    fn draw_fire(
+        &mut self,
+        fire: &FireTile,
+        texture_handler: &TextureHandler,
+        rl: &mut RaylibDrawHandle,
+    ) {
+        let (x, y) = fire.tile;
+        if fire.active {
+            let animation = self.tile_animation(x, y, fire.sheet);
+            texture_handler.draw_animation(rl, animation, fire.dest, Color::WHITE);
+            return;
+        }
+
+        let clip = if fire.selected { "selected" } else { "unlit" };
+        texture_handler.draw_pro(
+            rl,
+            fire.sheet,
+            texture_handler
+                .animations()
+                .clip(fire.sheet, clip)
+                .frame(0.),
+            fire.dest,
+            Color::WHITE,
+        );
     }
 }
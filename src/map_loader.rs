@@ -3,6 +3,7 @@ pub const MAP_PATH: &str = "static/maps/";
 use crate::{
     map::{self, LEVEL_HEIGHT_TILES, LEVEL_WIDTH_TILES, TileType},
     metadata_handler::MetadataHandler,
+    profile,
     save_handler::SAVE_PATH,
 };
 use raylib::prelude::*;
@@ -16,7 +17,7 @@ pub struct MapLoader;
 impl MapLoader {
     #[profiling::function]
     pub fn get_map_save(level_number: u8, level_map: &mut map::Level, rl: &mut RaylibHandle) {
-        let level_path = SAVE_PATH.to_string() + &level_number.to_string();
+        let level_path = profile::path(SAVE_PATH) + &level_number.to_string();
         MapLoader::map_loading(level_path, level_map, rl);
     }
 
@@ -86,6 +87,11 @@ impl MapLoader {
                         teleport_position: Vector2::zero(),
                     };
                 }
+                'p' => {
+                    level_map.tiles[x][y] = TileType::Portal {
+                        direction: Vector2::zero(),
+                    };
+                }
                 other => {
                     panic!("NOT DEFINED CHARACTER TO LOAD -{other}")
                 }
@@ -103,8 +109,6 @@ impl MapLoader {
         level_map: &mut map::Level,
         metadata_handler: &mut MetadataHandler,
     ) {
-        let mut map = "".to_string();
-
         let mut fire_td: HashMap<[u8; 2], bool> = HashMap::new();
         let mut fire_lr: HashMap<[u8; 2], bool> = HashMap::new();
         let mut fire_stop: HashMap<[u8; 2], bool> = HashMap::new();
@@ -112,45 +116,34 @@ impl MapLoader {
         for y in 0..LEVEL_HEIGHT_TILES {
             for x in 0..LEVEL_WIDTH_TILES {
                 match level_map.tiles[x][y] {
-                    TileType::Air { selected: _ } => map += ".",
-                    TileType::Tree {
-                        chance: _,
-                        selected: _,
-                    } => map += "#",
                     TileType::FireTD {
                         active,
                         selected: _,
                     } => {
                         fire_td.insert([x as u8, y as u8], active);
-                        map += "1";
                     }
                     TileType::FireLR {
                         active,
                         selected: _,
                     } => {
                         fire_lr.insert([x as u8, y as u8], active);
-                        map += "2";
                     }
                     TileType::FireStop {
                         active,
                         selected: _,
                     } => {
                         fire_stop.insert([x as u8, y as u8], active);
-                        map += "3";
                     }
-                    TileType::Swamp {
-                        teleport_position: _,
-                    } => map += "s",
-                    TileType::Exit(val) => map += &val.to_string(),
+                    _ => {}
                 };
             }
-            map += "\n";
         }
+        let map = MapLoader::map_to_string(&level_map.tiles);
         metadata_handler.change_bonfires(fire_td, fire_lr, fire_stop);
 
-        let path = SAVE_PATH.to_string() + &level_number.to_string();
+        let path = profile::path(SAVE_PATH) + &level_number.to_string();
 
-        let filenames = fs::read_dir(SAVE_PATH).unwrap();
+        let filenames = fs::read_dir(profile::path(SAVE_PATH)).unwrap();
 
         for filename in filenames {
             let file = match filename {
@@ -174,6 +167,62 @@ impl MapLoader {
         };
     }
 
+    // the same characters map_loading reads
+    fn map_to_string(tiles: &[[TileType; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES]) -> String {
+        let mut map = "".to_string();
+
+        // tiles go column by column, the file goes row by row
+        for y in 0..LEVEL_HEIGHT_TILES {
+            for column in tiles.iter() {
+                match column[y] {
+                    TileType::Air { selected: _ } => map += ".",
+                    TileType::Tree {
+                        chance: _,
+                        selected: _,
+                    } => map += "#",
+                    TileType::FireTD {
+                        active: _,
+                        selected: _,
+                    } => map += "1",
+                    TileType::FireLR {
+                        active: _,
+                        selected: _,
+                    } => map += "2",
+                    TileType::FireStop {
+                        active: _,
+                        selected: _,
+                    } => map += "3",
+                    TileType::Swamp {
+                        teleport_position: _,
+                    } => map += "s",
+                    TileType::Portal { direction: _ } => map += "p",
+                    TileType::Exit(val) => map += &val.to_string(),
+                };
+            }
+            map += "\n";
+        }
+
+        map
+    }
+
+    #[profiling::function]
+    pub fn export_path(level_number: u8) -> String {
+        MAP_PATH.to_string() + &level_number.to_string()
+    }
+
+    // never overwrites, a designer's level could be sitting there
+    pub fn export_map(
+        level_number: u8,
+        tiles: &[[TileType; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES],
+    ) -> Result<(), String> {
+        let path = MapLoader::export_path(level_number);
+        let mut file =
+            File::create_new(&path).map_err(|e| format!("couldn't create {path}: {e}"))?;
+
+        write!(file, "{}", MapLoader::map_to_string(tiles))
+            .map_err(|e| format!("couldn't write {path}: {e}"))
+    }
+
     #[profiling::function]
     pub fn get_map(level_number: u8, level_map: &mut map::Level, rl: &mut RaylibHandle) {
         let level_path = MAP_PATH.to_string() + &level_number.to_string();
@@ -6,7 +6,7 @@ use std::{
 };
 
 use crate::{
-    map::TILE_SIZE_PX, save_handler::SAVE_PATH, settings::SettingsHandler,
+    modifier::Modifier, profile, save_handler::SAVE_PATH, settings::Settings,
     spirits_handler::SpiritsHandler,
 };
 
@@ -37,6 +37,15 @@ pub struct SwampsMetadata {
     pub teleport: [u8; 2],
 }
 
+#[derive(Deserialize, Clone, Serialize)]
+pub struct PortalMetadata {
+    pub position: [u8; 2],
+    pub direction: [i8; 2],
+    pub amount: u8,
+    pub interval: f32, // seconds between two spawns
+    pub delay: f32,    // seconds before the first spawn
+}
+
 #[derive(Deserialize, Clone, Serialize)]
 pub struct MetadataHandler {
     pub survive: usize,
@@ -44,6 +53,13 @@ pub struct MetadataHandler {
     pub swamps: Vec<SwampsMetadata>,
     pub enemies: Vec<EnemyMetadata>,
     pub bonfires: Vec<BonfireMetadata>,
+    #[serde(default)]
+    pub portals: Vec<PortalMetadata>,
+    // from the settings, not the level files, so they never end up in saves or exports
+    #[serde(skip)]
+    pub modifier: Modifier,
+    #[serde(skip)]
+    pub safe_border: bool,
 }
 
 impl MetadataHandler {
@@ -80,6 +96,13 @@ impl MetadataHandler {
         self.swamps = level_metadata.swamps;
         self.enemies = level_metadata.enemies;
         self.bonfires = level_metadata.bonfires;
+        self.portals = level_metadata.portals;
+    }
+
+    // the next loaded level picks these up, the one being played keeps its own
+    pub fn set_modifiers(&mut self, settings: &Settings) {
+        self.modifier = settings.modifier;
+        self.safe_border = settings.safe_border;
     }
 
     #[profiling::function]
@@ -88,7 +111,7 @@ impl MetadataHandler {
     }
     #[profiling::function]
     pub fn load_save(&mut self) {
-        let filenames = fs::read_dir(SAVE_PATH).unwrap();
+        let filenames = fs::read_dir(profile::path(SAVE_PATH)).unwrap();
         let mut path = "-1".to_string();
         for filename in filenames {
             let file = match filename {
@@ -109,7 +132,7 @@ impl MetadataHandler {
         let path_to_save = if path == "-1" {
             panic!("ERROR DURING SAVE LOAD METADATA");
         } else {
-            SAVE_PATH.to_string() + &path + &METADATA_EXTENSION.to_string()
+            profile::path(SAVE_PATH) + &path + METADATA_EXTENSION
         };
 
         let Ok(string_json) = fs::read_to_string(path_to_save) else {
@@ -125,11 +148,12 @@ impl MetadataHandler {
         self.swamps = level_metadata.swamps;
         self.enemies = level_metadata.enemies;
         self.bonfires = level_metadata.bonfires;
+        self.portals = level_metadata.portals;
     }
 
     #[profiling::function]
     pub fn save(&self, level_number: u8) {
-        let Ok(filenames) = fs::read_dir(SAVE_PATH) else {
+        let Ok(filenames) = fs::read_dir(profile::path(SAVE_PATH)) else {
             panic!("COULDN'T EMPTY THE SAVE FOLDER TO SAVE")
         };
 
@@ -149,7 +173,7 @@ impl MetadataHandler {
         }
 
         let path_to_save =
-            SAVE_PATH.to_string() + &level_number.to_string() + &METADATA_EXTENSION.to_string();
+            profile::path(SAVE_PATH) + &level_number.to_string() + METADATA_EXTENSION;
 
         let Ok(file) = File::create(path_to_save) else {
             panic!("COULDN'T SAVE LAST LEVEL");
@@ -162,12 +186,24 @@ impl MetadataHandler {
         };
     }
 
+    // for generated levels, goes next to the hand-made ones
     #[profiling::function]
-    pub fn change_spirits(
-        &mut self,
-        spirits_handler: &SpiritsHandler,
-        settings_handler: &SettingsHandler,
-    ) {
+    pub fn export_path(level_number: u8) -> String {
+        METADATA_PATH.to_string() + &level_number.to_string() + METADATA_EXTENSION
+    }
+
+    // never overwrites, same as the map next to it
+    pub fn export(&self, level_number: u8) -> Result<(), String> {
+        let path = MetadataHandler::export_path(level_number);
+        let file = File::create_new(&path).map_err(|e| format!("couldn't create {path}: {e}"))?;
+        let writer = BufWriter::new(file);
+
+        serde_json::to_writer_pretty(writer, &self)
+            .map_err(|e| format!("couldn't write {path}: {e}"))
+    }
+
+    #[profiling::function]
+    pub fn change_spirits(&mut self, spirits_handler: &SpiritsHandler) {
         self.spirits = Vec::new();
 
         for spirit in spirits_handler.spirits.values() {
@@ -176,12 +212,8 @@ impl MetadataHandler {
             }
 
             let position = [
-                (spirit.get_position().x
-                    / (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32)
-                    .floor() as u8,
-                (spirit.get_position().y
-                    / (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32)
-                    .floor() as u8,
+                spirit.get_position().x.floor() as u8,
+                spirit.get_position().y.floor() as u8,
             ];
             let direction = [
                 spirit.get_direction().x.floor() as i8,
@@ -196,6 +228,25 @@ impl MetadataHandler {
         }
     }
 
+    #[profiling::function]
+    pub fn change_portals(&mut self, spirits_handler: &SpiritsHandler) {
+        self.portals = Vec::new();
+
+        for portal in spirits_handler.portals.iter() {
+            if portal.left == 0 {
+                continue;
+            }
+
+            self.portals.push(PortalMetadata {
+                position: portal.position,
+                direction: portal.direction,
+                amount: portal.left,
+                interval: portal.interval,
+                delay: portal.timer.max(0.),
+            });
+        }
+    }
+
     #[profiling::function]
     pub fn change_bonfires(
         &mut self,
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{locale::Locale, puzzle::PATROL_TICK};
+
+pub const MODIFIERS: [Modifier; 3] = [Modifier::Relaxed, Modifier::Standard, Modifier::Hard];
+
+// picked in the settings, a level takes it from MetadataHandler when it loads
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Modifier {
+    Relaxed,
+    #[default]
+    Standard,
+    Hard,
+}
+
+impl Modifier {
+    pub fn from_index(index: u8) -> Self {
+        MODIFIERS[(index as usize).min(MODIFIERS.len() - 1)]
+    }
+
+    pub fn index(self) -> usize {
+        MODIFIERS.iter().position(|m| *m == self).unwrap()
+    }
+
+    pub fn patrol_tick(self) -> f32 {
+        match self {
+            Modifier::Relaxed => PATROL_TICK * 1.4,
+            Modifier::Standard => PATROL_TICK,
+            Modifier::Hard => PATROL_TICK,
+        }
+    }
+
+    // enemies stand still, on hard they pace their row and faster than the spirits walk
+    pub fn enemy_tick(self) -> Option<f32> {
+        match self {
+            Modifier::Hard => Some(PATROL_TICK * 0.7),
+            _ => None,
+        }
+    }
+
+    pub fn start_wood(self) -> usize {
+        match self {
+            Modifier::Relaxed => 1,
+            _ => 0,
+        }
+    }
+
+    // one spirit less to save, but never none at all
+    pub fn survive(self, survive: usize) -> usize {
+        match self {
+            Modifier::Relaxed => survive.saturating_sub(1).max(1),
+            _ => survive,
+        }
+    }
+
+    // on hard a restart costs the level, it counts as lost
+    pub fn free_restart(self) -> bool {
+        self != Modifier::Hard
+    }
+
+    // a key for the locale
+    pub fn name(self) -> &'static str {
+        match self {
+            Modifier::Relaxed => "modifier.relaxed",
+            Modifier::Standard => "modifier.standard",
+            Modifier::Hard => "modifier.hard",
+        }
+    }
+
+    // goes after the score on the boards, standard runs don't need one
+    pub fn tag(self, locale: &Locale) -> String {
+        match self {
+            Modifier::Standard => String::new(),
+            _ => format!(" ({})", locale.get(self.name())),
+        }
+    }
+}
@@ -78,6 +78,11 @@ impl<'a> MusicHandler<'a> {
         self.sounds.get(music_name).unwrap().play();
     }
 
+    // optional sounds, like voices not every speaker has
+    pub fn has(&self, music_name: &str) -> bool {
+        self.sounds.contains_key(music_name)
+    }
+
     #[profiling::function]
     pub fn stop(&self, music_name: &str) {
         self.sounds.get(music_name).unwrap().stop();
@@ -1,18 +1,29 @@
 use raylib::prelude::*;
 
 use crate::{
-    HotkeyHandler, SCREEN_HEIGHT, SCREEN_WIDTH,
+    HotkeyHandler,
+    camera::WorldCamera,
     hotkey_handler::HotkeyCategory,
-    map::{LEVEL_HEIGHT_TILES, LEVEL_WIDTH_TILES, Level, TILE_SIZE_PX, TileType},
-    settings::SettingsHandler,
+    input::InputHandler,
+    map::{LEVEL_HEIGHT_TILES, LEVEL_WIDTH_TILES, Level, TileType},
+    score::ReplayAction,
     spirit::SpiritState,
     spirits_handler::SpiritsHandler,
     texture_handler::TextureHandler,
 };
 
+// how far from a spirit's centre a click still picks it, in tiles
+const PICK_DISTANCE: f32 = 1.1;
+
+const DOT_TEXTURE: &str = "dot";
+
+pub const TEXTURES: [&str; 1] = [DOT_TEXTURE];
+
 pub struct OrderHandler {
     spirit: Option<usize>,
     line_end: Option<Vector2>,
+    // picked with Select, waits for a second Select on the target instead of a held button
+    aiming: bool,
 }
 
 impl OrderHandler {
@@ -21,43 +32,65 @@ impl OrderHandler {
         Self {
             spirit: None,
             line_end: None,
+            aiming: false,
         }
     }
 
+    pub fn selected(&self) -> Option<usize> {
+        self.spirit
+    }
+
+    // the one after the spirit under the cursor, in the order they came in, to walk through them with a key
+    #[profiling::function]
+    pub fn next_spirit(spirits_handler: &SpiritsHandler, cursor: Vector2) -> Option<Vector2> {
+        let mut keys: Vec<usize> = spirits_handler.spirits.keys().copied().collect();
+        keys.sort();
+        let centre =
+            |key: &usize| spirits_handler.spirits[key].get_draw_position() + Vector2::one() / 2.;
+
+        // the nearest, so two spirits standing together don't keep handing the cursor back and forth
+        let current = keys
+            .iter()
+            .enumerate()
+            .filter(|(_, key)| centre(key).distance_to(cursor) <= PICK_DISTANCE)
+            .min_by(|(_, a), (_, b)| {
+                centre(a)
+                    .distance_to(cursor)
+                    .total_cmp(&centre(b).distance_to(cursor))
+            })
+            .map(|(i, _)| i);
+        let next = match current {
+            Some(i) => keys[(i + 1) % keys.len()],
+            None => *keys.first()?,
+        };
+        Some(centre(&next))
+    }
+
     #[profiling::function]
     pub fn select_spirit(
         &mut self,
         spirits_handler: &mut SpiritsHandler,
         level: &mut Level,
-        rl: &RaylibHandle,
+        input_handler: &InputHandler,
         hotkey_handler: &mut HotkeyHandler,
-        settings_handler: &mut SettingsHandler,
     ) {
-        let if_mouse = rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
-        if if_mouse || hotkey_handler.check_pressed(rl, HotkeyCategory::PickNearest) {
-            let mouse_pos = rl.get_mouse_position()
-                - Vector2::new(
-                    rl.get_screen_width() as f32 / 2.
-                        - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32 / 2.,
-                    rl.get_screen_height() as f32 / 2.
-                        - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                            / 2.,
-                );
+        let if_mouse = input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+        let if_select = hotkey_handler.check_pressed(input_handler, HotkeyCategory::Select);
+        // the second Select sends the spirit off to the tile under the cursor
+        let confirmed = self.aiming && if_select;
+        if if_mouse
+            || hotkey_handler.check_pressed(input_handler, HotkeyCategory::PickNearest)
+            || (if_select && !self.aiming)
+        {
+            let mouse_pos = WorldCamera.mouse_world(input_handler);
 
             let mut key_nearest = usize::MAX;
             let mut nearest_dist = f32::MAX;
 
             for (key, spirit) in spirits_handler.spirits.iter() {
-                let dist = (spirit.get_draw_position()
-                    + Vector2::new(
-                        ((TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) / 2) as f32,
-                        ((TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) / 2) as f32,
-                    ))
-                .distance_to(mouse_pos);
-                if dist
-                    <= (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32 * 1.1
-                    && dist < nearest_dist
-                {
+                let dist =
+                    (spirit.get_draw_position() + Vector2::one() / 2.).distance_to(mouse_pos);
+                if dist <= PICK_DISTANCE && dist < nearest_dist {
                     key_nearest = *key;
                     nearest_dist = dist;
                 }
@@ -65,21 +98,24 @@ impl OrderHandler {
             if key_nearest != usize::MAX {
                 self.spirit = Some(key_nearest);
             }
+            self.aiming = if_select && self.spirit.is_some();
         }
 
         if if_mouse {
             hotkey_handler.clear_last();
         }
 
-        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
-            || hotkey_handler.check_pressed(rl, HotkeyCategory::Cancel)
+        if input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
+            || hotkey_handler.check_pressed(input_handler, HotkeyCategory::Cancel)
         {
             self.spirit = None;
+            self.aiming = false;
             return;
         }
 
-        if rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
-            || hotkey_handler.check_down(rl, HotkeyCategory::PickNearest)
+        if input_handler.pointer_down(MouseButton::MOUSE_BUTTON_LEFT)
+            || hotkey_handler.check_down(input_handler, HotkeyCategory::PickNearest)
+            || (self.aiming && !confirmed)
         {
             for y in 0..LEVEL_HEIGHT_TILES {
                 for x in 0..LEVEL_WIDTH_TILES {
@@ -109,19 +145,10 @@ impl OrderHandler {
                 return;
             }
 
-            let mouse_pos = rl.get_mouse_position()
-                - Vector2::new(
-                    rl.get_screen_width() as f32 / 2.
-                        - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32 / 2.,
-                    rl.get_screen_height() as f32 / 2.
-                        - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                            / 2.,
-                );
-
-            let tile_pos =
-                mouse_pos / (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32;
+            let mouse_pos = WorldCamera.mouse_world(input_handler);
+
             let (mut tile_x, mut tile_y) =
-                (tile_pos.x.floor() as usize, tile_pos.y.floor() as usize);
+                (mouse_pos.x.floor() as usize, mouse_pos.y.floor() as usize);
 
             if tile_x >= LEVEL_WIDTH_TILES {
                 tile_x = LEVEL_WIDTH_TILES - 1;
@@ -156,16 +183,8 @@ impl OrderHandler {
             return;
         }
 
-        let mouse_pos = rl.get_mouse_position()
-            - Vector2::new(
-                rl.get_screen_width() as f32 / 2.
-                    - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32 / 2.,
-                rl.get_screen_height() as f32 / 2.
-                    - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32 / 2.,
-            );
-        let tile_pos =
-            mouse_pos / (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32;
-        let (mut tile_x, mut tile_y) = (tile_pos.x.floor() as usize, tile_pos.y.floor() as usize);
+        let mouse_pos = WorldCamera.mouse_world(input_handler);
+        let (mut tile_x, mut tile_y) = (mouse_pos.x.floor() as usize, mouse_pos.y.floor() as usize);
 
         if tile_x >= LEVEL_WIDTH_TILES {
             tile_x = LEVEL_WIDTH_TILES - 1;
@@ -190,6 +209,11 @@ impl OrderHandler {
                         if let Some(spirit) = spirits_handler.spirits.get_mut(&key) {
                             *selected = false;
                             spirit.set_state(SpiritState::LightFire(tile_x, tile_y));
+                            level.record(ReplayAction::Light {
+                                spirit: key,
+                                x: tile_x,
+                                y: tile_y,
+                            });
                         }
                     }
                 }
@@ -202,6 +226,11 @@ impl OrderHandler {
                     if let Some(spirit) = spirits_handler.spirits.get_mut(&key) {
                         *selected = false;
                         spirit.set_state(SpiritState::ChopTree(tile_x, tile_y));
+                        level.record(ReplayAction::Chop {
+                            spirit: key,
+                            x: tile_x,
+                            y: tile_y,
+                        });
                     }
                 }
             }
@@ -209,26 +238,18 @@ impl OrderHandler {
         }
 
         self.spirit = None;
+        self.aiming = false;
     }
 
     #[profiling::function]
     pub fn update_line(
         &mut self,
         level: &Level,
-        rl: &RaylibHandle,
+        input_handler: &InputHandler,
         hotkey_handler: &mut HotkeyHandler,
-        settings_handler: &mut SettingsHandler,
     ) {
-        let mouse_pos = rl.get_mouse_position()
-            - Vector2::new(
-                rl.get_screen_width() as f32 / 2.
-                    - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32 / 2.,
-                rl.get_screen_height() as f32 / 2.
-                    - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32 / 2.,
-            );
-        let tile_pos =
-            mouse_pos / (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32;
-        let (mut tile_x, mut tile_y) = (tile_pos.x.floor() as usize, tile_pos.y.floor() as usize);
+        let mouse_pos = WorldCamera.mouse_world(input_handler);
+        let (mut tile_x, mut tile_y) = (mouse_pos.x.floor() as usize, mouse_pos.y.floor() as usize);
 
         if tile_x >= LEVEL_WIDTH_TILES {
             tile_x = LEVEL_WIDTH_TILES - 1;
@@ -238,30 +259,24 @@ impl OrderHandler {
             tile_y = LEVEL_HEIGHT_TILES - 1;
         }
 
-        if rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
-            || hotkey_handler.check_down(rl, HotkeyCategory::PickNearest)
+        if input_handler.pointer_down(MouseButton::MOUSE_BUTTON_LEFT)
+            || hotkey_handler.check_down(input_handler, HotkeyCategory::PickNearest)
+            || self.aiming
         {
             match level.tiles[tile_x][tile_y] {
                 TileType::Air { selected: _ }
                 | TileType::Swamp {
                     teleport_position: _,
                 }
+                | TileType::Portal { direction: _ }
                 | TileType::Exit(_) => {}
                 _ => {
-                    self.line_end = Some(
-                        Vector2::new(tile_x as f32, tile_y as f32)
-                            * (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32,
-                    );
+                    self.line_end = Some(Vector2::new(tile_x as f32, tile_y as f32));
                     return;
                 }
             }
 
-            self.line_end = Some(
-                mouse_pos
-                    - Vector2::one()
-                        * (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32
-                        / 2.,
-            );
+            self.line_end = Some(mouse_pos - Vector2::one() / 2.);
         } else {
             self.line_end = None;
         }
@@ -273,7 +288,6 @@ impl OrderHandler {
         spirits_handler: &SpiritsHandler,
         texture_handler: &TextureHandler,
         rl: &mut RaylibDrawHandle,
-        settings_handler: &mut SettingsHandler,
     ) {
         let Some(key) = self.spirit else {
             return;
@@ -289,30 +303,17 @@ impl OrderHandler {
 
         let direction = line_end - spirit.get_draw_position();
 
-        let length = (direction.length()
-            / (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32)
-            .floor()
-            * 2.
-            + 1.;
+        let length = direction.length().floor() * 2. + 1.;
 
+        let camera = WorldCamera;
         for i in 0..=length as usize {
-            let position = spirit.get_draw_position() + direction / length * i as f32;
+            let position =
+                camera.to_screen(spirit.get_draw_position() + direction / length * i as f32);
 
             // pixel-perfect effect (may be a bit extra)
-            let position = Vector2::new(
-                (position.x / settings_handler.settings.pixel_scale as f32).floor()
-                    * settings_handler.settings.pixel_scale as f32,
-                (position.y / settings_handler.settings.pixel_scale as f32).floor()
-                    * settings_handler.settings.pixel_scale as f32,
-            );
-
-            rl.draw_texture_ex(
-                texture_handler.get_safe("dot"),
-                position,
-                0.0,
-                settings_handler.settings.pixel_scale as f32,
-                Color::RAYWHITE,
-            );
+            let position = Vector2::new(position.x.floor(), position.y.floor());
+
+            texture_handler.draw(rl, DOT_TEXTURE, position, Color::RAYWHITE);
         }
     }
 }
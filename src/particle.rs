@@ -24,24 +24,21 @@ impl Particle {
         }
     }
 
-    pub fn update(&mut self, rl: &mut RaylibHandle) {
+    pub fn update(&mut self, delta: f32) {
         for i in 0..self.positions.len() {
             let position = self.positions.get_mut(i).unwrap();
 
             let angle = 2. * PI / self.amount as f32 * i as f32;
             let destination = Vector2::new(angle.cos() * self.radius, angle.sin() * self.radius);
 
-            *position = position.lerp(
-                self.start_position + destination,
-                self.speed * rl.get_frame_time(),
-            );
+            *position = position.lerp(self.start_position + destination, self.speed * delta);
             self.done = position.distance_to(self.start_position + destination) <= 1.;
         }
     }
 
     pub fn draw(&self, rl: &mut RaylibDrawHandle) {
         for position in self.positions.iter() {
-            rl.draw_circle_v(position, 4. + (position.x as i32 % 6) as f32, Color::WHITE);
+            rl.draw_circle_v(position, 1. + (position.x as i32 % 2) as f32, Color::WHITE);
         }
     }
 }
@@ -0,0 +1,177 @@
+use raylib::prelude::*;
+
+use crate::{
+    SCREEN_HEIGHT, SCREEN_WIDTH,
+    app::App,
+    color::CustomColor,
+    hotkey_handler::HotkeyCategory,
+    scene::{Cut, Scene, SceneChange, SceneState},
+    ui::{Button, get_text_size},
+};
+
+const PAUSE_BUTTON_LABELS: [&str; 3] = ["pause.restart", "pause.settings", "pause.exit"];
+const PAUSE_TITLE: &str = "pause.title";
+const PAUSE_BUTTON_HOTKEYS: [HotkeyCategory; 3] = [
+    HotkeyCategory::PickButton1,
+    HotkeyCategory::PickButton2,
+    HotkeyCategory::PickButton3,
+];
+
+const PAUSE_BUTTON_TEXTURE: &str = "game_buttons";
+const PAUSE_BUTTON_Y_OFFSET: f32 = 3.;
+const PAUSE_BUTTON_TEXTURE_WIDTH: f32 = 64.;
+const PAUSE_BUTTON_TEXTURE_HEIGHT: f32 = 16.;
+const PAUSE_BUTTON_SHIFT: f32 = 16.;
+
+const PAUSE_TEXT_SIZE: f32 = 12.;
+const PAUSE_TEXT_SPACING: f32 = 1.05;
+
+const PANEL_TEXTURE: &str = "pause_menu";
+const PANEL_WIDTH: f32 = 128.;
+const PANEL_HEIGHT: f32 = 96.;
+const PANEL_TEXT_Y_OFFSET: f32 = 7.;
+
+pub const TEXTURES: [&str; 2] = [PAUSE_BUTTON_TEXTURE, PANEL_TEXTURE];
+
+// pushed over a running level, the level underneath just stops updating
+pub struct PauseMenuHandler {
+    buttons: Vec<Button>,
+}
+
+impl PauseMenuHandler {
+    #[profiling::function]
+    pub fn new() -> Self {
+        let mut buttons = Vec::new();
+        for i in 0..PAUSE_BUTTON_LABELS.len() {
+            buttons.push(Button {
+                rect: Rectangle::new(
+                    (SCREEN_WIDTH as f32 - PAUSE_BUTTON_TEXTURE_WIDTH) / 2.,
+                    SCREEN_HEIGHT as f32 / 2.
+                        + (PAUSE_BUTTON_Y_OFFSET + PAUSE_BUTTON_SHIFT) * (i as f32 - 1.),
+                    PAUSE_BUTTON_TEXTURE_WIDTH,
+                    PAUSE_BUTTON_TEXTURE_HEIGHT,
+                ),
+                ..Default::default()
+            });
+        }
+
+        return Self { buttons };
+    }
+
+    fn pressed(&self, index: usize, app: &mut App) -> bool {
+        app.hotkey_handler
+            .check_pressed(&app.input_handler, PAUSE_BUTTON_HOTKEYS[index])
+            || (self.buttons[index]
+                .rect
+                .check_collision_point_rec(app.input_handler.pointer())
+                && app.hotkey_handler.check_click(&app.input_handler))
+    }
+}
+
+impl SceneState for PauseMenuHandler {
+    #[profiling::function]
+    fn update(&mut self, app: &mut App, rl: &mut RaylibHandle) {
+        let targets: Vec<Rectangle> = self.buttons.iter().map(|button| button.rect).collect();
+        app.gamepad_handler
+            .navigate(rl, &mut app.input_handler, &app.settings_handler, &targets);
+
+        if app
+            .hotkey_handler
+            .check_pressed(&app.input_handler, HotkeyCategory::Exit)
+        {
+            app.scene_handler.change(SceneChange::Pop, Box::new(Cut));
+            return;
+        }
+
+        if self.pressed(0, app) {
+            // first, on hard the restart takes the player to the game over screen instead
+            app.restart_level(rl);
+            app.scene_handler.change(SceneChange::Pop, Box::new(Cut));
+            return;
+        }
+
+        if self.pressed(1, app) {
+            app.scene_handler.push(Scene::Settings);
+            return;
+        }
+
+        if self.pressed(2, app) {
+            if app.daily_handler.active {
+                app.daily_handler.stop();
+            } else {
+                app.save_handler.set_to_save();
+            }
+            app.scene_handler.set(Scene::MainMenu);
+        }
+    }
+
+    #[profiling::function]
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        let font = &app.font;
+
+        let panel_position = Vector2::new(
+            (SCREEN_WIDTH as f32 - PANEL_WIDTH) / 2.,
+            (SCREEN_HEIGHT as f32 - PANEL_HEIGHT) / 2.,
+        );
+
+        app.texture_handler
+            .draw(rl, PANEL_TEXTURE, panel_position, Color::WHITE);
+
+        let title = app.locale.get(PAUSE_TITLE);
+        let text_size = get_text_size(font, title, PAUSE_TEXT_SIZE, PAUSE_TEXT_SPACING);
+
+        rl.draw_text_ex(
+            font,
+            title,
+            panel_position
+                + Vector2::new(
+                    PAUSE_BUTTON_TEXTURE_WIDTH - text_size.x / 2.,
+                    PANEL_TEXT_Y_OFFSET - text_size.y / 2.,
+                ),
+            PAUSE_TEXT_SIZE,
+            PAUSE_TEXT_SPACING,
+            CustomColor::BLACK_TEXT,
+        );
+
+        let mouse_position = app.input_handler.pointer();
+        for (index, button_rect) in self.buttons.iter().enumerate() {
+            let mouse_over = button_rect.rect.check_collision_point_rec(mouse_position);
+
+            let (texture_offset, text_offset) =
+                if mouse_over && app.input_handler.pointer_up(MouseButton::MOUSE_BUTTON_LEFT) {
+                    (PAUSE_BUTTON_TEXTURE_HEIGHT, -2.)
+                } else {
+                    (0., 0.)
+                };
+
+            let source = Rectangle::new(
+                0.,
+                texture_offset,
+                PAUSE_BUTTON_TEXTURE_WIDTH,
+                PAUSE_BUTTON_TEXTURE_HEIGHT,
+            );
+
+            let label = app.locale.get(PAUSE_BUTTON_LABELS[index]);
+            let text_size_button = get_text_size(font, label, PAUSE_TEXT_SIZE, PAUSE_TEXT_SPACING);
+
+            button_rect.draw_with_text_middle(
+                rl,
+                label,
+                font,
+                &app.texture_handler,
+                PAUSE_BUTTON_TEXTURE,
+                &source,
+                text_size_button,
+                &CustomColor::BLACK_TEXT,
+                PAUSE_TEXT_SIZE,
+                PAUSE_TEXT_SPACING,
+                Vector2::new(0., text_offset),
+                Vector2::zero(),
+            );
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use raylib::prelude::*;
+use serde::Deserialize;
+
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+const PRESETS_PATH: &str = "static/post_processing.json";
+const SHADERS_PATH: &str = "static/shaders/";
+
+// filled in from here every frame, the json doesn't get to set them
+const RESOLUTION_UNIFORM: &str = "resolution";
+const TIME_UNIFORM: &str = "time";
+
+pub const DEFAULT_PRESET: &str = "none";
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+enum UniformValue {
+    Float(f32),
+    Vector(Vec<f32>),
+}
+
+#[derive(Deserialize, Clone)]
+struct PassData {
+    shader: String,
+    #[serde(default)]
+    uniforms: HashMap<String, UniformValue>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PresetData {
+    pub id: String,
+    pub name: String,
+    passes: Vec<PassData>,
+}
+
+#[derive(Deserialize)]
+struct PresetsWrapper {
+    presets: Vec<PresetData>,
+}
+
+struct Pass {
+    shader: Shader,
+    resolution_location: i32,
+    time_location: i32,
+}
+
+struct Preset {
+    id: String,
+    passes: Vec<Pass>,
+}
+
+// the world is drawn into a buffer and then bounced between two of them, one shader per pass,
+// the last pass lands in the real target
+pub struct PostProcessing {
+    presets: Vec<Preset>,
+    buffers: [RenderTexture2D; 2],
+    skipped: Vec<String>,
+}
+
+// the settings menu only needs the names, so it can read them without any shaders around
+#[profiling::function]
+pub fn load_presets() -> Vec<PresetData> {
+    let Ok(string_json) = std::fs::read_to_string(PRESETS_PATH) else {
+        panic!("COULDN'T LOAD JSON FOR POST PROCESSING");
+    };
+    let Ok(wrapper) = serde_json::from_str::<PresetsWrapper>(&string_json) else {
+        panic!("COULDN'T PARSE JSON FOR POST PROCESSING");
+    };
+
+    return wrapper.presets;
+}
+
+impl PostProcessing {
+    #[profiling::function]
+    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
+        let mut skipped = Vec::new();
+        let presets = load_presets()
+            .into_iter()
+            .map(|preset| Preset {
+                passes: preset
+                    .passes
+                    .iter()
+                    .filter_map(|pass| match Self::load_pass(pass, rl, thread) {
+                        Ok(pass) => Some(pass),
+                        Err(path) => {
+                            println!("couldn't compile {path}, the pass is skipped");
+                            skipped.push(path);
+                            None
+                        }
+                    })
+                    .collect(),
+                id: preset.id,
+            })
+            .collect();
+
+        let buffers = [(); 2].map(|_| {
+            rl.load_render_texture(thread, SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32)
+                .expect("COULDN'T LOAD POST PROCESSING BUFFER")
+        });
+
+        Self {
+            presets,
+            buffers,
+            skipped,
+        }
+    }
+
+    // shaders that didn't compile
+    pub fn skipped(&self) -> &[String] {
+        &self.skipped
+    }
+
+    // a shader that doesn't compile comes back as raylib's default one, such a pass is just skipped
+    fn load_pass(
+        pass: &PassData,
+        rl: &mut RaylibHandle,
+        thread: &RaylibThread,
+    ) -> Result<Pass, String> {
+        let path = format!("{SHADERS_PATH}{}.fs", pass.shader);
+        let mut shader = rl.load_shader(thread, None, Some(&path));
+
+        if !shader.is_shader_valid() || shader.id == unsafe { ffi::rlGetShaderIdDefault() } {
+            return Err(path);
+        }
+
+        for (name, value) in pass.uniforms.iter() {
+            let location = shader.get_shader_location(name);
+            match value {
+                UniformValue::Float(x) => shader.set_shader_value(location, *x),
+                UniformValue::Vector(v) => match v.as_slice() {
+                    [x, y] => shader.set_shader_value(location, Vector2::new(*x, *y)),
+                    [x, y, z] => shader.set_shader_value(location, Vector3::new(*x, *y, *z)),
+                    [x, y, z, w] => shader.set_shader_value(location, Vector4::new(*x, *y, *z, *w)),
+                    _ => panic!("WRONG SIZE OF UNIFORM {name} IN {path}"),
+                },
+            }
+        }
+
+        Ok(Pass {
+            resolution_location: shader.get_shader_location(RESOLUTION_UNIFORM),
+            time_location: shader.get_shader_location(TIME_UNIFORM),
+            shader,
+        })
+    }
+
+    // draw_world ends up in target either way, through the preset with this id if there's one
+    #[profiling::function]
+    pub fn draw(
+        &mut self,
+        rl: &mut RaylibDrawHandle,
+        thread: &RaylibThread,
+        target: &mut RenderTexture2D,
+        preset: &str,
+        draw_world: impl FnOnce(&mut RaylibDrawHandle),
+    ) {
+        let passes = match self.presets.iter_mut().find(|p| p.id == preset) {
+            Some(found) if !found.passes.is_empty() => &mut found.passes,
+            _ => {
+                let mut t = rl.begin_texture_mode(thread, target);
+                draw_world(&mut t);
+                return;
+            }
+        };
+
+        let [first, second] = &mut self.buffers;
+        {
+            let mut t = rl.begin_texture_mode(thread, first);
+            t.clear_background(Color::BLANK);
+            draw_world(&mut t);
+        }
+
+        let resolution = Vector2::new(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32);
+        let time = rl.get_time() as f32;
+        let last = passes.len() - 1;
+
+        for (i, pass) in passes.iter_mut().enumerate() {
+            let (source, destination) = if i % 2 == 0 {
+                (&*first, &mut *second)
+            } else {
+                (&*second, &mut *first)
+            };
+            let destination = if i == last { &mut *target } else { destination };
+
+            pass.shader
+                .set_shader_value(pass.resolution_location, resolution);
+            pass.shader.set_shader_value(pass.time_location, time);
+
+            let mut t = rl.begin_texture_mode(thread, destination);
+            if i != last {
+                t.clear_background(Color::BLANK);
+            }
+            let mut s = t.begin_shader_mode(&mut pass.shader);
+            s.draw_texture_rec(
+                source,
+                Rectangle::new(0., 0., resolution.x, -resolution.y),
+                Vector2::zero(),
+                Color::WHITE,
+            );
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use std::{fs, sync::OnceLock};
+
+// settings, saves and everything else the player leaves behind
+const PROFILE_DIR: &str = "dynamic/";
+
+static ROOT: OnceLock<String> = OnceLock::new();
+
+// a script starts from the defaults in a fresh folder and never touches the player's files
+#[profiling::function]
+pub fn use_temp() {
+    let root = std::env::temp_dir().join(format!("veles_night_{}", std::process::id()));
+    let Some(root) = root.to_str() else {
+        panic!("TEMP DIR PATH ISN'T UTF-8");
+    };
+    let root = root.to_string() + "/";
+    fs::create_dir_all(&root).expect("COULDN'T CREATE TEMP PROFILE DIR");
+    ROOT.set(root).expect("PROFILE DIR IS ALREADY IN USE");
+}
+
+pub fn path(name: &str) -> String {
+    ROOT.get()
+        .map_or(PROFILE_DIR, |root| root.as_str())
+        .to_string()
+        + name
+}
@@ -0,0 +1,1020 @@
+use std::{
+    collections::{HashSet, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    enemy_spirit::EnemiesHandler,
+    map::{LEVEL_HEIGHT_TILES, LEVEL_WIDTH_TILES, Level, TileType},
+    metadata_handler::MetadataHandler,
+    modifier::Modifier,
+    spirits_handler::SpiritsHandler,
+};
+
+// same as the spirit patrol timer, used to turn portal seconds into ticks
+pub const PATROL_TICK: f32 = 0.5;
+
+// nothing we ship takes this long, looping spirits just run out of ticks
+const MAX_TICKS: usize = 256;
+
+// candidates() leaves the spirit open, pick_sacrifice() fills it in
+const ANY_SPIRIT: usize = usize::MAX;
+
+// position, one of 4 directions and the teleport cooldown
+const SPIRIT_STATES: usize = LEVEL_WIDTH_TILES * LEVEL_HEIGHT_TILES * 4 * 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FireKind {
+    TopDown,
+    LeftRight,
+    Stop,
+}
+
+impl FireKind {
+    // same order as the build buttons, so level N unlocks the first N kinds
+    pub const ALL: [FireKind; 3] = [FireKind::TopDown, FireKind::LeftRight, FireKind::Stop];
+
+    pub fn tile(&self) -> TileType {
+        match self {
+            FireKind::TopDown => TileType::FireTD {
+                active: false,
+                selected: false,
+            },
+            FireKind::LeftRight => TileType::FireLR {
+                active: false,
+                selected: false,
+            },
+            FireKind::Stop => TileType::FireStop {
+                active: false,
+                selected: false,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ActionKind {
+    Chop {
+        spirit: usize,
+        x: usize,
+        y: usize,
+    },
+    Light {
+        spirit: usize,
+        x: usize,
+        y: usize,
+    },
+    // building is only worth something once the fire is lit, so the search does both at once
+    BuildAndLight {
+        fire: FireKind,
+        spirit: usize,
+        x: usize,
+        y: usize,
+    },
+}
+
+impl ActionKind {
+    // how many clicks it takes the player
+    pub fn cost(&self) -> usize {
+        match self {
+            ActionKind::BuildAndLight { .. } => 2,
+            _ => 1,
+        }
+    }
+
+    // (spirit, x, y)
+    pub fn target(&self) -> (usize, usize, usize) {
+        match *self {
+            ActionKind::Chop { spirit, x, y }
+            | ActionKind::Light { spirit, x, y }
+            | ActionKind::BuildAndLight { spirit, x, y, .. } => (spirit, x, y),
+        }
+    }
+
+    pub fn with_spirit(&self, spirit: usize) -> ActionKind {
+        match *self {
+            ActionKind::Chop { x, y, .. } => ActionKind::Chop { spirit, x, y },
+            ActionKind::Light { x, y, .. } => ActionKind::Light { spirit, x, y },
+            ActionKind::BuildAndLight { fire, x, y, .. } => {
+                ActionKind::BuildAndLight { fire, spirit, x, y }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Action {
+    pub tick: usize,
+    pub kind: ActionKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SimSpirit {
+    pub key: usize,
+    pub x: i32,
+    pub y: i32,
+    pub dx: i32,
+    pub dy: i32,
+    pub teleported: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SimPortal {
+    pub x: i32,
+    pub y: i32,
+    pub dx: i32,
+    pub dy: i32,
+    pub left: u8,
+    pub interval: usize,
+    pub timer: usize,
+}
+
+// only moves on hard, along its row
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SimEnemy {
+    pub x: i32,
+    pub y: i32,
+    pub dx: i32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum StepOutcome {
+    Moved,
+    Turned,
+    Bounced(usize, usize),
+    Teleported,
+    Survived,
+    Died,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PathEnd {
+    Exit,
+    Death,
+    Loop,
+}
+
+pub struct SpiritPath {
+    pub end: PathEnd,
+    pub cells: Vec<(usize, usize)>,
+    pub bounced_on: Vec<(usize, usize)>,
+    visited: [u16; LEVEL_WIDTH_TILES], // a bit per row, same cells as above
+}
+
+impl SpiritPath {
+    fn visit(&mut self, x: usize, y: usize) {
+        if self.visited[x] & (1 << y) == 0 {
+            self.visited[x] |= 1 << y;
+            self.cells.push((x, y));
+        }
+    }
+
+    // would changing this tile change where the spirit goes
+    pub fn touches(&self, x: usize, y: usize) -> bool {
+        self.visited[x] & (1 << y) != 0 || self.bounced_on.contains(&(x, y))
+    }
+}
+
+// the level in tile units, stepping every spirit at once like the patrol timer does
+#[derive(Clone)]
+pub struct Puzzle {
+    pub tiles: [[TileType; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES],
+    pub spirits: Vec<SimSpirit>,
+    pub portals: Vec<SimPortal>,
+    pub enemies: Vec<SimEnemy>,
+    pub enemy_speed: f32, // enemy steps per tick, none unless they pace
+    pub wood: usize,
+    pub survived: usize,
+    pub survive: usize,
+    pub fires_available: usize,
+    pub safe_border: bool,
+    pub tick: usize,
+    event: bool,
+    next_key: usize,
+}
+
+impl Puzzle {
+    #[profiling::function]
+    pub fn new(
+        tiles: [[TileType; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES],
+        metadata_handler: &MetadataHandler,
+        fires_available: usize,
+    ) -> Self {
+        let mut spirits = Vec::new();
+        for group in metadata_handler.spirits.iter() {
+            for _ in 0..group.amount {
+                spirits.push(SimSpirit {
+                    key: spirits.len(),
+                    x: group.position[0] as i32,
+                    y: group.position[1] as i32,
+                    dx: group.direction[0] as i32,
+                    dy: group.direction[1] as i32,
+                    teleported: 0,
+                });
+            }
+        }
+
+        let patrol_tick = metadata_handler.modifier.patrol_tick();
+        let portals = metadata_handler
+            .portals
+            .iter()
+            .map(|portal| SimPortal {
+                x: portal.position[0] as i32,
+                y: portal.position[1] as i32,
+                dx: portal.direction[0] as i32,
+                dy: portal.direction[1] as i32,
+                left: portal.amount,
+                interval: Puzzle::seconds_to_ticks(portal.interval, patrol_tick),
+                timer: Puzzle::seconds_to_ticks(portal.delay, patrol_tick),
+            })
+            .collect();
+
+        let enemies = metadata_handler
+            .enemies
+            .iter()
+            .map(|enemy| SimEnemy {
+                x: enemy.position[0] as i32,
+                y: enemy.position[1] as i32,
+                dx: 1,
+            })
+            .collect();
+
+        let next_key = spirits.len();
+        let mut puzzle = Self {
+            tiles,
+            spirits,
+            portals,
+            enemies,
+            enemy_speed: Puzzle::enemy_speed(metadata_handler.modifier),
+            wood: 0,
+            survived: 0,
+            survive: metadata_handler
+                .modifier
+                .survive(metadata_handler.get_survive()),
+            fires_available: fires_available.min(FireKind::ALL.len()),
+            safe_border: metadata_handler.safe_border,
+            tick: 0,
+            event: false,
+            next_key,
+        };
+
+        for bonfire in metadata_handler.bonfires.iter() {
+            puzzle.set_fire_active(
+                bonfire.position[0] as usize,
+                bonfire.position[1] as usize,
+                bonfire.active,
+            );
+        }
+
+        return puzzle;
+    }
+
+    // snapshot of a level that is being played, spirit keys match SpiritsHandler
+    #[profiling::function]
+    pub fn from_level(
+        level: &Level,
+        spirits_handler: &SpiritsHandler,
+        enemies_handler: &EnemiesHandler,
+        fires_available: usize,
+    ) -> Self {
+        let mut spirits = Vec::new();
+        for (key, spirit) in spirits_handler.spirits.iter() {
+            // the ones already walking to a tree or a fire are as good as gone
+            if spirit.get_dead() || !spirit.is_patrolling() {
+                continue;
+            }
+            spirits.push(SimSpirit {
+                key: *key,
+                x: spirit.get_position().x.round() as i32,
+                y: spirit.get_position().y.round() as i32,
+                dx: spirit.get_direction().x as i32,
+                dy: spirit.get_direction().y as i32,
+                teleported: spirit.get_teleported(),
+            });
+        }
+        // HashMap order is random, the solver should see the same puzzle every time
+        spirits.sort_by_key(|spirit| spirit.key);
+
+        let patrol_tick = level.modifier.patrol_tick();
+        let portals = spirits_handler
+            .portals
+            .iter()
+            .map(|portal| SimPortal {
+                x: portal.position[0] as i32,
+                y: portal.position[1] as i32,
+                dx: portal.direction[0] as i32,
+                dy: portal.direction[1] as i32,
+                left: portal.left,
+                interval: Puzzle::seconds_to_ticks(portal.interval, patrol_tick),
+                timer: Puzzle::seconds_to_ticks(portal.timer, patrol_tick),
+            })
+            .collect();
+
+        let enemies = enemies_handler
+            .enemies
+            .values()
+            .map(|enemy| SimEnemy {
+                x: enemy.get_position().x.round() as i32,
+                y: enemy.get_position().y.round() as i32,
+                dx: enemy.get_direction().x as i32,
+            })
+            .collect();
+
+        let next_key = spirits_handler
+            .spirits
+            .keys()
+            .max()
+            .map_or(0, |key| key + 1);
+        Self {
+            tiles: level.tiles,
+            spirits,
+            portals,
+            enemies,
+            enemy_speed: Puzzle::enemy_speed(level.modifier),
+            wood: level.wood,
+            survived: level.survived,
+            survive: level.survive,
+            fires_available: fires_available.min(FireKind::ALL.len()),
+            safe_border: level.safe_border,
+            tick: 0,
+            event: false,
+            next_key,
+        }
+    }
+
+    fn enemy_speed(modifier: Modifier) -> f32 {
+        modifier
+            .enemy_tick()
+            .map_or(0., |tick| modifier.patrol_tick() / tick)
+    }
+
+    fn enemy_at(&self, x: i32, y: i32) -> bool {
+        self.enemies
+            .iter()
+            .any(|enemy| (enemy.x, enemy.y) == (x, y))
+    }
+
+    pub fn seconds_to_ticks(seconds: f32, patrol_tick: f32) -> usize {
+        (seconds.max(0.) / patrol_tick).ceil() as usize
+    }
+
+    pub fn pending_spawns(&self) -> usize {
+        self.portals.iter().map(|portal| portal.left as usize).sum()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.spirits.is_empty() && self.pending_spawns() == 0
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.is_finished() && self.survived >= self.survive
+    }
+
+    // even if every spirit left walks out it won't be enough
+    pub fn is_hopeless(&self) -> bool {
+        self.survived + self.spirits.len() + self.pending_spawns() < self.survive
+    }
+
+    // the outcome if nobody touches the map anymore, paths are enough without portals
+    pub fn wins_untouched(&self) -> bool {
+        if self.pending_spawns() > 0 {
+            let mut puzzle = self.clone();
+            while !puzzle.is_finished() && puzzle.tick < MAX_TICKS {
+                puzzle.tick();
+            }
+            return puzzle.is_won();
+        }
+
+        let mut exits = 0;
+        for spirit in self.spirits.iter() {
+            match self.spirit_path(spirit).end {
+                PathEnd::Exit => exits += 1,
+                PathEnd::Death => {}
+                // a looping spirit never lets the level end
+                PathEnd::Loop => return false,
+            }
+        }
+
+        self.survived + exits >= self.survive
+    }
+
+    // which spirit to spend on the action and whether the level is won if nobody does anything after.
+    // one that would loop forever goes first, then one that dies anyway, then anyone.
+    // only re-walks the spirits whose path goes through the changed tile
+    fn pick_sacrifice(&self, kind: ActionKind, paths: &[SpiritPath]) -> Option<(ActionKind, bool)> {
+        let mut child = self.clone();
+        child.apply(kind);
+
+        let (_, x, y) = kind.target();
+        let mut exits = Vec::new();
+        let mut deaths = Vec::new();
+        let mut loops = Vec::new();
+        for (spirit, path) in self.spirits.iter().zip(paths) {
+            let end = if path.touches(x, y) {
+                child.spirit_path(spirit).end
+            } else {
+                path.end
+            };
+            match end {
+                PathEnd::Exit => exits.push(spirit.key),
+                PathEnd::Death => deaths.push(spirit.key),
+                PathEnd::Loop => loops.push(spirit.key),
+            }
+        }
+
+        let sacrifice = *loops.first().or(deaths.first()).or(exits.first())?;
+        let kind = kind.with_spirit(sacrifice);
+
+        if child.pending_spawns() > 0 {
+            child.remove_spirit(sacrifice);
+            return Some((kind, child.wins_untouched()));
+        }
+
+        if loops.len() > 1 {
+            return Some((kind, false));
+        }
+        let exits = exits.len() - (loops.is_empty() && deaths.is_empty()) as usize;
+        Some((kind, child.survived + exits >= child.survive))
+    }
+
+    fn set_fire_active(&mut self, x: usize, y: usize, value: bool) {
+        match &mut self.tiles[x][y] {
+            TileType::FireTD { active, .. }
+            | TileType::FireLR { active, .. }
+            | TileType::FireStop { active, .. } => *active = value,
+            _ => panic!("NO FIRE TO LIGHT AT {x} {y}"),
+        }
+    }
+
+    fn remove_spirit(&mut self, key: usize) {
+        self.spirits.retain(|spirit| spirit.key != key);
+    }
+
+    #[profiling::function]
+    pub fn apply(&mut self, kind: ActionKind) {
+        match kind {
+            ActionKind::Chop { spirit, x, y } => {
+                self.tiles[x][y] = TileType::Air { selected: false };
+                self.wood += 1;
+                self.remove_spirit(spirit);
+            }
+            ActionKind::Light { spirit, x, y } => {
+                self.set_fire_active(x, y, true);
+                self.remove_spirit(spirit);
+            }
+            ActionKind::BuildAndLight { fire, spirit, x, y } => {
+                self.tiles[x][y] = fire.tile();
+                self.wood -= 1;
+                self.set_fire_active(x, y, true);
+                self.remove_spirit(spirit);
+            }
+        }
+    }
+
+    // mirrors Spirit::patrol, keep the two in sync
+    fn step(
+        tiles: &[[TileType; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES],
+        safe_border: bool,
+        spirit: &mut SimSpirit,
+    ) -> StepOutcome {
+        let (tile_x, tile_y) = (spirit.x as usize, spirit.y as usize);
+        let mut next = (spirit.x + spirit.dx, spirit.y + spirit.dy);
+        let (next_x, next_y) = (next.0.max(0) as usize, next.1.max(0) as usize);
+
+        if spirit.teleported != 0 {
+            spirit.teleported -= 1;
+        }
+
+        match tiles[tile_x][tile_y] {
+            TileType::FireTD { active: true, .. } if spirit.dy == 0 => {
+                (spirit.dx, spirit.dy) = (0, 1);
+                return StepOutcome::Turned;
+            }
+            TileType::FireLR { active: true, .. } if spirit.dx == 0 => {
+                (spirit.dx, spirit.dy) = (1, 0);
+                return StepOutcome::Turned;
+            }
+            TileType::Exit(_) => return StepOutcome::Survived,
+            TileType::Swamp { teleport_position } if spirit.teleported == 0 => {
+                spirit.teleported = 2;
+                next = (teleport_position.x as i32, teleport_position.y as i32);
+            }
+            _ => {}
+        }
+
+        let off_map =
+            next.0 < 0 || next.1 < 0 || next_x >= LEVEL_WIDTH_TILES || next_y >= LEVEL_HEIGHT_TILES;
+        if spirit.teleported <= 1 && safe_border && off_map {
+            (spirit.dx, spirit.dy) = (-spirit.dx, -spirit.dy);
+            return StepOutcome::Turned;
+        }
+
+        if spirit.teleported <= 1
+            && !safe_border
+            && (next_x >= LEVEL_WIDTH_TILES
+                || next_y >= LEVEL_HEIGHT_TILES
+                || tile_x == 0
+                || tile_y == 0)
+        {
+            return StepOutcome::Died;
+        }
+
+        if spirit.teleported <= 1 {
+            match tiles[next_x][next_y] {
+                TileType::Tree { .. } | TileType::FireStop { active: true, .. } => {
+                    (spirit.dx, spirit.dy) = (-spirit.dx, -spirit.dy);
+                    return StepOutcome::Bounced(next_x, next_y);
+                }
+                _ => {}
+            }
+        }
+
+        (spirit.x, spirit.y) = next;
+        if spirit.teleported == 2 {
+            return StepOutcome::Teleported;
+        }
+        StepOutcome::Moved
+    }
+
+    #[profiling::function]
+    pub fn tick(&mut self) {
+        self.event = false;
+
+        // faster than the spirits, so some ticks they take two steps
+        let steps = ((self.tick + 1) as f32 * self.enemy_speed) as usize
+            - (self.tick as f32 * self.enemy_speed) as usize;
+        for _ in 0..steps {
+            for enemy in self.enemies.iter_mut() {
+                let next = enemy.x + enemy.dx;
+                let open = next >= 0
+                    && (next as usize) < LEVEL_WIDTH_TILES
+                    && matches!(
+                        self.tiles[next as usize][enemy.y as usize],
+                        TileType::Air { .. }
+                    );
+                if open {
+                    enemy.x = next;
+                } else {
+                    enemy.dx = -enemy.dx;
+                }
+            }
+        }
+
+        for i in 0..self.portals.len() {
+            let portal = &mut self.portals[i];
+            if portal.left == 0 {
+                continue;
+            }
+            if portal.timer == 0 {
+                portal.left -= 1;
+                portal.timer = portal.interval;
+                let spirit = SimSpirit {
+                    key: self.next_key,
+                    x: portal.x,
+                    y: portal.y,
+                    dx: portal.dx,
+                    dy: portal.dy,
+                    teleported: 0,
+                };
+                self.spirits.push(spirit);
+                self.next_key += 1;
+                self.event = true;
+            }
+            let portal = &mut self.portals[i];
+            if portal.timer > 0 {
+                portal.timer -= 1;
+            }
+        }
+
+        let mut survived = 0;
+        let tiles = &self.tiles;
+        let safe_border = self.safe_border;
+        let enemies = &self.enemies;
+        let enemy_at = |x: i32, y: i32| enemies.iter().any(|enemy| (enemy.x, enemy.y) == (x, y));
+        let mut event = false;
+        self.spirits.retain_mut(|spirit| {
+            let outcome = Puzzle::step(tiles, safe_border, spirit);
+            if outcome != StepOutcome::Moved {
+                event = true;
+            }
+            match outcome {
+                StepOutcome::Survived => {
+                    survived += 1;
+                    return false;
+                }
+                StepOutcome::Died => return false,
+                _ => {}
+            }
+            if enemy_at(spirit.x, spirit.y) {
+                event = true;
+                return false;
+            }
+            true
+        });
+
+        self.survived += survived;
+        self.event |= event;
+        self.tick += 1;
+    }
+
+    fn spirit_state(spirit: &SimSpirit) -> usize {
+        let direction = match (spirit.dx, spirit.dy) {
+            (1, _) => 0,
+            (-1, _) => 1,
+            (_, 1) => 2,
+            _ => 3,
+        };
+        let cell = spirit.x as usize * LEVEL_HEIGHT_TILES + spirit.y as usize;
+        (cell * 4 + direction) * 3 + spirit.teleported as usize
+    }
+
+    pub fn spirit_paths(&self) -> Vec<SpiritPath> {
+        self.spirits
+            .iter()
+            .map(|spirit| self.spirit_path(spirit))
+            .collect()
+    }
+
+    // where a single spirit goes if nobody touches the map
+    #[profiling::function]
+    pub fn spirit_path(&self, spirit: &SimSpirit) -> SpiritPath {
+        let mut spirit = *spirit;
+        let mut seen = [false; SPIRIT_STATES];
+        let mut path = SpiritPath {
+            end: PathEnd::Loop,
+            cells: Vec::new(),
+            bounced_on: Vec::new(),
+            visited: [0; LEVEL_WIDTH_TILES],
+        };
+        path.visit(spirit.x as usize, spirit.y as usize);
+
+        for _ in self.tick..MAX_TICKS {
+            let state = Puzzle::spirit_state(&spirit);
+            if seen[state] {
+                return path;
+            }
+            seen[state] = true;
+
+            match Puzzle::step(&self.tiles, self.safe_border, &mut spirit) {
+                StepOutcome::Survived => {
+                    path.end = PathEnd::Exit;
+                    return path;
+                }
+                StepOutcome::Died => {
+                    path.end = PathEnd::Death;
+                    return path;
+                }
+                StepOutcome::Bounced(x, y) if !path.bounced_on.contains(&(x, y)) => {
+                    path.bounced_on.push((x, y));
+                }
+                _ => {}
+            }
+
+            // a pacing enemy is taken where it is now, close enough for picking what to try
+            if self.enemy_at(spirit.x, spirit.y) {
+                path.end = PathEnd::Death;
+                return path;
+            }
+            path.visit(spirit.x as usize, spirit.y as usize);
+        }
+
+        return path;
+    }
+
+    // actions worth trying right now, anything off the spirits' paths can't change the outcome
+    #[profiling::function]
+    pub fn candidates(&self, paths: &[SpiritPath]) -> Vec<ActionKind> {
+        let mut path_cells = Vec::new();
+        let mut bounce_trees = Vec::new();
+        // grids next to the lists so checking for duplicates stays cheap
+        let mut on_path = [[false; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES];
+        let mut listed_tree = [[false; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES];
+
+        for path in paths.iter() {
+            for &cell in path.cells.iter() {
+                if !on_path[cell.0][cell.1] {
+                    on_path[cell.0][cell.1] = true;
+                    path_cells.push(cell);
+                }
+            }
+            for &tree in path.bounced_on.iter() {
+                if !listed_tree[tree.0][tree.1] {
+                    listed_tree[tree.0][tree.1] = true;
+                    bounce_trees.push(tree);
+                }
+            }
+        }
+
+        let mut candidates = Vec::new();
+
+        for &(x, y) in path_cells.iter() {
+            match self.tiles[x][y] {
+                TileType::FireTD { active: false, .. }
+                | TileType::FireLR { active: false, .. }
+                | TileType::FireStop { active: false, .. } => {
+                    candidates.push(ActionKind::Light {
+                        spirit: ANY_SPIRIT,
+                        x,
+                        y,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        // a new fire turns spirits down or right, the first tree that way is worth opening up too
+        let mut turn_trees = Vec::new();
+        for &(x, y) in path_cells.iter() {
+            for (dx, dy) in [(0, 1), (1, 0)] {
+                let (mut tree_x, mut tree_y) = (x + dx, y + dy);
+                while tree_x < LEVEL_WIDTH_TILES
+                    && tree_y < LEVEL_HEIGHT_TILES
+                    && matches!(self.tiles[tree_x][tree_y], TileType::Air { .. })
+                {
+                    (tree_x, tree_y) = (tree_x + dx, tree_y + dy);
+                }
+                if tree_x < LEVEL_WIDTH_TILES
+                    && tree_y < LEVEL_HEIGHT_TILES
+                    && matches!(self.tiles[tree_x][tree_y], TileType::Tree { .. })
+                    && !listed_tree[tree_x][tree_y]
+                {
+                    listed_tree[tree_x][tree_y] = true;
+                    turn_trees.push((tree_x, tree_y));
+                }
+            }
+        }
+
+        // chopping what a spirit bumps into opens the way through
+        for &(x, y) in bounce_trees.iter().chain(turn_trees.iter()) {
+            candidates.push(ActionKind::Chop {
+                spirit: ANY_SPIRIT,
+                x,
+                y,
+            });
+        }
+
+        // any tree nobody bumps into is as good as any other for wood
+        'spare: for (x, column) in self.tiles.iter().enumerate() {
+            for (y, tile) in column.iter().enumerate() {
+                if let TileType::Tree { .. } = tile
+                    && !listed_tree[x][y]
+                {
+                    candidates.push(ActionKind::Chop {
+                        spirit: ANY_SPIRIT,
+                        x,
+                        y,
+                    });
+                    break 'spare;
+                }
+            }
+        }
+
+        if self.wood > 0 {
+            for &(x, y) in path_cells.iter() {
+                if let TileType::Air { .. } = self.tiles[x][y] {
+                    for &fire in FireKind::ALL[..self.fires_available].iter() {
+                        candidates.push(ActionKind::BuildAndLight {
+                            fire,
+                            spirit: ANY_SPIRIT,
+                            x,
+                            y,
+                        });
+                    }
+                }
+            }
+        }
+
+        candidates
+    }
+
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for column in self.tiles.iter() {
+            for tile in column.iter() {
+                let code: u8 = match tile {
+                    TileType::Air { .. } => 0,
+                    TileType::Tree { .. } => 1,
+                    TileType::FireTD { active, .. } => 2 + *active as u8,
+                    TileType::FireLR { active, .. } => 4 + *active as u8,
+                    TileType::FireStop { active, .. } => 6 + *active as u8,
+                    TileType::Swamp { .. } => 8,
+                    TileType::Portal { .. } => 9,
+                    TileType::Exit(_) => 10,
+                };
+                code.hash(&mut hasher);
+            }
+        }
+
+        let mut spirits: Vec<(i32, i32, i32, i32, u8)> = self
+            .spirits
+            .iter()
+            .map(|spirit| (spirit.x, spirit.y, spirit.dx, spirit.dy, spirit.teleported))
+            .collect();
+        spirits.sort();
+        spirits.hash(&mut hasher);
+
+        self.portals.hash(&mut hasher);
+        self.enemies.hash(&mut hasher);
+        self.wood.hash(&mut hasher);
+        self.survived.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
+
+pub enum SolveResult {
+    Solved(Vec<Action>),
+    // nothing up to the action budget, spirits are spent greedily so this is a strong guess and not a proof
+    Unsolvable,
+    // ran out of nodes before we could tell
+    Unknown,
+}
+
+pub struct Solver {
+    node_budget: usize,
+    nodes: usize,
+    visited: HashSet<(u64, usize)>,
+    act_now: bool, // only act at the very start, no waiting for spirits to move
+}
+
+impl Solver {
+    pub fn new(node_budget: usize) -> Self {
+        Self {
+            node_budget,
+            nodes: 0,
+            visited: HashSet::new(),
+            act_now: false,
+        }
+    }
+
+    // iterative deepening, so the first solution uses the fewest actions.
+    // most maps are solved by setting everything up right away, that pass is a lot cheaper
+    // so it goes first, even if waiting for the spirits could take fewer actions
+    #[profiling::function]
+    pub fn solve(&mut self, puzzle: &Puzzle, max_actions: usize) -> SolveResult {
+        self.nodes = 0;
+
+        for act_now in [true, false] {
+            self.act_now = act_now;
+            for actions in 0..=max_actions {
+                self.visited.clear();
+                if let Some(mut solution) = self.search(puzzle.clone(), actions) {
+                    solution.reverse();
+                    return SolveResult::Solved(solution);
+                }
+                if self.nodes >= self.node_budget {
+                    return SolveResult::Unknown;
+                }
+            }
+        }
+
+        return SolveResult::Unsolvable;
+    }
+
+    // returns the actions in reverse order
+    fn search(&mut self, mut puzzle: Puzzle, actions_left: usize) -> Option<Vec<Action>> {
+        loop {
+            if puzzle.is_finished() {
+                return if puzzle.is_won() { Some(vec![]) } else { None };
+            }
+            if puzzle.is_hopeless() || puzzle.tick >= MAX_TICKS {
+                return None;
+            }
+
+            if actions_left == 0 {
+                return if puzzle.wins_untouched() {
+                    Some(vec![])
+                } else {
+                    None
+                };
+            }
+
+            // between events every spirit just keeps walking, acting then is the same as acting earlier
+            if puzzle.tick == 0 || (puzzle.event && !self.act_now) {
+                // seen this exact state already, with the same budget it goes the same way
+                if !self.visited.insert((puzzle.state_hash(), actions_left)) {
+                    return None;
+                }
+
+                self.nodes += 1;
+                if self.nodes >= self.node_budget {
+                    return None;
+                }
+
+                let paths = puzzle.spirit_paths();
+                for kind in puzzle.candidates(&paths) {
+                    if kind.cost() > actions_left {
+                        continue;
+                    }
+                    let Some((kind, wins)) = puzzle.pick_sacrifice(kind, &paths) else {
+                        continue;
+                    };
+                    // the last action, no need to go down a level to see how it ends
+                    if kind.cost() == actions_left {
+                        if wins {
+                            return Some(vec![Action {
+                                tick: puzzle.tick,
+                                kind,
+                            }]);
+                        }
+                        continue;
+                    }
+                    let mut child = puzzle.clone();
+                    child.apply(kind);
+                    child.event = true;
+                    if let Some(mut rest) = self.search(child, actions_left - kind.cost()) {
+                        rest.push(Action {
+                            tick: puzzle.tick,
+                            kind,
+                        });
+                        return Some(rest);
+                    }
+                    if self.nodes >= self.node_budget {
+                        return None;
+                    }
+                }
+            }
+
+            if self.act_now {
+                return None;
+            }
+            puzzle.tick();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata_handler::SpiritMetadata;
+
+    const ROW: usize = 2;
+    const EXIT_X: usize = 9;
+    const BLOCK_X: usize = 5;
+
+    // a corridor of trees with an exit at the end and one tree in the way
+    fn corridor(spirits: u8, survive: usize) -> Puzzle {
+        let mut tiles = [[TileType::Tree {
+            chance: 0,
+            selected: false,
+        }; LEVEL_HEIGHT_TILES]; LEVEL_WIDTH_TILES];
+        for column in tiles.iter_mut().take(EXIT_X).skip(1) {
+            column[ROW] = TileType::Air { selected: false };
+        }
+        tiles[BLOCK_X][ROW] = TileType::Tree {
+            chance: 0,
+            selected: false,
+        };
+        tiles[EXIT_X][ROW] = TileType::Exit('>');
+
+        let metadata = MetadataHandler {
+            survive,
+            spirits: vec![SpiritMetadata {
+                position: [1, ROW as u8],
+                amount: spirits,
+                direction: [1, 0],
+            }],
+            swamps: Vec::new(),
+            enemies: Vec::new(),
+            bonfires: Vec::new(),
+            portals: Vec::new(),
+            modifier: Modifier::Standard,
+            safe_border: false,
+        };
+        Puzzle::new(tiles, &metadata, 0)
+    }
+
+    #[test]
+    fn blocked_spirits_loop() {
+        let puzzle = corridor(2, 1);
+        assert!(!puzzle.wins_untouched());
+        assert!(
+            puzzle
+                .spirit_paths()
+                .iter()
+                .all(|path| path.end == PathEnd::Loop)
+        );
+    }
+
+    #[test]
+    fn solves_by_chopping_the_tree() {
+        let puzzle = corridor(2, 1);
+        let SolveResult::Solved(solution) = Solver::new(10_000).solve(&puzzle, 2) else {
+            panic!("THE CORRIDOR HAS A ONE CHOP SOLUTION");
+        };
+        assert_eq!(solution.len(), 1);
+        let (_, x, y) = solution[0].kind.target();
+        assert!(matches!(solution[0].kind, ActionKind::Chop { .. }));
+        assert_eq!((x, y), (BLOCK_X, ROW));
+    }
+
+    #[test]
+    fn reports_unsolvable() {
+        // chopping costs one of the two spirits, so both can never get out
+        let puzzle = corridor(2, 2);
+        assert!(matches!(
+            Solver::new(100_000).solve(&puzzle, 2),
+            SolveResult::Unsolvable
+        ));
+    }
+}
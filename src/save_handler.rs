@@ -3,19 +3,14 @@ use std::fs;
 use raylib::prelude::*;
 
 use crate::{
-    dialogue::DialogueHandler,
-    enemy_spirit::EnemiesHandler,
-    level_transition::LevelTransition,
-    map::Level,
+    game::Game,
     map_loader::MapLoader,
-    metadata_handler::MetadataHandler,
+    profile,
     scene::{Scene, SceneHandler},
     settings::SettingsHandler,
-    spirits_handler::SpiritsHandler,
-    ui::UIHandler,
 };
 
-pub const SAVE_PATH: &str = "dynamic/save/";
+pub const SAVE_PATH: &str = "save/";
 
 pub struct SaveHandler {
     pub should_save: bool,
@@ -45,7 +40,7 @@ impl SaveHandler {
 
     #[profiling::function]
     pub fn check_saves(&mut self) {
-        let save_p = &SAVE_PATH.to_string();
+        let save_p = &profile::path(SAVE_PATH);
 
         let mut dir;
         match fs::read_dir(save_p) {
@@ -61,7 +56,7 @@ impl SaveHandler {
 
     #[profiling::function]
     pub fn get_level_number() -> u8 {
-        let filenames = fs::read_dir(SAVE_PATH).unwrap();
+        let filenames = fs::read_dir(profile::path(SAVE_PATH)).unwrap();
 
         for filename in filenames {
             let file = match filename {
@@ -86,46 +81,27 @@ impl SaveHandler {
     #[profiling::function]
     pub fn load_save(
         &mut self,
-        metadata_handler: &mut MetadataHandler,
-        level: &mut Level,
-        spirits_handler: &mut SpiritsHandler,
-        enemies_handler: &mut EnemiesHandler,
-        ui_handler: &mut UIHandler,
-        level_number: &mut u8,
-        level_transition: &mut LevelTransition,
+        game: &mut Game,
         rl: &mut RaylibHandle,
         scene_handler: &mut SceneHandler,
-        dialogue_handler: &mut DialogueHandler,
-        settings_handler: &mut SettingsHandler,
+        settings_handler: &SettingsHandler,
     ) {
-        metadata_handler.load_save();
-
-        *level_number = SaveHandler::get_level_number();
-        level_transition.set_cards(*level_number as usize);
-        level.load_save(level_number.clone(), metadata_handler, rl); //need other function call
-        spirits_handler.spawn_spirits(metadata_handler, settings_handler);
-        enemies_handler.spawn_enemies(metadata_handler, settings_handler);
+        game.load_save(rl, settings_handler);
         scene_handler.set(Scene::Level);
-        *ui_handler = UIHandler::new(
-            level_number.clone() as usize,
-            settings_handler.settings.pixel_scale as f32,
-        );
-        dialogue_handler.load_dialogue(&format!("level_{}", *level_number + 1));
         self.should_load = false;
     }
 
     #[profiling::function]
-    pub fn create_save_file(
-        &mut self,
-        metadata_handler: &mut MetadataHandler,
-        level: &mut Level,
-        spirits_handler: &mut SpiritsHandler,
-        level_number: &mut u8,
-        settings_handler: &mut SettingsHandler,
-    ) {
-        MapLoader::save_map(*level_number, level, metadata_handler);
-        metadata_handler.change_spirits(spirits_handler, settings_handler);
-        metadata_handler.save(*level_number);
+    pub fn create_save_file(&mut self, game: &mut Game) {
+        MapLoader::save_map(
+            game.level_number,
+            &mut game.level,
+            &mut game.metadata_handler,
+        );
+        game.metadata_handler.change_spirits(&game.spirits_handler);
+        game.metadata_handler.change_portals(&game.spirits_handler);
+        game.metadata_handler.save(game.level_number);
+        game.dialogue_handler.save_progress(game.level_number);
         self.should_save = false;
     }
 }
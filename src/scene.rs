@@ -1,8 +1,25 @@
+use std::collections::VecDeque;
+
 use raylib::prelude::*;
+use serde::Deserialize;
 
-use crate::{SCREEN_HEIGHT, SCREEN_WIDTH, settings::SettingsHandler};
+use crate::{
+    SCREEN_HEIGHT, SCREEN_WIDTH,
+    achievements::AchievementGallery,
+    app::App,
+    controls_menu::ControlsMenu,
+    daily::{DailyLoadingScene, DailyResultScene},
+    game::LevelScene,
+    gameover_handler::{GameOverHandler, GameOverHandlerType},
+    history::HistoryScene,
+    level_selection::LevelSelector,
+    level_transition::TransitionScene,
+    main_menu::MainMenuHandler,
+    pause_menu::PauseMenuHandler,
+    settings_menu::SettingsMenuHandler,
+};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
 pub enum Scene {
     MainMenu,
     Level,
@@ -11,48 +28,258 @@ pub enum Scene {
     GameEnd,
     LevelSelection,
     Settings,
+    DailyLoading,
+    DailyResult,
+    Achievements,
+    Pause,
+    History,
+    Controls,
 }
 
-#[derive(Debug)]
-pub struct SceneHandler {
-    current: Scene,
-    next: Option<Scene>,
+// what a scene does while it's on the stack, only the top one gets updated
+pub trait SceneState {
+    // also when whatever was pushed over it gets popped
+    fn enter(&mut self, _app: &mut App, _rl: &mut RaylibHandle) {}
+    fn exit(&mut self, _app: &mut App, _rl: &mut RaylibHandle) {}
+
+    fn update(&mut self, app: &mut App, rl: &mut RaylibHandle);
+
+    // the part that goes through post processing
+    fn draw_world(&mut self, _app: &mut App, _rl: &mut RaylibDrawHandle) {}
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle);
+
+    // scenes under an overlay keep being drawn, just not updated
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    // None keeps whatever the window had before
+    fn title(&self, _app: &App, _rl: &RaylibHandle) -> Option<String> {
+        None
+    }
+}
+
+// one of each, the stack in SceneHandler only holds which ones are up
+pub struct Scenes {
+    pub main_menu: MainMenuHandler,
+    pub settings_menu: SettingsMenuHandler,
+    pub level_selector: LevelSelector,
+    pub gameover: GameOverHandler,
+    pub gameend: GameOverHandler,
+    pub pause_menu: PauseMenuHandler,
+    level: LevelScene,
+    transition: TransitionScene,
+    daily_loading: DailyLoadingScene,
+    daily_result: DailyResultScene,
+    achievements: AchievementGallery,
+    history: HistoryScene,
+    controls: ControlsMenu,
+}
+
+impl Scenes {
+    #[profiling::function]
+    pub fn new() -> Self {
+        Self {
+            main_menu: MainMenuHandler::new(),
+            settings_menu: SettingsMenuHandler::new(),
+            level_selector: LevelSelector::new(),
+            gameover: GameOverHandler::new(GameOverHandlerType::Level),
+            gameend: GameOverHandler::new(GameOverHandlerType::Game),
+            pause_menu: PauseMenuHandler::new(),
+            level: LevelScene,
+            transition: TransitionScene::new(),
+            daily_loading: DailyLoadingScene,
+            daily_result: DailyResultScene,
+            achievements: AchievementGallery,
+            history: HistoryScene::new(),
+            controls: ControlsMenu::new(),
+        }
+    }
+
+    pub fn get(&mut self, scene: Scene) -> &mut dyn SceneState {
+        match scene {
+            Scene::MainMenu => &mut self.main_menu,
+            Scene::Level => &mut self.level,
+            Scene::Transition => &mut self.transition,
+            Scene::GameOver => &mut self.gameover,
+            Scene::GameEnd => &mut self.gameend,
+            Scene::LevelSelection => &mut self.level_selector,
+            Scene::Settings => &mut self.settings_menu,
+            Scene::DailyLoading => &mut self.daily_loading,
+            Scene::DailyResult => &mut self.daily_result,
+            Scene::Achievements => &mut self.achievements,
+            Scene::Pause => &mut self.pause_menu,
+            Scene::History => &mut self.history,
+            Scene::Controls => &mut self.controls,
+        }
+    }
+
+    // from the topmost scene that covers the whole screen up to the top
+    pub fn visible(&mut self, stack: &[Scene]) -> Vec<Scene> {
+        let mut start = stack.len().saturating_sub(1);
+        while start > 0 && self.get(stack[start]).is_overlay() {
+            start -= 1;
+        }
+        stack[start..].to_vec()
+    }
+
+    // exits go top down, so a scene can still see what it was covering
+    #[profiling::function]
+    pub fn swap(&mut self, swap: SceneSwap, app: &mut App, rl: &mut RaylibHandle) {
+        for scene in swap.exited {
+            self.get(scene).exit(app, rl);
+        }
+        if let Some(scene) = swap.entered {
+            self.get(scene).enter(app, rl);
+        }
+    }
+}
+
+pub enum SceneChange {
+    Set(Scene),
+    Push(Scene),
+    Pop,
+}
+
+pub struct SceneSwap {
+    pub exited: Vec<Scene>,
+    pub entered: Option<Scene>,
+}
+
+// anything that hides one scene and shows the next, the swap happens once it's covered
+pub trait SceneTransition {
+    fn update(&mut self, frame_time: f32);
+    fn covered(&self) -> bool;
+    fn finished(&self) -> bool;
+    fn draw(&self, rl: &mut RaylibDrawHandle);
+}
+
+pub struct Fade {
     progress: f32,
     fade_in: bool,
-    initiated: bool,
+}
+
+impl Fade {
+    const FADE_SPEED: f32 = 4.;
+
+    pub fn new() -> Self {
+        Self {
+            progress: 0.,
+            fade_in: true,
+        }
+    }
+}
+
+impl SceneTransition for Fade {
+    fn update(&mut self, frame_time: f32) {
+        if self.fade_in {
+            self.progress += Self::FADE_SPEED * frame_time;
+            if self.progress >= 1.0 {
+                self.progress = 1.;
+                self.fade_in = false;
+            }
+        } else {
+            self.progress -= Self::FADE_SPEED * frame_time;
+        }
+    }
+
+    fn covered(&self) -> bool {
+        !self.fade_in
+    }
+
+    fn finished(&self) -> bool {
+        !self.fade_in && self.progress <= 0.
+    }
+
+    fn draw(&self, rl: &mut RaylibDrawHandle) {
+        rl.draw_rectangle_v(
+            Vector2::zero(),
+            Vector2::new(SCREEN_WIDTH as f32, SCREEN_HEIGHT as f32),
+            Color::BLACK.alpha(self.progress.max(0.)),
+        );
+    }
+}
+
+// for overlays that should just pop up
+pub struct Cut;
+
+impl SceneTransition for Cut {
+    fn update(&mut self, _frame_time: f32) {}
+
+    fn covered(&self) -> bool {
+        true
+    }
+
+    fn finished(&self) -> bool {
+        true
+    }
+
+    fn draw(&self, _rl: &mut RaylibDrawHandle) {}
+}
+
+pub struct SceneHandler {
+    stack: Vec<Scene>,
+    next: Option<SceneChange>,
+    queued: VecDeque<SceneChange>, // asked for while another one was still waiting to swap in
+    transition: Option<Box<dyn SceneTransition>>,
 }
 
 impl SceneHandler {
     #[profiling::function]
     pub fn new() -> Self {
         Self {
-            current: Scene::MainMenu,
+            stack: vec![Scene::MainMenu],
             next: None,
-            progress: 0.,
-            fade_in: true,
-            initiated: false,
+            queued: VecDeque::new(),
+            transition: None,
         }
     }
 
     #[profiling::function]
     pub fn get_current(&self) -> Scene {
-        self.current
+        *self.stack.last().unwrap()
     }
 
+    // the scene at the bottom, what the player is actually in under any menus
+    #[profiling::function]
+    pub fn get_base(&self) -> Scene {
+        self.stack[0]
+    }
+
+    pub fn get_stack(&self) -> &[Scene] {
+        &self.stack
+    }
+
+    // whatever the stack had, it's just this scene now
     #[profiling::function]
     pub fn set(&mut self, scene: Scene) {
-        if self.initiated {
+        self.change(SceneChange::Set(scene), Box::new(Fade::new()));
+    }
+
+    #[profiling::function]
+    pub fn push(&mut self, scene: Scene) {
+        self.change(SceneChange::Push(scene), Box::new(Fade::new()));
+    }
+
+    #[profiling::function]
+    pub fn pop(&mut self) {
+        self.change(SceneChange::Pop, Box::new(Fade::new()));
+    }
+
+    // a change asked for while another is waiting goes after it, under the same transition
+    #[profiling::function]
+    pub fn change(&mut self, change: SceneChange, transition: Box<dyn SceneTransition>) {
+        if self.next.is_some() {
+            self.queued.push_back(change);
             return;
         }
-        self.initiated = true;
-        self.fade_in = true;
-        self.progress = 0.;
-        self.next = Some(scene);
+        self.next = Some(change);
+        self.transition = Some(transition);
     }
 
     #[profiling::function]
     pub fn get_next(&self) -> Scene {
-        match self.current {
+        match self.get_current() {
             Scene::Level => Scene::Transition,
             Scene::Transition => Scene::Level,
             Scene::MainMenu => Scene::Level,
@@ -61,39 +288,67 @@ impl SceneHandler {
         }
     }
 
-    const FADE_SPEED: f32 = 4.;
-
+    // hands back what got swapped so the scenes can run their enter and exit
     #[profiling::function]
-    pub fn update(&mut self, rl: &mut RaylibHandle) {
-        if self.fade_in {
-            if let Some(next) = self.next {
-                self.progress += Self::FADE_SPEED * rl.get_frame_time();
-
-                if self.progress >= 1.0 {
-                    self.fade_in = false;
-                    self.current = next;
-                    self.next = None;
-                    self.initiated = false;
+    pub fn update(&mut self, delta: f32) -> Option<SceneSwap> {
+        let Some(transition) = &mut self.transition else {
+            return None;
+        };
+        transition.update(delta);
+        let (covered, finished) = (transition.covered(), transition.finished());
+
+        let mut swap = None;
+        if covered && let Some(change) = self.next.take() {
+            swap = Some(self.apply(change));
+            // the screen is still covered, the next one swaps in on the next frame
+            self.next = self.queued.pop_front();
+        }
+
+        if finished && self.next.is_none() {
+            self.transition = None;
+        }
+
+        return swap;
+    }
+
+    fn apply(&mut self, change: SceneChange) -> SceneSwap {
+        match change {
+            SceneChange::Set(scene) => {
+                let exited = self.stack.drain(..).rev().collect();
+                self.stack.push(scene);
+                SceneSwap {
+                    exited,
+                    entered: Some(scene),
                 }
             }
-        } else {
-            self.progress -= Self::FADE_SPEED * rl.get_frame_time();
-
-            if self.progress <= 0.0 {
-                self.fade_in = true;
+            SceneChange::Push(scene) => {
+                self.stack.push(scene);
+                SceneSwap {
+                    exited: Vec::new(),
+                    entered: Some(scene),
+                }
+            }
+            SceneChange::Pop => {
+                // the bottom scene stays, there has to be something to show
+                if self.stack.len() == 1 {
+                    return SceneSwap {
+                        exited: Vec::new(),
+                        entered: None,
+                    };
+                }
+                // whatever was under it is back and gets entered again
+                SceneSwap {
+                    exited: vec![self.stack.pop().unwrap()],
+                    entered: self.stack.last().copied(),
+                }
             }
         }
     }
 
     #[profiling::function]
-    pub fn draw(&self, rl: &mut RaylibDrawHandle, settings_handler: &SettingsHandler) {
-        rl.draw_rectangle_v(
-            Vector2::zero(),
-            Vector2::new(
-                (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32,
-                (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32,
-            ),
-            Color::BLACK.alpha(self.progress),
-        );
+    pub fn draw(&self, rl: &mut RaylibDrawHandle) {
+        if let Some(transition) = &self.transition {
+            transition.draw(rl);
+        }
     }
 }
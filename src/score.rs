@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::{map::Level, modifier::Modifier, profile};
+
+const LEADERBOARD_PATH: &str = "leaderboard.json";
+const REPLAY_DIR: &str = "replays/";
+const LEADERBOARD_SIZE: usize = 5;
+
+// finishing at all is worth something, the rest is for optimising
+const SCORE_BASE: i64 = 1000;
+const SCORE_PER_EXTRA_SPIRIT: i64 = 500;
+const SCORE_PER_WOOD: i64 = 100;
+const SCORE_PER_BONFIRE: i64 = 150; // taken away, fewer fires is a neater solution
+const SCORE_PER_SECOND: i64 = 5; // taken away too
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum ReplayAction {
+    Chop { spirit: usize, x: usize, y: usize },
+    Light { spirit: usize, x: usize, y: usize },
+    Build { fire: String, x: usize, y: usize },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReplayStep {
+    pub time: f32, // seconds since the level started
+    pub action: ReplayAction,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Replay {
+    level: u8,
+    player: String,
+    date: String,
+    score: i64,
+    steps: Vec<ReplayStep>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScoreEntry {
+    pub player: String,
+    pub date: String,
+    pub score: i64,
+    pub extra_spirits: usize,
+    pub wood: usize,
+    pub bonfires: usize,
+    pub time: f32,
+    pub replay: String, // file name in the replays folder of the profile
+    #[serde(default)]
+    pub modifier: Modifier,
+}
+
+impl ScoreEntry {
+    fn new(level: &Level, player: &str, replay: String) -> Self {
+        let extra_spirits = level.survived.saturating_sub(level.survive);
+        let score = SCORE_BASE
+            + extra_spirits as i64 * SCORE_PER_EXTRA_SPIRIT
+            + level.wood as i64 * SCORE_PER_WOOD
+            - level.bonfires_built as i64 * SCORE_PER_BONFIRE
+            - level.time.floor() as i64 * SCORE_PER_SECOND;
+
+        Self {
+            player: player.to_string(),
+            date: Local::now().format("%d.%m.%Y").to_string(),
+            score: score.max(0),
+            extra_spirits,
+            wood: level.wood,
+            bonfires: level.bonfires_built,
+            time: level.time,
+            replay,
+            modifier: level.modifier,
+        }
+    }
+}
+
+pub struct ScoreHandler {
+    boards: HashMap<u8, Vec<ScoreEntry>>, // best first
+    pub last: Option<ScoreEntry>,
+    pub last_place: Option<usize>, // none if it didn't make the board
+}
+
+impl ScoreHandler {
+    #[profiling::function]
+    pub fn new() -> Self {
+        let boards = match std::fs::read_to_string(profile::path(LEADERBOARD_PATH)) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_default(),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            boards,
+            last: None,
+            last_place: None,
+        }
+    }
+
+    pub fn board(&self, level_number: u8) -> &[ScoreEntry] {
+        match self.boards.get(&level_number) {
+            Some(board) => board,
+            None => &[],
+        }
+    }
+
+    // called once when the level is won
+    #[profiling::function]
+    pub fn submit(&mut self, level_number: u8, level: &Level, player: &str) {
+        let replay = format!(
+            "level_{}_{}.json",
+            level_number + 1,
+            Local::now().format("%Y%m%d_%H%M%S")
+        );
+        let entry = ScoreEntry::new(level, player, replay);
+
+        let board = self.boards.entry(level_number).or_default();
+        let place = board
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(board.len());
+
+        self.last = Some(entry.clone());
+        self.last_place = None;
+
+        if place >= LEADERBOARD_SIZE {
+            return;
+        }
+
+        board.insert(place, entry.clone());
+        // whoever got pushed off the board takes their replay with them
+        for dropped in board.drain(LEADERBOARD_SIZE.min(board.len())..) {
+            let _ = std::fs::remove_file(profile::path(REPLAY_DIR) + &dropped.replay);
+        }
+        self.last_place = Some(place);
+
+        let replay = Replay {
+            level: level_number,
+            player: entry.player,
+            date: entry.date,
+            score: entry.score,
+            steps: level.replay.clone(),
+        };
+        let Ok(s) = serde_json::to_string_pretty(&replay) else {
+            panic!("COULDN'T SERIALIZE REPLAY TO JSON");
+        };
+        std::fs::create_dir_all(profile::path(REPLAY_DIR))
+            .expect("COULDN'T CREATE REPLAY DIRECTORY");
+        std::fs::write(profile::path(REPLAY_DIR) + &entry.replay, s)
+            .expect("COULDN'T WRITE REPLAY TO FILE");
+
+        self.save();
+    }
+
+    // the name gets asked for after the run is already on the board
+    #[profiling::function]
+    pub fn rename_last(&mut self, level_number: u8, player: &str) {
+        let (Some(last), Some(place)) = (&mut self.last, self.last_place) else {
+            return;
+        };
+        last.player = player.to_string();
+
+        let Some(entry) = self
+            .boards
+            .get_mut(&level_number)
+            .and_then(|board| board.get_mut(place))
+        else {
+            return;
+        };
+        entry.player = player.to_string();
+
+        let path = profile::path(REPLAY_DIR) + &entry.replay;
+        if let Ok(s) = std::fs::read_to_string(&path)
+            && let Ok(mut replay) = serde_json::from_str::<Replay>(&s)
+        {
+            replay.player = player.to_string();
+            let Ok(s) = serde_json::to_string_pretty(&replay) else {
+                panic!("COULDN'T SERIALIZE REPLAY TO JSON");
+            };
+            std::fs::write(path, s).expect("COULDN'T WRITE REPLAY TO FILE");
+        }
+
+        self.save();
+    }
+
+    #[profiling::function]
+    fn save(&self) {
+        let Ok(s) = serde_json::to_string_pretty(&self.boards) else {
+            panic!("COULDN'T SERIALIZE LEADERBOARD TO JSON");
+        };
+
+        std::fs::write(profile::path(LEADERBOARD_PATH), s)
+            .expect("COULDN'T WRITE LEADERBOARD TO FILE");
+    }
+}
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::map::TILE_SCALE_DEFAULT;
-pub const MAXIMUM_PIXEL_SCALE: u8 = 3;
+use crate::{modifier::Modifier, post_processing::DEFAULT_PRESET, profile};
+pub const MAXIMUM_WINDOW_SCALE: u8 = 3;
+pub const WINDOW_SCALE_DEFAULT: u8 = 3;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Settings {
@@ -9,9 +10,70 @@ pub struct Settings {
     pub general_audio: f32,
     pub music: f32,
     pub sound: f32,
-    pub pixel_scale: u8,
-    pub shader: bool,
+    // only how big the window opens, the game itself is always drawn at SCREEN_WIDTH x SCREEN_HEIGHT
+    #[serde(alias = "pixel_scale")]
+    pub window_scale: u8,
+    // id of a preset from static/post_processing.json
+    #[serde(default = "default_post_processing")]
+    pub post_processing: String,
     pub fullscreen: bool,
+    // goes on the leaderboards, empty until the first run that makes one asks for it
+    #[serde(default)]
+    pub player_name: String,
+    #[serde(default)]
+    pub modifier: Modifier,
+    #[serde(default)]
+    pub safe_border: bool, // spirits turn back at the edge of the map instead of leaving it
+    #[serde(default)]
+    pub smooth_scaling: bool, // fill the window instead of sticking to whole pixels
+    #[serde(default)]
+    pub text_speed: TextSpeed,
+}
+
+// how fast dialogue types itself out, Skip doesn't show it at all
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum TextSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+    Instant,
+    Skip,
+}
+
+impl TextSpeed {
+    pub fn chars_per_second(self) -> f32 {
+        match self {
+            TextSpeed::Slow => 25.,
+            TextSpeed::Normal => 50.,
+            TextSpeed::Fast => 100.,
+            TextSpeed::Instant | TextSpeed::Skip => f32::INFINITY,
+        }
+    }
+
+    // a string key, the settings menu looks it up in the locale
+    pub fn name(self) -> &'static str {
+        match self {
+            TextSpeed::Slow => "text_speed.slow",
+            TextSpeed::Normal => "text_speed.normal",
+            TextSpeed::Fast => "text_speed.fast",
+            TextSpeed::Instant => "text_speed.instant",
+            TextSpeed::Skip => "text_speed.skip",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            TextSpeed::Slow => TextSpeed::Normal,
+            TextSpeed::Normal => TextSpeed::Fast,
+            TextSpeed::Fast => TextSpeed::Instant,
+            TextSpeed::Instant => TextSpeed::Skip,
+            TextSpeed::Skip => TextSpeed::Slow,
+        }
+    }
+}
+fn default_post_processing() -> String {
+    DEFAULT_PRESET.to_string()
 }
 impl Settings {
     pub fn get_final_music_volume(&self) -> f32 {
@@ -24,13 +86,18 @@ impl Settings {
 impl Default for Settings {
     fn default() -> Self {
         Self {
-            pixel_scale: TILE_SCALE_DEFAULT as u8,
+            window_scale: WINDOW_SCALE_DEFAULT,
             general_audio: 100.0,
             music: 100.0,
             sound: 100.0,
             language: "ru".to_string(),
-            shader: false,
+            post_processing: default_post_processing(),
             fullscreen: false,
+            player_name: String::new(),
+            modifier: Modifier::default(),
+            safe_border: false,
+            smooth_scaling: false,
+            text_speed: TextSpeed::default(),
         }
     }
 }
@@ -39,12 +106,12 @@ pub struct SettingsHandler {
     pub settings: Settings,
 }
 
-const SETTINGS_PATH: &str = "dynamic/settings.json";
+const SETTINGS_PATH: &str = "settings.json";
 
 impl SettingsHandler {
     #[profiling::function]
     pub fn new() -> Self {
-        let path = SETTINGS_PATH.to_string();
+        let path = profile::path(SETTINGS_PATH);
 
         if !std::fs::exists(&path).expect("COULDN'T CHECK IF SETTINGS FILE EXISTS") {
             return Self {
@@ -72,7 +139,7 @@ impl SettingsHandler {
             panic!("COULDN'T SERIALIZE SETTINGS TO JSON");
         };
 
-        std::fs::write(SETTINGS_PATH, s).expect("COULDN'T WRITE SETTINGS TO FILE");
+        std::fs::write(profile::path(SETTINGS_PATH), s).expect("COULDN'T WRITE SETTINGS TO FILE");
     }
     pub fn get_settings(&self) -> &Settings {
         return &self.settings;
@@ -1,11 +1,17 @@
 use raylib::prelude::*;
 
 use crate::{
-    SCREEN_HEIGHT, SCREEN_WIDTH,
+    SCREEN_WIDTH,
+    app::App,
     color::CustomColor,
     hotkey_handler::{HotkeyCategory, HotkeyHandler},
-    scene::{Scene, SceneHandler},
-    settings::{MAXIMUM_PIXEL_SCALE, Settings, SettingsHandler},
+    input::InputHandler,
+    locale::{self, DEFAULT_LANGUAGE, Locale},
+    modifier::Modifier,
+    post_processing::{self, DEFAULT_PRESET, PresetData},
+    scene::{Scene, SceneHandler, SceneState},
+    settings::{MAXIMUM_WINDOW_SCALE, Settings, SettingsHandler, TextSpeed},
+    text_layout::{Align, TextBox},
     texture_handler::TextureHandler,
     ui::{Button, get_text_size},
 };
@@ -19,13 +25,17 @@ pub enum SliderStyle {
 #[repr(u8)]
 #[derive(PartialEq, Clone, Copy)]
 pub enum SettingsOptions {
-    Shader = 0,
+    PostProcessing = 0,
     Fullscreen = 1,
-    BorderSlider = 2,
-    MusicVolume = 3,
-    SoundVolume = 4,
-    GeneralAudio = 5,
-    Resolution = 6,
+    SafeBorder = 2,
+    Language = 3,
+    TextSpeed = 4,
+    BorderSlider = 5,
+    MusicVolume = 6,
+    SoundVolume = 7,
+    GeneralAudio = 8,
+    Resolution = 9,
+    Modifier = 10,
 }
 
 const SLIDER_WIDTH_PX: u8 = 48;
@@ -33,18 +43,21 @@ const SLIDER_HEIGHT_PX: u8 = 16;
 const RULER_WIDTH_PX: u8 = 48;
 const RULER_PICKER_WIDTH_PX: u8 = 16;
 const SLIDER_TEXTURE_OFFSET: u8 = 16;
+const SLIDER_NUDGE: f32 = 10.;
 const BUTTON_TEXTURE_WIDTH: f32 = 16.;
 const BUTTON_TEXTURE_HEIGHT: f32 = 16.;
 
 const UI_X_OFFSET: f32 = 128.;
-const UI_Y_OFFSET: f32 = 14.;
-const UI_SHIFT_SIZE: f32 = 18.;
+const UI_Y_OFFSET: f32 = 18.;
+const UI_SHIFT_SIZE: f32 = 16.;
 const UI_Y_TOP_OFFSET: f32 = 1.;
 const TEXT_X_OFFSET: f32 = 32.;
 const TEXT_SIZE: f32 = 12.;
 const TEXT_SPACING: f32 = 1.25;
+const VALUE_TEXT_X_OFFSET: f32 = 180.;
+const VALUE_TEXT_SIZE: f32 = 8.;
 
-const UI_UTILITY_Y_OFFSET_SAVE_BUTTON: u8 = 124;
+const UI_UTILITY_MARGIN: f32 = 1.;
 const UI_UTILITY_WIDTH: u8 = 64;
 const UI_UTILITY_HEIGHT: u8 = 16;
 
@@ -53,43 +66,69 @@ const UI_WARNING_Y_OFFSET: u8 = 105;
 const UI_WARNING_X_SHIFT: u8 = 61;
 const UI_WARNING_WIDTH: u8 = 32;
 const UI_WARNING_HEIGHT: u8 = 16;
+const UI_WARNING_TEXT_WIDTH: f32 = 112.;
 //64. 26.
 
 const BACKGROUND_COLOR_HEX: &str = "0b8a8f";
 const BACKGROUND_IMAGE: &str = "settings_bg";
 const SETTINGS_BUTTON_TEXTURE: &str = "settings_button";
 const SETTINGS_UI_TEXTURE: &str = "pause_menu";
-
-const BUTTONS_SETTINGS: [&str; 2] = ["Шейдер", "Полный экран"];
-const SLIDERS_SETTINGS: [&str; 4] = [
-    "Общая громкость",
-    "Громкость музыки",
-    "Громкость звуков",
-    "Разрешение",
+const VOLUME_SLIDER_TEXTURE: &str = "volume_slider";
+const RULER_SLIDER_TEXTURE: &str = "ruler_slider";
+
+const BUTTONS_SETTINGS: [&str; 5] = [
+    "settings.post_processing",
+    "settings.fullscreen",
+    "settings.safe_border",
+    "settings.language",
+    "settings.text_speed",
+];
+const SLIDERS_SETTINGS: [&str; 5] = [
+    "settings.general_audio",
+    "settings.music",
+    "settings.sound",
+    "settings.resolution",
+    "settings.difficulty",
 ];
 
-const WARNING_TEXT: [&str; 3] = ["Вы хотите выйти", "без сохранения", "настроек?"];
-const WARNING_BUTTONS_TEXT: [&str; 2] = ["Да", "Нет"];
+const WARNING_TEXT: &str = "settings.warning";
+const WARNING_BUTTONS_TEXT: [&str; 2] = ["settings.yes", "settings.no"];
 
-const UTILITY_BUTTONS: [&str; 2] = ["Назад", "Сохранить"];
+const UTILITY_BUTTONS: [&str; 2] = ["settings.back", "settings.save"];
 const UTILITY_BUTTONS_TEXTURE: &str = "main_menu_buttons";
+// between back and save, opens the key rebinding
+const CONTROLS_BUTTON_TEXT: &str = "settings.controls";
+
+pub const TEXTURES: [&str; 6] = [
+    BACKGROUND_IMAGE,
+    SETTINGS_BUTTON_TEXTURE,
+    SETTINGS_UI_TEXTURE,
+    UTILITY_BUTTONS_TEXTURE,
+    VOLUME_SLIDER_TEXTURE,
+    RULER_SLIDER_TEXTURE,
+];
 
 const SETTINGS_OPTIONS: [SettingsOptions; BUTTONS_SETTINGS.len() + SLIDERS_SETTINGS.len()] = [
-    SettingsOptions::Shader,
+    SettingsOptions::PostProcessing,
     SettingsOptions::Fullscreen,
+    SettingsOptions::SafeBorder,
+    SettingsOptions::Language,
+    SettingsOptions::TextSpeed,
     SettingsOptions::GeneralAudio,
     SettingsOptions::MusicVolume,
     SettingsOptions::SoundVolume,
     SettingsOptions::Resolution,
+    SettingsOptions::Modifier,
 ];
 
-const PIXEL_SCALE_TO_SLIDER_VALUE: f32 = 100. / MAXIMUM_PIXEL_SCALE as f32;
+const WINDOW_SCALE_TO_SLIDER_VALUE: f32 = 100. / MAXIMUM_WINDOW_SCALE as f32;
 
-const SLIDER_TYPES: [SliderStyle; 4] = [
+const SLIDER_TYPES: [SliderStyle; 5] = [
     SliderStyle::Volume,
     SliderStyle::Volume,
     SliderStyle::Volume,
     SliderStyle::Ruler,
+    SliderStyle::Ruler, // three snap points, one per modifier
 ];
 
 fn find_nearest(values: Vec<usize>, value: usize) -> usize {
@@ -174,8 +213,8 @@ impl SliderStyle {
 
     fn get_texture_name(slider_style: &SliderStyle) -> &str {
         return match *slider_style {
-            SliderStyle::Volume => "volume_slider",
-            SliderStyle::Ruler => "ruler_slider",
+            SliderStyle::Volume => VOLUME_SLIDER_TEXTURE,
+            SliderStyle::Ruler => RULER_SLIDER_TEXTURE,
         };
     }
     fn get_dimensions(slider_style: &SliderStyle) -> (usize, usize) {
@@ -191,11 +230,7 @@ impl SliderStyle {
         };
     }
 
-    fn get_rects(
-        slider_style: &SliderStyle,
-        multiplier: usize,
-        initial_position: Vector2,
-    ) -> Vec<Rectangle> {
+    fn get_rects(slider_style: &SliderStyle, initial_position: Vector2) -> Vec<Rectangle> {
         let mut vector: Vec<Rectangle> = vec![];
         let need_special_size = SliderStyle::special_size_picker(slider_style);
 
@@ -210,8 +245,8 @@ impl SliderStyle {
             vector.push(Rectangle {
                 x: initial_position.x,
                 y: initial_position.y,
-                width: (width * multiplier) as f32,
-                height: (height * multiplier) as f32,
+                width: width as f32,
+                height: height as f32,
             });
         }
 
@@ -227,8 +262,8 @@ pub struct Slider {
 }
 
 impl Slider {
-    pub fn new(slider_style: SliderStyle, start_position: Vector2, pixel_scale: usize) -> Self {
-        let rects = SliderStyle::get_rects(&slider_style, pixel_scale, start_position);
+    pub fn new(slider_style: SliderStyle, start_position: Vector2) -> Self {
+        let rects = SliderStyle::get_rects(&slider_style, start_position);
 
         Self {
             slider_value: 50,
@@ -238,7 +273,7 @@ impl Slider {
         }
     }
 
-    fn back_to_default(&mut self, scale: f32, position: Vector2) {
+    fn back_to_default(&mut self, position: Vector2) {
         let need_special_size = SliderStyle::special_size_picker(&self.slider_style);
 
         for index in 0..SliderStyle::get_sprite_parts_amount(&self.slider_style) {
@@ -251,24 +286,27 @@ impl Slider {
             };
             self.rects[index as usize].x = position.x;
             self.rects[index as usize].y = position.y;
-            self.rects[index as usize].width = width as f32 * scale;
-            self.rects[index as usize].height = height as f32 * scale;
+            self.rects[index as usize].width = width as f32;
+            self.rects[index as usize].height = height as f32;
         }
     }
 }
 
 pub struct SettingsMenuHandler {
-    previous_scene: Option<Scene>,
     picked_element: Option<usize>,
     buttons: Vec<Button>,
     sliders: Vec<Slider>,
     ui_buttons: Vec<Button>,
     in_menu_settings: Settings,
     draw_warning: bool,
+    presets: Vec<PresetData>,
+    languages: Vec<(String, String)>, // (code, name)
+    controls_button: Button,
+    controls_open: bool, // coming back from the controls keeps what was changed here
     pub should_remade: bool,
 }
 impl SettingsMenuHandler {
-    pub fn new(scale: f32) -> Self {
+    pub fn new() -> Self {
         let mut buttons = Vec::new();
         let mut sliders = Vec::new();
         let mut buttons_utility = Vec::new();
@@ -300,82 +338,72 @@ impl SettingsMenuHandler {
             sliders.push(Slider::new(
                 *slider_type,
                 Vector2::new(
-                    UI_X_OFFSET * scale as f32,
-                    (UI_Y_OFFSET
+                    UI_X_OFFSET,
+                    UI_Y_OFFSET
                         + UI_Y_TOP_OFFSET
-                        + (index + BUTTONS_SETTINGS.len()) as f32 * UI_SHIFT_SIZE)
-                        * scale as f32,
-                ) + SliderStyle::get_sprite_offset(slider_type)
-                    * Vector2::new(scale as f32, scale as f32),
-                scale as usize,
+                        + (index + BUTTONS_SETTINGS.len()) as f32 * UI_SHIFT_SIZE,
+                ) + SliderStyle::get_sprite_offset(slider_type),
             ));
         }
-        Self::set_ui_to_default(&mut buttons, &mut buttons_utility, &mut sliders, scale);
+        Self::set_ui_to_default(&mut buttons, &mut buttons_utility, &mut sliders);
         return Self {
             picked_element: None,
-            previous_scene: None,
             buttons,
             ui_buttons: buttons_utility,
             sliders,
             in_menu_settings: Settings::default(),
             draw_warning: false,
+            presets: post_processing::load_presets(),
+            languages: locale::languages(),
+            controls_button: Button {
+                rect: Rectangle::new(
+                    (SCREEN_WIDTH as f32 - UI_UTILITY_WIDTH as f32) / 2.,
+                    UI_UTILITY_MARGIN,
+                    UI_UTILITY_WIDTH as f32,
+                    UI_UTILITY_HEIGHT as f32,
+                ),
+                ..Default::default()
+            },
+            controls_open: false,
             should_remade: false,
         };
     }
-    pub fn rescale_ui(&mut self, new_scale: f32) {
-        Self::set_ui_to_default(
-            &mut self.buttons,
-            &mut self.ui_buttons,
-            &mut self.sliders,
-            new_scale,
-        );
-    }
-
     fn set_ui_to_default(
         common_buttons: &mut Vec<Button>,
         utility_buttons: &mut Vec<Button>,
         sliders: &mut Vec<Slider>,
-        new_scale: f32,
     ) {
         for (index, button) in common_buttons.iter_mut().enumerate() {
-            button.rect.x = new_scale * UI_X_OFFSET;
-            button.rect.y =
-                (UI_Y_OFFSET + UI_Y_TOP_OFFSET + index as f32 * UI_SHIFT_SIZE) * new_scale;
-            button.rect.width = BUTTON_TEXTURE_WIDTH * new_scale;
-            button.rect.height = BUTTON_TEXTURE_HEIGHT * new_scale;
+            button.rect.x = UI_X_OFFSET;
+            button.rect.y = UI_Y_OFFSET + UI_Y_TOP_OFFSET + index as f32 * UI_SHIFT_SIZE;
+            button.rect.width = BUTTON_TEXTURE_WIDTH;
+            button.rect.height = BUTTON_TEXTURE_HEIGHT;
         }
+        // back in the top left corner, save in the top right, the rows take the whole height
         for index in 0..UTILITY_BUTTONS.len() {
-            let additional_offset = Vector2::new(1., 1.) * new_scale;
-            let additional_multiplier = if index == 0 { 1. } else { 0. };
-
-            utility_buttons[index].rect.x =
-                new_scale * (SCREEN_WIDTH - UI_UTILITY_WIDTH as i32) as f32 * index as f32 / 2.
-                    + additional_multiplier * additional_offset.x;
-            utility_buttons[index].rect.y =
-                index as f32 * UI_UTILITY_Y_OFFSET_SAVE_BUTTON as f32 * new_scale
-                    + additional_multiplier * additional_offset.y;
-            utility_buttons[index].rect.width = UI_UTILITY_WIDTH as f32 * new_scale;
-            utility_buttons[index].rect.height = UI_UTILITY_HEIGHT as f32 * new_scale;
+            utility_buttons[index].rect.x = UI_UTILITY_MARGIN
+                + (SCREEN_WIDTH as f32 - UI_UTILITY_WIDTH as f32 - UI_UTILITY_MARGIN * 2.)
+                    * index as f32;
+            utility_buttons[index].rect.y = UI_UTILITY_MARGIN;
+            utility_buttons[index].rect.width = UI_UTILITY_WIDTH as f32;
+            utility_buttons[index].rect.height = UI_UTILITY_HEIGHT as f32;
         }
         for index in UTILITY_BUTTONS.len()..UTILITY_BUTTONS.len() + WARNING_BUTTONS_TEXT.len() {
-            utility_buttons[index].rect.x = new_scale * UI_WARNING_X_OFFSET as f32
-                + UI_WARNING_X_SHIFT as f32 * new_scale * (index - UTILITY_BUTTONS.len()) as f32;
-            utility_buttons[index].rect.y = new_scale * UI_WARNING_Y_OFFSET as f32;
-            utility_buttons[index].rect.width = UI_WARNING_WIDTH as f32 * new_scale;
-            utility_buttons[index].rect.height = UI_WARNING_HEIGHT as f32 * new_scale;
+            utility_buttons[index].rect.x = UI_WARNING_X_OFFSET as f32
+                + UI_WARNING_X_SHIFT as f32 * (index - UTILITY_BUTTONS.len()) as f32;
+            utility_buttons[index].rect.y = UI_WARNING_Y_OFFSET as f32;
+            utility_buttons[index].rect.width = UI_WARNING_WIDTH as f32;
+            utility_buttons[index].rect.height = UI_WARNING_HEIGHT as f32;
         }
 
         for (index, slider) in sliders.iter_mut().enumerate() {
             slider.back_to_default(
-                new_scale,
                 Vector2::new(
-                    UI_X_OFFSET * new_scale,
-                    (UI_Y_OFFSET
+                    UI_X_OFFSET,
+                    UI_Y_OFFSET
                         + UI_Y_TOP_OFFSET
-                        + (index + BUTTONS_SETTINGS.len()) as f32 * UI_SHIFT_SIZE)
-                        * new_scale,
-                ) + SliderStyle::get_sprite_offset(&slider.slider_style)
-                    * Vector2::new(new_scale, new_scale),
+                        + (index + BUTTONS_SETTINGS.len()) as f32 * UI_SHIFT_SIZE,
+                ) + SliderStyle::get_sprite_offset(&slider.slider_style),
             );
         }
     }
@@ -383,10 +411,82 @@ impl SettingsMenuHandler {
     fn set_setting_button(settings: &mut Settings, settings_option: SettingsOptions, value: bool) {
         match settings_option {
             SettingsOptions::Fullscreen => settings.fullscreen = value,
-            SettingsOptions::Shader => settings.shader = value,
+            SettingsOptions::SafeBorder => settings.safe_border = value,
             _ => panic!("Not implemented yet!"),
         };
     }
+    fn next_preset(presets: &[PresetData], current: &str) -> String {
+        let index = presets.iter().position(|p| p.id == current);
+        match index {
+            Some(index) => presets[(index + 1) % presets.len()].id.clone(),
+            None => DEFAULT_PRESET.to_string(),
+        }
+    }
+    fn preset_name<'a>(&'a self, locale: &'a Locale) -> &'a str {
+        self.presets
+            .iter()
+            .find(|p| p.id == self.in_menu_settings.post_processing)
+            .map_or("", |p| locale.get(&p.name))
+    }
+    fn next_language(languages: &[(String, String)], current: &str) -> String {
+        let index = languages.iter().position(|(code, _)| code == current);
+        match index {
+            Some(index) => languages[(index + 1) % languages.len()].0.clone(),
+            None => DEFAULT_LANGUAGE.to_string(),
+        }
+    }
+    // each language is named in itself, so it can be found from any other one
+    fn language_name(&self) -> &str {
+        self.languages
+            .iter()
+            .find(|(code, _)| *code == self.in_menu_settings.language)
+            .map_or(self.in_menu_settings.language.as_str(), |(_, name)| {
+                name.as_str()
+            })
+    }
+    // what the d-pad goes between, only yes and no while the warning is up
+    fn focus_targets(&self) -> Vec<Rectangle> {
+        if self.draw_warning {
+            return self.ui_buttons[UTILITY_BUTTONS.len()..]
+                .iter()
+                .map(|button| button.rect)
+                .collect();
+        }
+        let mut targets: Vec<Rectangle> = self.ui_buttons[..UTILITY_BUTTONS.len()]
+            .iter()
+            .chain([&self.controls_button])
+            .chain(self.buttons.iter())
+            .map(|button| button.rect)
+            .collect();
+        targets.extend(
+            self.sliders
+                .iter()
+                .map(|slider| slider.rects[SliderStyle::get_outline_rect(&slider.slider_style)]),
+        );
+        targets
+    }
+    // one snap point over, or a tenth of the way for the smooth ones
+    fn nudge_slider(&mut self, index: usize, direction: f32) {
+        let slider = &mut self.sliders[index];
+        let value_to_set = if slider.snap {
+            let snap_points = SliderStyle::get_snap_points(&slider.slider_style);
+            let nearest = find_nearest(snap_points.clone(), slider.slider_value as usize);
+            let next = (nearest as isize + direction as isize)
+                .clamp(0, snap_points.len() as isize - 1) as usize;
+            slider.slider_value = snap_points[next] as u8;
+            next as u8
+        } else {
+            slider.slider_value =
+                (slider.slider_value as f32 + direction * SLIDER_NUDGE).clamp(0., 100.) as u8;
+            slider.slider_value
+        };
+
+        Self::set_setting_slider(
+            &mut self.in_menu_settings,
+            SETTINGS_OPTIONS[index + BUTTONS_SETTINGS.len()],
+            value_to_set,
+        );
+    }
     fn align_buttons_with_inner_settings(&mut self) {
         self.align_settings(&self.in_menu_settings.clone());
     }
@@ -404,8 +504,9 @@ impl SettingsMenuHandler {
         match settings_option {
             SettingsOptions::SoundVolume => settings.sound = value as f32,
             SettingsOptions::MusicVolume => settings.music = value as f32,
-            SettingsOptions::Resolution => settings.pixel_scale = value + 1,
+            SettingsOptions::Resolution => settings.window_scale = value + 1,
             SettingsOptions::GeneralAudio => settings.general_audio = value as f32,
+            SettingsOptions::Modifier => settings.modifier = Modifier::from_index(value),
             _ => panic!("Not implemented yet!"),
         };
     }
@@ -413,14 +514,26 @@ impl SettingsMenuHandler {
     pub fn align_settings(&mut self, settings: &Settings) {
         for (index, button) in self.buttons.iter_mut().enumerate() {
             match SETTINGS_OPTIONS[index] {
-                SettingsOptions::Shader => {
-                    self.in_menu_settings.shader = settings.shader;
-                    button.selected = settings.shader;
+                SettingsOptions::PostProcessing => {
+                    self.in_menu_settings.post_processing = settings.post_processing.clone();
+                    button.selected = settings.post_processing != DEFAULT_PRESET;
                 }
                 SettingsOptions::Fullscreen => {
                     self.in_menu_settings.fullscreen = settings.fullscreen;
                     button.selected = settings.fullscreen;
                 }
+                SettingsOptions::SafeBorder => {
+                    self.in_menu_settings.safe_border = settings.safe_border;
+                    button.selected = settings.safe_border;
+                }
+                SettingsOptions::Language => {
+                    self.in_menu_settings.language = settings.language.clone();
+                    button.selected = settings.language != DEFAULT_LANGUAGE;
+                }
+                SettingsOptions::TextSpeed => {
+                    self.in_menu_settings.text_speed = settings.text_speed;
+                    button.selected = settings.text_speed != TextSpeed::default();
+                }
                 _ => panic!("Not implemented yet!"),
             };
         }
@@ -436,57 +549,45 @@ impl SettingsMenuHandler {
                     slider.slider_value = settings.sound as u8;
                 }
                 SettingsOptions::Resolution => {
-                    self.in_menu_settings.pixel_scale = settings.pixel_scale;
+                    self.in_menu_settings.window_scale = settings.window_scale;
                     let snap_points = SliderStyle::get_snap_points(&slider.slider_style);
                     slider.slider_value = snap_points[find_nearest(
                         SliderStyle::get_snap_points(&slider.slider_style),
-                        (settings.pixel_scale as f32 * PIXEL_SCALE_TO_SLIDER_VALUE) as usize,
+                        (settings.window_scale as f32 * WINDOW_SCALE_TO_SLIDER_VALUE) as usize,
                     ) as usize] as u8;
                 }
                 SettingsOptions::GeneralAudio => {
                     self.in_menu_settings.general_audio = settings.general_audio;
                     slider.slider_value = settings.general_audio as u8;
                 }
+                SettingsOptions::Modifier => {
+                    self.in_menu_settings.modifier = settings.modifier;
+                    let snap_points = SliderStyle::get_snap_points(&slider.slider_style);
+                    slider.slider_value = snap_points[settings.modifier.index()] as u8;
+                }
 
                 _ => panic!("Not implemented yet!"),
             };
         }
     }
 
-    pub fn set_scene(&mut self, scene: Scene) {
-        self.previous_scene = Some(scene);
-    }
-
-    pub fn check_scene(&mut self) -> bool {
-        return self.previous_scene.is_some();
-    }
-
     #[profiling::function]
     pub fn update(
         &mut self,
         scene_handler: &mut SceneHandler,
-        rl: &mut RaylibHandle,
+        input_handler: &InputHandler,
         settings_handler: &mut SettingsHandler,
         hotkey_handler: &mut HotkeyHandler,
     ) {
         for (i, button) in self.ui_buttons.iter_mut().enumerate() {
-            if hotkey_handler
-                .check_released(rl, (HotkeyCategory::PickButton1 as u8 + i as u8 % 2).into())
-                || (button.selected
-                    && rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT)
-                    && button.rect.check_collision_point_rec(
-                        rl.get_mouse_position()
-                            - Vector2::new(
-                                rl.get_screen_width() as f32 / 2.
-                                    - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32)
-                                        as f32
-                                        / 2.,
-                                rl.get_screen_height() as f32 / 2.
-                                    - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32)
-                                        as f32
-                                        / 2.,
-                            ),
-                    ))
+            if hotkey_handler.check_released(
+                input_handler,
+                (HotkeyCategory::PickButton1 as u8 + i as u8 % 2).into(),
+            ) || (button.selected
+                && hotkey_handler.check_click_released(input_handler)
+                && button
+                    .rect
+                    .check_collision_point_rec(input_handler.pointer()))
             {
                 let warning = self.draw_warning;
                 button.selected = false;
@@ -499,28 +600,21 @@ impl SettingsMenuHandler {
                             self.draw_warning = true;
                             return;
                         }
-                        if self.previous_scene.is_some() {
-                            scene_handler.set(self.previous_scene.unwrap());
-                            self.previous_scene = None;
-                        }
+                        scene_handler.pop();
                     }
                     1 => {
                         if warning {
                             continue;
                         }
 
+                        // the window gets resized to the new preset on the next frame
                         self.should_remade = true;
-                        rl.set_window_size(
-                            SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32,
-                            SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32,
-                        );
                         settings_handler.set_settings(&self.in_menu_settings);
                         settings_handler.save();
                     }
                     2 => {
                         self.draw_warning = false;
-                        scene_handler.set(self.previous_scene.unwrap());
-                        self.previous_scene = None;
+                        scene_handler.pop();
                     }
                     3 => {
                         self.draw_warning = false;
@@ -535,24 +629,47 @@ impl SettingsMenuHandler {
             return;
         }
 
+        if hotkey_handler.check_click_released(input_handler)
+            && self
+                .controls_button
+                .rect
+                .check_collision_point_rec(input_handler.pointer())
+        {
+            self.controls_open = true;
+            scene_handler.push(Scene::Controls);
+            return;
+        }
+
         for (index, button) in self.buttons.iter_mut().enumerate() {
-            if rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT)
+            if hotkey_handler.check_click_released(input_handler)
                 && self.picked_element.is_some_and(|b| b == index)
             {
                 self.picked_element = None;
-                if button.rect.check_collision_point_rec(
-                    rl.get_mouse_position()
-                        - Vector2::new(
-                            rl.get_screen_width() as f32 / 2.
-                                - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32)
-                                    as f32
-                                    / 2.,
-                            rl.get_screen_height() as f32 / 2.
-                                - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32)
-                                    as f32
-                                    / 2.,
-                        ),
-                ) {
+                if button
+                    .rect
+                    .check_collision_point_rec(input_handler.pointer())
+                {
+                    // not an on/off, every click moves to the next preset
+                    if SETTINGS_OPTIONS[index] == SettingsOptions::PostProcessing {
+                        self.in_menu_settings.post_processing = Self::next_preset(
+                            &self.presets,
+                            &self.in_menu_settings.post_processing,
+                        );
+                        button.selected = self.in_menu_settings.post_processing != DEFAULT_PRESET;
+                        continue;
+                    }
+                    if SETTINGS_OPTIONS[index] == SettingsOptions::Language {
+                        self.in_menu_settings.language =
+                            Self::next_language(&self.languages, &self.in_menu_settings.language);
+                        button.selected = self.in_menu_settings.language != DEFAULT_LANGUAGE;
+                        continue;
+                    }
+                    if SETTINGS_OPTIONS[index] == SettingsOptions::TextSpeed {
+                        self.in_menu_settings.text_speed = self.in_menu_settings.text_speed.next();
+                        button.selected = self.in_menu_settings.text_speed != TextSpeed::default();
+                        continue;
+                    }
+
                     button.selected = !button.selected;
                     SettingsMenuHandler::set_setting_button(
                         &mut self.in_menu_settings,
@@ -564,7 +681,7 @@ impl SettingsMenuHandler {
         }
 
         if self.picked_element.is_some_and(|b| b >= self.buttons.len())
-            && rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT)
+            && input_handler.pointer_released(MouseButton::MOUSE_BUTTON_LEFT)
         {
             let val = self.picked_element.unwrap();
             self.picked_element = None;
@@ -586,18 +703,9 @@ impl SettingsMenuHandler {
 
         for (index, slider) in self.sliders.iter_mut().enumerate() {
             let outline_rect_n = SliderStyle::get_outline_rect(&slider.slider_style);
-            if slider.rects[outline_rect_n].check_collision_point_rec(
-                rl.get_mouse_position()
-                    - Vector2::new(
-                        rl.get_screen_width() as f32 / 2.
-                            - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                        rl.get_screen_height() as f32 / 2.
-                            - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                    ),
-            ) && self.picked_element.is_none()
-                && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            if slider.rects[outline_rect_n].check_collision_point_rec(input_handler.pointer())
+                && self.picked_element.is_none()
+                && input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT)
             {
                 self.picked_element = Some(index + buttons_len);
             }
@@ -609,15 +717,8 @@ impl SettingsMenuHandler {
             {
                 continue;
             };
-            let mouse_cords = rl.get_mouse_position()
-                - Vector2::new(
-                    rl.get_screen_width() as f32 / 2.
-                        - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32 / 2.,
-                    rl.get_screen_height() as f32 / 2.
-                        - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                            / 2.,
-                );
-            let maxlen = SLIDER_WIDTH_PX * settings_handler.settings.pixel_scale;
+            let mouse_cords = input_handler.pointer();
+            let maxlen = SLIDER_WIDTH_PX;
             let slider_value = if mouse_cords.x < slider.rects[outline_rect_n].x {
                 0.
             } else {
@@ -647,36 +748,24 @@ impl SettingsMenuHandler {
     #[profiling::function]
     pub fn draw(
         &mut self,
+        locale: &Locale,
         font: &Font,
         texture_handler: &TextureHandler,
         rl: &mut RaylibDrawHandle,
-        settings_handler: &mut SettingsHandler,
+        input_handler: &InputHandler,
         hotkey_handler: &mut HotkeyHandler,
     ) {
         rl.clear_background(Color::from_hex(BACKGROUND_COLOR_HEX).unwrap());
 
-        rl.draw_texture_ex(
-            texture_handler.get_safe(BACKGROUND_IMAGE),
-            Vector2::zero(),
-            0.0,
-            settings_handler.settings.pixel_scale as f32,
-            Color::WHITE,
-        );
+        texture_handler.draw(rl, BACKGROUND_IMAGE, Vector2::zero(), Color::WHITE);
 
         for (button_num, button) in self.buttons.iter().enumerate() {
-            let mouse_down = rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT);
-
-            let texture_offset = if button.rect.check_collision_point_rec(
-                rl.get_mouse_position()
-                    - Vector2::new(
-                        rl.get_screen_width() as f32 / 2.
-                            - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                        rl.get_screen_height() as f32 / 2.
-                            - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                    ),
-            ) && mouse_down
+            let mouse_down = hotkey_handler.check_click_down(input_handler);
+
+            let texture_offset = if button
+                .rect
+                .check_collision_point_rec(input_handler.pointer())
+                && mouse_down
                 && self.picked_element.is_none()
                 && !self.draw_warning
             {
@@ -699,8 +788,9 @@ impl SettingsMenuHandler {
                 0.
             };
 
-            rl.draw_texture_pro(
-                texture_handler.get_safe(SETTINGS_BUTTON_TEXTURE),
+            texture_handler.draw_pro(
+                rl,
+                SETTINGS_BUTTON_TEXTURE,
                 Rectangle::new(
                     texture_offset,
                     button_state,
@@ -708,25 +798,46 @@ impl SettingsMenuHandler {
                     BUTTON_TEXTURE_WIDTH,
                 ),
                 button.rect,
-                Vector2::zero(),
-                0.0,
                 Color::WHITE,
             );
 
             rl.draw_text_pro(
                 font,
-                BUTTONS_SETTINGS[button_num],
+                locale.get(BUTTONS_SETTINGS[button_num]),
                 Vector2::new(
-                    TEXT_X_OFFSET * settings_handler.settings.pixel_scale as f32,
-                    (UI_Y_OFFSET + UI_Y_TOP_OFFSET + button_num as f32 * UI_SHIFT_SIZE)
-                        * settings_handler.settings.pixel_scale as f32,
+                    TEXT_X_OFFSET,
+                    UI_Y_OFFSET + UI_Y_TOP_OFFSET + button_num as f32 * UI_SHIFT_SIZE,
                 ),
                 Vector2::zero(),
                 0.,
-                TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
+                TEXT_SIZE,
+                TEXT_SPACING,
                 Color::RAYWHITE,
             );
+
+            let value = match SETTINGS_OPTIONS[button_num] {
+                SettingsOptions::PostProcessing => Some(self.preset_name(locale)),
+                SettingsOptions::Language => Some(self.language_name()),
+                SettingsOptions::TextSpeed => {
+                    Some(locale.get(self.in_menu_settings.text_speed.name()))
+                }
+                _ => None,
+            };
+            if let Some(value) = value {
+                rl.draw_text_pro(
+                    font,
+                    value,
+                    Vector2::new(
+                        VALUE_TEXT_X_OFFSET,
+                        UI_Y_OFFSET + UI_Y_TOP_OFFSET + 4. + button_num as f32 * UI_SHIFT_SIZE,
+                    ),
+                    Vector2::zero(),
+                    0.,
+                    VALUE_TEXT_SIZE,
+                    0.,
+                    Color::RAYWHITE,
+                );
+            }
         }
 
         for (slider_num, slider) in self.sliders.iter_mut().enumerate() {
@@ -747,7 +858,7 @@ impl SettingsMenuHandler {
                         slider.slider_value as f32
                     };
 
-                    if rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
+                    if input_handler.pointer_down(MouseButton::MOUSE_BUTTON_LEFT)
                         && self
                             .picked_element
                             .is_some_and(|b| b == self.buttons.len() + slider_num)
@@ -759,89 +870,90 @@ impl SettingsMenuHandler {
                         (x, y) = SliderStyle::get_picker_pressed_texture(&slider.slider_style);
                     }
 
-                    slider.rects[i].x =
-                        slider.rects[SliderStyle::get_outline_rect(&slider.slider_style)].x
-                            + picker_offset * settings_handler.settings.pixel_scale as f32
-                            + (SLIDER_WIDTH_PX as f32 * new_val / 100.).floor()
-                                * settings_handler.settings.pixel_scale as f32;
+                    slider.rects[i].x = slider.rects
+                        [SliderStyle::get_outline_rect(&slider.slider_style)]
+                    .x + picker_offset
+                        + (SLIDER_WIDTH_PX as f32 * new_val / 100.).floor();
                 } else if i == picker {
                     width = ((SLIDER_WIDTH_PX as f32 * slider.slider_value as f32 / 100.).floor()
-                        + picker_offset * settings_handler.settings.pixel_scale as f32)
-                        as usize;
+                        + picker_offset) as usize;
 
-                    slider.rects[i].width =
-                        width as f32 * settings_handler.settings.pixel_scale as f32;
+                    slider.rects[i].width = width as f32;
                 }
 
-                rl.draw_texture_pro(
-                    texture_handler.get_safe(SliderStyle::get_texture_name(&slider.slider_style)),
+                texture_handler.draw_pro(
+                    rl,
+                    SliderStyle::get_texture_name(&slider.slider_style),
                     Rectangle::new(x as f32, y as f32, width as f32, height as f32),
                     slider.rects[i],
-                    Vector2::zero(),
-                    0.0,
                     Color::WHITE,
                 );
             }
 
             rl.draw_text_pro(
                 font,
-                SLIDERS_SETTINGS[slider_num],
+                locale.get(SLIDERS_SETTINGS[slider_num]),
                 Vector2::new(
-                    TEXT_X_OFFSET * settings_handler.settings.pixel_scale as f32,
-                    (UI_Y_OFFSET
+                    TEXT_X_OFFSET,
+                    UI_Y_OFFSET
                         + UI_Y_TOP_OFFSET
-                        + (slider_num + BUTTONS_SETTINGS.len()) as f32 * UI_SHIFT_SIZE)
-                        * settings_handler.settings.pixel_scale as f32,
+                        + (slider_num + BUTTONS_SETTINGS.len()) as f32 * UI_SHIFT_SIZE,
                 ),
                 Vector2::zero(),
                 0.,
-                TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
+                TEXT_SIZE,
+                TEXT_SPACING,
                 Color::RAYWHITE,
             );
+
+            // the ruler alone doesn't say which one is picked
+            if SETTINGS_OPTIONS[slider_num + BUTTONS_SETTINGS.len()] == SettingsOptions::Modifier {
+                rl.draw_text_pro(
+                    font,
+                    locale.get(self.in_menu_settings.modifier.name()),
+                    Vector2::new(
+                        VALUE_TEXT_X_OFFSET,
+                        UI_Y_OFFSET
+                            + UI_Y_TOP_OFFSET
+                            + 4.
+                            + (slider_num + BUTTONS_SETTINGS.len()) as f32 * UI_SHIFT_SIZE,
+                    ),
+                    Vector2::zero(),
+                    0.,
+                    VALUE_TEXT_SIZE,
+                    0.,
+                    Color::RAYWHITE,
+                );
+            }
         }
 
         for i in 0..UTILITY_BUTTONS.len() {
-            let (texture_offset, text_offset) =
-                if (self.ui_buttons[i].rect.check_collision_point_rec(
-                    rl.get_mouse_position()
-                        - Vector2::new(
-                            rl.get_screen_width() as f32 / 2.
-                                - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32)
-                                    as f32
-                                    / 2.,
-                            rl.get_screen_height() as f32 / 2.
-                                - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32)
-                                    as f32
-                                    / 2.,
-                        ),
-                ) && self.picked_element.is_none()
-                    && rl.is_mouse_button_up(MouseButton::MOUSE_BUTTON_LEFT)
-                    || hotkey_handler
-                        .check_down(rl, (HotkeyCategory::PickButton1 as u8 + i as u8).into()))
-                    && !self.draw_warning
-                {
-                    self.ui_buttons[i].selected = true;
-                    (0., 0.)
-                } else {
-                    (
-                        UI_UTILITY_HEIGHT as f32,
-                        settings_handler.settings.pixel_scale as f32,
-                    )
-                };
+            let (texture_offset, text_offset) = if (self.ui_buttons[i]
+                .rect
+                .check_collision_point_rec(input_handler.pointer())
+                && self.picked_element.is_none()
+                && input_handler.pointer_up(MouseButton::MOUSE_BUTTON_LEFT)
+                || hotkey_handler.check_down(
+                    input_handler,
+                    (HotkeyCategory::PickButton1 as u8 + i as u8).into(),
+                ))
+                && !self.draw_warning
+            {
+                self.ui_buttons[i].selected = true;
+                (0., 0.)
+            } else {
+                (UI_UTILITY_HEIGHT as f32, 1.)
+            };
 
-            let text_dimensions = get_text_size(
-                font,
-                UTILITY_BUTTONS[i],
-                TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-            );
+            let text = locale.get(UTILITY_BUTTONS[i]);
+            let text_dimensions = get_text_size(font, text, TEXT_SIZE, TEXT_SPACING);
 
             self.ui_buttons[i].draw_with_text_middle(
                 rl,
-                UTILITY_BUTTONS[i],
+                text,
                 font,
-                texture_handler.get_safe(UTILITY_BUTTONS_TEXTURE),
+                texture_handler,
+                UTILITY_BUTTONS_TEXTURE,
                 &Rectangle::new(
                     0.,
                     texture_offset,
@@ -850,95 +962,98 @@ impl SettingsMenuHandler {
                 ),
                 text_dimensions,
                 &Color::RAYWHITE,
-                TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
+                TEXT_SIZE,
+                TEXT_SPACING,
                 Vector2::new(0., text_offset),
-                Vector2::new(0., -(settings_handler.settings.pixel_scale as f32)),
+                Vector2::new(0., -1.),
             );
         }
 
+        let (texture_offset, text_offset) = if self
+            .controls_button
+            .rect
+            .check_collision_point_rec(input_handler.pointer())
+            && self.picked_element.is_none()
+            && !self.draw_warning
+        {
+            (0., 0.)
+        } else {
+            (UI_UTILITY_HEIGHT as f32, 1.)
+        };
+        let text = locale.get(CONTROLS_BUTTON_TEXT);
+        self.controls_button.draw_with_text_middle(
+            rl,
+            text,
+            font,
+            texture_handler,
+            UTILITY_BUTTONS_TEXTURE,
+            &Rectangle::new(
+                0.,
+                texture_offset,
+                UI_UTILITY_WIDTH as f32,
+                UI_UTILITY_HEIGHT as f32,
+            ),
+            get_text_size(font, text, TEXT_SIZE, TEXT_SPACING),
+            &Color::RAYWHITE,
+            TEXT_SIZE,
+            TEXT_SPACING,
+            Vector2::new(0., text_offset),
+            Vector2::new(0., -1.),
+        );
+
         if !self.draw_warning {
             return;
         }
 
-        rl.draw_texture_ex(
-            texture_handler.get_safe(SETTINGS_UI_TEXTURE),
-            Vector2::new(
-                64. * settings_handler.settings.pixel_scale as f32,
-                26. * settings_handler.settings.pixel_scale as f32,
-            ),
-            0.0,
-            settings_handler.settings.pixel_scale as f32,
+        texture_handler.draw(
+            rl,
+            SETTINGS_UI_TEXTURE,
+            Vector2::new(64., 26.),
             Color::WHITE,
         );
 
-        for i in 0..WARNING_TEXT.len() {
-            let text_size = get_text_size(
-                font,
-                WARNING_TEXT[i],
-                TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-            );
-
-            rl.draw_text_pro(
-                font,
-                WARNING_TEXT[i],
-                Vector2::new(
-                    128. * settings_handler.settings.pixel_scale as f32 - text_size.x / 2.,
-                    text_size.y
-                        + 26. * settings_handler.settings.pixel_scale as f32
-                        + text_size.y * i as f32,
-                ),
-                Vector2::zero(),
-                0.,
-                TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-                CustomColor::BLACK_TEXT,
-            );
-        }
+        let text_box = TextBox {
+            width: UI_WARNING_TEXT_WIDTH,
+            lines_per_page: 0,
+            align: Align::Center,
+            size: TEXT_SIZE,
+            spacing: TEXT_SPACING,
+            color: CustomColor::BLACK_TEXT,
+        };
+        text_box.layout(font, locale.get(WARNING_TEXT)).draw(
+            rl,
+            font,
+            0,
+            Vector2::new(128. - UI_WARNING_TEXT_WIDTH / 2., 38.),
+            usize::MAX,
+        );
 
         for i in 0..WARNING_BUTTONS_TEXT.len() {
             let (texture_offset, text_offset) = if (self.ui_buttons[i + UTILITY_BUTTONS.len()]
                 .rect
-                .check_collision_point_rec(
-                    rl.get_mouse_position()
-                        - Vector2::new(
-                            rl.get_screen_width() as f32 / 2.
-                                - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32)
-                                    as f32
-                                    / 2.,
-                            rl.get_screen_height() as f32 / 2.
-                                - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32)
-                                    as f32
-                                    / 2.,
-                        ),
-                )
-                && rl.is_mouse_button_up(MouseButton::MOUSE_BUTTON_LEFT)
-                || hotkey_handler
-                    .check_down(rl, (HotkeyCategory::PickButton1 as u8 + i as u8).into()))
+                .check_collision_point_rec(input_handler.pointer())
+                && input_handler.pointer_up(MouseButton::MOUSE_BUTTON_LEFT)
+                || hotkey_handler.check_down(
+                    input_handler,
+                    (HotkeyCategory::PickButton1 as u8 + i as u8).into(),
+                ))
                 && self.picked_element.is_none()
             {
                 self.ui_buttons[i + UTILITY_BUTTONS.len()].selected = true;
                 (0., 0.)
             } else {
-                (
-                    UI_UTILITY_HEIGHT as f32,
-                    settings_handler.settings.pixel_scale as f32,
-                )
+                (UI_UTILITY_HEIGHT as f32, 1.)
             };
 
-            let text_size = get_text_size(
-                font,
-                WARNING_BUTTONS_TEXT[i],
-                TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-            );
+            let text = locale.get(WARNING_BUTTONS_TEXT[i]);
+            let text_size = get_text_size(font, text, TEXT_SIZE, TEXT_SPACING);
 
             self.ui_buttons[i + WARNING_BUTTONS_TEXT.len()].draw_with_text_middle(
                 rl,
-                WARNING_BUTTONS_TEXT[i],
+                text,
                 font,
-                texture_handler.get_safe(UTILITY_BUTTONS_TEXTURE),
+                texture_handler,
+                UTILITY_BUTTONS_TEXTURE,
                 &Rectangle::new(
                     0.,
                     texture_offset,
@@ -947,11 +1062,69 @@ impl SettingsMenuHandler {
                 ),
                 text_size,
                 &Color::RAYWHITE,
-                TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
+                TEXT_SIZE,
+                TEXT_SPACING,
                 Vector2::new(0., text_offset),
-                Vector2::new(0., -(settings_handler.settings.pixel_scale as f32)),
+                Vector2::new(0., -1.),
             );
         }
     }
 }
+
+impl SceneState for SettingsMenuHandler {
+    // whatever was left unsaved last time is gone, the menu shows what's actually set
+    fn enter(&mut self, app: &mut App, _rl: &mut RaylibHandle) {
+        if std::mem::take(&mut self.controls_open) {
+            return;
+        }
+        self.draw_warning = false;
+        self.picked_element = None;
+        self.align_settings(app.settings_handler.get_settings());
+    }
+
+    fn update(&mut self, app: &mut App, rl: &mut RaylibHandle) {
+        // left and right on a slider move it, a slider held with A just follows the stick
+        let mouse = app.input_handler.pointer();
+        let slider = self.sliders.iter().position(|slider| {
+            slider.rects[SliderStyle::get_outline_rect(&slider.slider_style)]
+                .check_collision_point_rec(mouse)
+        });
+        if let Some(index) = slider
+            && let Some(step) = app.gamepad_handler.step()
+            && step.x != 0.
+            && !self.draw_warning
+        {
+            self.nudge_slider(index, step.x);
+        } else if self
+            .picked_element
+            .is_none_or(|element| element < self.buttons.len())
+        {
+            app.gamepad_handler.navigate(
+                rl,
+                &mut app.input_handler,
+                &app.settings_handler,
+                &self.focus_targets(),
+            );
+        }
+
+        SettingsMenuHandler::update(
+            self,
+            &mut app.scene_handler,
+            &app.input_handler,
+            &mut app.settings_handler,
+            &mut app.hotkey_handler,
+        );
+    }
+
+    fn draw(&mut self, app: &mut App, rl: &mut RaylibDrawHandle) {
+        SettingsMenuHandler::draw(
+            self,
+            &app.locale,
+            &app.font,
+            &app.texture_handler,
+            rl,
+            &app.input_handler,
+            &mut app.hotkey_handler,
+        );
+    }
+}
@@ -1,13 +1,18 @@
 use raylib::prelude::*;
 
 use crate::{
-    map::{LEVEL_HEIGHT_TILES, LEVEL_WIDTH_TILES, Level, TILE_SIZE_PX, TileType},
+    achievements::GameEvent,
+    animation::AnimationPlayer,
+    camera::WorldCamera,
+    map::{LEVEL_HEIGHT_TILES, LEVEL_WIDTH_TILES, Level, TileType},
     music_handler::MusicHandler,
     settings::SettingsHandler,
     texture_handler::TextureHandler,
 };
 
 const SPIRIT_SPEED: f32 = 5.;
+// how close to a tree or a fire counts as there, in tiles
+const REACH_DISTANCE: f32 = 0.1;
 
 pub enum SpiritState {
     Patrol,
@@ -15,6 +20,7 @@ pub enum SpiritState {
     LightFire(usize, usize),
 }
 
+// positions are in tiles, WorldCamera turns them into pixels when drawing
 pub struct Spirit {
     position: Vector2,
     draw_position: Vector2,
@@ -23,6 +29,7 @@ pub struct Spirit {
     state: SpiritState,
     dead: bool,
     teleported: u8,
+    animation: AnimationPlayer,
 }
 
 impl Spirit {
@@ -37,6 +44,7 @@ impl Spirit {
             state: SpiritState::Patrol,
             dead: false,
             teleported: 0,
+            animation: AnimationPlayer::new("spirit"),
         }
     }
 
@@ -50,16 +58,10 @@ impl Spirit {
             state: SpiritState::Patrol,
             dead: false,
             teleported: 0,
+            animation: AnimationPlayer::new("spirit"),
         }
     }
 
-    pub fn rescale(&mut self, prev_scale: f32, new_scale: f32) {
-        self.position.x = self.position.x / prev_scale * new_scale;
-        self.position.y = self.position.y / prev_scale * new_scale;
-        self.draw_position.x = self.draw_position.x / prev_scale * new_scale;
-        self.draw_position.y = self.draw_position.y / prev_scale * new_scale;
-    }
-
     #[profiling::function]
     pub fn get_direction(&self) -> Vector2 {
         self.direction
@@ -70,7 +72,6 @@ impl Spirit {
         self.position // + Vector2::one() * (TILE_SIZE / 2) as f32
     }
 
-    #[allow(unused)]
     #[profiling::function]
     pub fn get_teleported(&self) -> u8 {
         self.teleported
@@ -89,6 +90,10 @@ impl Spirit {
         self.dead
     }
 
+    pub fn is_patrolling(&self) -> bool {
+        matches!(self.state, SpiritState::Patrol)
+    }
+
     #[profiling::function]
     pub fn set_state(&mut self, state: SpiritState) {
         self.state = state;
@@ -99,38 +104,42 @@ impl Spirit {
         &mut self,
         level: &mut Level,
         music_handler: &MusicHandler,
-        rl: &mut RaylibHandle,
+        delta: f32,
         settings_handler: &SettingsHandler,
     ) {
+        self.animation.update(delta);
+
         match self.state {
             SpiritState::Patrol => {
-                if self.timer >= 0.5 {
+                if self.timer >= level.modifier.patrol_tick() {
                     self.patrol(music_handler, level, settings_handler);
                     self.timer = 0.0;
                 } else {
-                    self.timer += rl.get_frame_time();
-                    self.update_position_smoothly(rl);
+                    self.timer += delta;
+                    self.update_position_smoothly(delta);
                 }
             }
             SpiritState::ChopTree(x, y) => {
-                self.chop_tree(x, y, level, music_handler, rl, settings_handler)
+                self.chop_tree(x, y, level, music_handler, delta, settings_handler)
             }
             SpiritState::LightFire(x, y) => {
-                self.light_fire(x, y, level, music_handler, rl, settings_handler)
+                self.light_fire(x, y, level, music_handler, delta, settings_handler)
             }
         }
     }
 
+    pub fn pause_animation(&mut self, paused: bool) {
+        self.animation.set_paused(paused);
+    }
+
     #[profiling::function]
     pub fn kill_spirit(&mut self) {
         self.dead = true;
     }
 
     #[profiling::function]
-    pub fn update_position_smoothly(&mut self, rl: &mut RaylibHandle) {
-        self.draw_position = self
-            .draw_position
-            .lerp(self.position, SPIRIT_SPEED * rl.get_frame_time());
+    pub fn update_position_smoothly(&mut self, delta: f32) {
+        self.draw_position = self.draw_position.lerp(self.position, SPIRIT_SPEED * delta);
     }
 
     fn patrol(
@@ -140,23 +149,13 @@ impl Spirit {
         settings_handler: &SettingsHandler,
     ) {
         let (tile_x, tile_y) = (
-            (self.get_position().x
-                / (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32)
-                .floor() as usize,
-            (self.get_position().y
-                / (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32)
-                .floor() as usize,
+            self.get_position().x.floor() as usize,
+            self.get_position().y.floor() as usize,
         );
 
-        let mut next = self.get_position()
-            + self.direction * (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32;
+        let mut next = self.get_position() + self.direction;
 
-        let (next_x, next_y) = (
-            (next.x / (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32).round()
-                as usize,
-            (next.y / (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32).round()
-                as usize,
-        );
+        let (next_x, next_y) = (next.x.round() as usize, next.y.round() as usize);
 
         if self.teleported != 0 {
             self.teleported -= 1;
@@ -186,20 +185,31 @@ impl Spirit {
                 self.dead = true;
                 music_handler.play("foom", &settings_handler.get_settings());
                 level.survive();
+                level.push_event(GameEvent::SpiritSaved);
                 return;
             }
 
             TileType::Swamp { teleport_position } => {
                 if self.teleported == 0 {
                     self.teleported = 2;
-                    next = teleport_position
-                        * (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32;
+                    next = teleport_position;
+                    level.push_event(GameEvent::SwampTeleport);
                 }
             }
             _ => {}
         }
 
+        let off_map = next.x < 0.
+            || next.y < 0.
+            || next_x >= LEVEL_WIDTH_TILES
+            || next_y >= LEVEL_HEIGHT_TILES;
+        if self.teleported <= 1 && level.safe_border && off_map {
+            self.direction *= -1.;
+            return;
+        }
+
         if self.teleported <= 1
+            && !level.safe_border
             && (next_x >= LEVEL_WIDTH_TILES
                 || next_y >= LEVEL_HEIGHT_TILES
                 || tile_x <= 0
@@ -245,7 +255,7 @@ impl Spirit {
         y: usize,
         level: &mut Level,
         music_handler: &MusicHandler,
-        rl: &RaylibHandle,
+        delta: f32,
         settings_handler: &SettingsHandler,
     ) {
         match level.tiles[x][y] {
@@ -272,39 +282,44 @@ impl Spirit {
             }
         }
 
-        let target = Vector2::new(
-            x as f32 * (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32,
-            y as f32 * (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32,
-        );
+        let target = Vector2::new(x as f32, y as f32);
 
-        if self.position.distance_to(target)
-            <= ((settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) / 10) as f32
-        {
+        if self.position.distance_to(target) <= REACH_DISTANCE {
             let tile = level.tiles.get_mut(x).unwrap().get_mut(y).unwrap();
-            match tile {
+            let sheet = match tile {
                 TileType::FireTD {
                     active,
                     selected: _,
+                } => {
+                    *active = true;
+                    "fire_td"
                 }
-                | TileType::FireLR {
+                TileType::FireLR {
                     active,
                     selected: _,
+                } => {
+                    *active = true;
+                    "fire_lr"
                 }
-                | TileType::FireStop {
+                TileType::FireStop {
                     active,
                     selected: _,
-                } => *active = true,
+                } => {
+                    *active = true;
+                    "fire_stop"
+                }
                 _ => {
                     panic!("no such tile bruh")
                 }
-            }
+            };
+            // it catches first and only then burns
+            level.play_tile_animation(x, y, sheet, "ignite");
+            level.push_event(GameEvent::BonfireLit);
             self.dead = true;
             music_handler.play("foom", &settings_handler.get_settings());
         }
 
-        self.position = self
-            .position
-            .lerp(target, SPIRIT_SPEED * rl.get_frame_time());
+        self.position = self.position.lerp(target, SPIRIT_SPEED * delta);
 
         self.draw_position = self.position;
     }
@@ -315,7 +330,7 @@ impl Spirit {
         y: usize,
         level: &mut Level,
         music_handler: &MusicHandler,
-        rl: &RaylibHandle,
+        delta: f32,
         settings_handler: &SettingsHandler,
     ) {
         match level.tiles[x][y] {
@@ -329,52 +344,28 @@ impl Spirit {
             }
         };
 
-        let target = Vector2::new(
-            x as f32 * (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32,
-            y as f32 * (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32,
-        );
+        let target = Vector2::new(x as f32, y as f32);
 
-        if self.position.distance_to(target)
-            <= ((settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) / 10) as f32
-        {
+        if self.position.distance_to(target) <= REACH_DISTANCE {
             level.tiles[x][y] = TileType::Air { selected: false };
+            level.spawn_effect(target, AnimationPlayer::with_clip("trees", "chop"));
             level.add_wood();
+            level.push_event(GameEvent::TreeChopped);
             self.dead = true;
             music_handler.play("foom", &settings_handler.get_settings());
         }
 
-        self.position = self
-            .position
-            .lerp(target, SPIRIT_SPEED * rl.get_frame_time());
+        self.position = self.position.lerp(target, SPIRIT_SPEED * delta);
 
         self.draw_position = self.position;
     }
 
     #[profiling::function]
-    pub fn draw(
-        &self,
-        rl: &mut RaylibDrawHandle,
-        texture_handler: &TextureHandler,
-        settings_handler: &SettingsHandler,
-    ) {
-        let source = Rectangle::new(
-            ((rl.get_time() * 8.) % 4.).floor() as f32 * 16.,
-            16.,
-            16.,
-            16.,
-        );
-
-        rl.draw_texture_pro(
-            texture_handler.get_safe("spirit"),
-            source,
-            Rectangle::new(
-                self.get_draw_position().x,
-                self.get_draw_position().y,
-                (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32,
-                (settings_handler.settings.pixel_scale as i32 * TILE_SIZE_PX) as f32,
-            ),
-            Vector2::zero(),
-            0.0,
+    pub fn draw(&self, rl: &mut RaylibDrawHandle, texture_handler: &TextureHandler) {
+        texture_handler.draw_animation(
+            rl,
+            &self.animation,
+            WorldCamera.tile_rect(self.get_draw_position()),
             Color::WHITE,
         );
 
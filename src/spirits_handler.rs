@@ -1,11 +1,19 @@
-use crate::{
-    Spirit, map::TILE_SIZE_PX, metadata_handler::MetadataHandler, settings::SettingsHandler,
-};
+use crate::{Spirit, metadata_handler::MetadataHandler};
 use raylib::prelude::*;
 use std::collections::HashMap;
 
+pub struct PortalSpawner {
+    pub position: [u8; 2],
+    pub direction: [i8; 2],
+    pub left: u8,
+    pub interval: f32,
+    pub timer: f32,
+}
+
 pub struct SpiritsHandler {
     pub spirits: HashMap<usize, Spirit>,
+    pub portals: Vec<PortalSpawner>,
+    next_key: usize,
 }
 
 impl SpiritsHandler {
@@ -13,19 +21,12 @@ impl SpiritsHandler {
     pub fn new() -> Self {
         Self {
             spirits: HashMap::new(),
-        }
-    }
-    pub fn rescale_ui(&mut self, prev_scale: f32, new_scale: f32) {
-        for (_, spirit) in self.spirits.iter_mut() {
-            spirit.rescale(prev_scale, new_scale);
+            portals: Vec::new(),
+            next_key: 0,
         }
     }
     #[profiling::function]
-    pub fn spawn_spirits(
-        &mut self,
-        metadata_handler: &mut MetadataHandler,
-        settings_handler: &SettingsHandler,
-    ) {
+    pub fn spawn_spirits(&mut self, metadata_handler: &mut MetadataHandler) {
         self.spirits = HashMap::new();
         for spirits_list in 0..metadata_handler.spirits.len() {
             for i in 0..metadata_handler.spirits[spirits_list].amount {
@@ -33,12 +34,8 @@ impl SpiritsHandler {
                     i as usize + spirits_list * 4,
                     Spirit::new(
                         Vector2::new(
-                            metadata_handler.spirits[spirits_list].position[0] as f32
-                                * (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
-                            metadata_handler.spirits[spirits_list].position[1] as f32
-                                * (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32)
-                                    as f32,
+                            metadata_handler.spirits[spirits_list].position[0] as f32,
+                            metadata_handler.spirits[spirits_list].position[1] as f32,
                         ),
                         Vector2::new(
                             metadata_handler.spirits[spirits_list].direction[0] as f32,
@@ -48,5 +45,51 @@ impl SpiritsHandler {
                 );
             }
         }
+
+        self.next_key = self.spirits.keys().max().map_or(0, |key| key + 1);
+
+        self.portals = Vec::new();
+        for portal in metadata_handler.portals.iter() {
+            self.portals.push(PortalSpawner {
+                position: portal.position,
+                direction: portal.direction,
+                left: portal.amount,
+                interval: portal.interval,
+                timer: portal.delay,
+            });
+        }
+    }
+
+    // spirits that portals are still going to release,
+    // the level can't be over while there are some
+    #[profiling::function]
+    pub fn pending_spawns(&self) -> usize {
+        self.portals.iter().map(|portal| portal.left as usize).sum()
+    }
+
+    #[profiling::function]
+    pub fn update_portals(&mut self, delta: f32) {
+        for portal in self.portals.iter_mut() {
+            if portal.left == 0 {
+                continue;
+            }
+
+            portal.timer -= delta;
+            if portal.timer > 0. {
+                continue;
+            }
+
+            portal.timer += portal.interval;
+            portal.left -= 1;
+
+            self.spirits.insert(
+                self.next_key,
+                Spirit::new(
+                    Vector2::new(portal.position[0] as f32, portal.position[1] as f32),
+                    Vector2::new(portal.direction[0] as f32, portal.direction[1] as f32),
+                ),
+            );
+            self.next_key += 1;
+        }
     }
 }
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::profile;
+
+const STATS_PATH: &str = "stats.json";
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ProgressStats {
+    // "level_3" or "daily_20261019" -> how many times the hint key was pressed there
+    #[serde(default)]
+    pub hints: HashMap<String, u32>,
+}
+
+pub struct StatsHandler {
+    pub stats: ProgressStats,
+}
+
+impl StatsHandler {
+    #[profiling::function]
+    pub fn new() -> Self {
+        let Ok(s) = std::fs::read_to_string(profile::path(STATS_PATH)) else {
+            return Self {
+                stats: ProgressStats::default(),
+            };
+        };
+        let Ok(stats) = serde_json::from_str(&s) else {
+            return Self {
+                stats: ProgressStats::default(),
+            };
+        };
+
+        Self { stats }
+    }
+
+    #[profiling::function]
+    pub fn save(&self) {
+        let Ok(s) = serde_json::to_string_pretty(&self.stats) else {
+            panic!("COULDN'T SERIALIZE STATS TO JSON");
+        };
+
+        std::fs::write(profile::path(STATS_PATH), s).expect("COULDN'T WRITE STATS TO FILE");
+    }
+
+    pub fn add_hint(&mut self, key: &str) {
+        *self.stats.hints.entry(key.to_string()).or_insert(0) += 1;
+        self.save();
+    }
+}
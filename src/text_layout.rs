@@ -0,0 +1,448 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use raylib::prelude::*;
+
+use crate::ui::get_text_size;
+
+// the same gap raylib leaves between lines of its own
+const LINE_GAP: f32 = 2.;
+// emphasis is the same glyphs drawn twice a pixel apart, the font has no bold
+const EMPHASIS_OFFSET: f32 = 1.;
+
+const EMPHASIS_MARK: char = '*';
+// ** is a star that stays a star
+const EMPHASIS_ESCAPE: &str = "**";
+const COLOR_TAG_START: &str = "{#";
+const COLOR_TAG_END: &str = "{/}";
+
+// most text on screen is the same every frame, past this many the cache starts over
+const CACHE_SIZE: usize = 256;
+
+// the text, the font it's measured with and everything about the box that moves lines around
+#[derive(PartialEq, Eq, Hash)]
+struct CacheKey {
+    text: String,
+    font: u32,
+    width: u32,
+    lines_per_page: usize,
+    size: u32,
+    spacing: u32,
+}
+
+type Pages = Vec<Vec<Line>>;
+
+static CACHE: LazyLock<Mutex<HashMap<CacheKey, Arc<Pages>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// a reloaded font can come back with the old one's texture id and different glyphs
+pub fn clear_cache() {
+    CACHE.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// no color is the box's own, so a cached layout can be drawn in any
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Style {
+    color: Option<Color>,
+    emphasis: bool,
+}
+
+// a piece of a line in one style, x is from the start of the line
+struct Run {
+    text: String,
+    style: Style,
+    x: f32,
+}
+
+struct Line {
+    runs: Vec<Run>,
+    width: f32,
+    chars: usize,
+}
+
+// where text has to fit and how it looks there,
+// lines_per_page 0 puts everything on one page no matter how long it gets
+#[derive(Clone, Copy)]
+pub struct TextBox {
+    pub width: f32,
+    pub lines_per_page: usize,
+    pub align: Align,
+    pub size: f32,
+    pub spacing: f32,
+    pub color: Color,
+}
+
+// text already cut into lines and pages, lines break at spaces and at \n,
+// *stars* around words make them stand out, ** is a plain star, {#rrggbb}...{/} paints them
+pub struct Layout {
+    text_box: TextBox,
+    pages: Arc<Pages>,
+}
+
+impl TextBox {
+    #[profiling::function]
+    pub fn layout(&self, font: &Font, text: &str) -> Layout {
+        let key = CacheKey {
+            text: text.to_string(),
+            font: font.texture.id,
+            width: self.width.to_bits(),
+            lines_per_page: self.lines_per_page,
+            size: self.size.to_bits(),
+            spacing: self.spacing.to_bits(),
+        };
+
+        let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        let pages = match cache.get(&key) {
+            Some(pages) => pages.clone(),
+            None => {
+                let measure = |text: &str| get_text_size(font, text, self.size, self.spacing).x;
+                let pages = Arc::new(self.pages(&measure, text));
+                if cache.len() >= CACHE_SIZE {
+                    cache.clear();
+                }
+                cache.insert(key, pages.clone());
+                pages
+            }
+        };
+
+        Layout {
+            text_box: *self,
+            pages,
+        }
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.size + LINE_GAP
+    }
+
+    fn pages(&self, measure: &dyn Fn(&str) -> f32, text: &str) -> Pages {
+        let lines: Vec<Line> = self
+            .wrap(measure, &parse(text))
+            .into_iter()
+            .map(|line| self.line(measure, line))
+            .collect();
+
+        let per_page = if self.lines_per_page == 0 {
+            lines.len().max(1)
+        } else {
+            self.lines_per_page
+        };
+
+        let mut pages = Vec::new();
+        let mut lines = lines.into_iter().peekable();
+        while lines.peek().is_some() {
+            pages.push(lines.by_ref().take(per_page).collect());
+        }
+        if pages.is_empty() {
+            pages.push(Vec::new());
+        }
+
+        pages
+    }
+
+    fn wrap(
+        &self,
+        measure: &dyn Fn(&str) -> f32,
+        chars: &[(char, Style)],
+    ) -> Vec<Vec<(char, Style)>> {
+        let text = |line: &[(char, Style)]| line.iter().map(|(c, _)| *c).collect::<String>();
+
+        let mut lines = Vec::new();
+        for paragraph in chars.split(|(c, _)| *c == '\n') {
+            let mut line: Vec<(char, Style)> = Vec::new();
+
+            for word in paragraph
+                .split(|(c, _)| *c == ' ')
+                .filter(|word| !word.is_empty())
+            {
+                let separator = (!line.is_empty()).then_some((' ', word[0].1));
+                let candidate: Vec<(char, Style)> = line
+                    .iter()
+                    .copied()
+                    .chain(separator)
+                    .chain(word.iter().copied())
+                    .collect();
+                if measure(&text(&candidate)) <= self.width {
+                    line = candidate;
+                    continue;
+                }
+
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+
+                // a word wider than the whole box gets cut wherever it runs out
+                for &c in word {
+                    line.push(c);
+                    if line.len() > 1 && measure(&text(&line)) > self.width {
+                        line.pop();
+                        lines.push(std::mem::take(&mut line));
+                        line.push(c);
+                    }
+                }
+            }
+
+            lines.push(line);
+        }
+
+        lines
+    }
+
+    fn line(&self, measure: &dyn Fn(&str) -> f32, chars: Vec<(char, Style)>) -> Line {
+        let mut runs: Vec<Run> = Vec::new();
+        let mut text = String::new();
+
+        for (c, style) in chars.iter() {
+            if runs.last().is_none_or(|run| run.style != *style) {
+                let x = if text.is_empty() {
+                    0.
+                } else {
+                    measure(&text) + self.spacing
+                };
+                runs.push(Run {
+                    text: String::new(),
+                    style: *style,
+                    x,
+                });
+            }
+
+            if let Some(run) = runs.last_mut() {
+                run.text.push(*c);
+            }
+            text.push(*c);
+        }
+
+        Line {
+            runs,
+            width: measure(&text),
+            chars: chars.len(),
+        }
+    }
+}
+
+// markup goes away here, every character that's left knows how it's drawn
+fn parse(text: &str) -> Vec<(char, Style)> {
+    let mut chars = Vec::new();
+    let mut style = Style {
+        color: None,
+        emphasis: false,
+    };
+
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix(EMPHASIS_ESCAPE) {
+            chars.push((EMPHASIS_MARK, style));
+            rest = after;
+            continue;
+        }
+
+        if c == EMPHASIS_MARK {
+            style.emphasis = !style.emphasis;
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        if let Some(after) = rest.strip_prefix(COLOR_TAG_END) {
+            style.color = None;
+            rest = after;
+            continue;
+        }
+
+        if let Some(after) = rest.strip_prefix(COLOR_TAG_START)
+            && let Some(end) = after.find('}')
+            && let Ok(color) = Color::from_hex(&after[..end])
+        {
+            style.color = Some(color);
+            rest = &after[end + 1..];
+            continue;
+        }
+
+        chars.push((c, style));
+        rest = &rest[c.len_utf8()..];
+    }
+
+    chars
+}
+
+impl Layout {
+    pub fn pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    // of the widest line, for whatever goes behind the text
+    pub fn width(&self, page: usize) -> f32 {
+        self.pages[page]
+            .iter()
+            .map(|line| line.width)
+            .fold(0., f32::max)
+    }
+
+    pub fn height(&self, page: usize) -> f32 {
+        let lines = self.pages[page].len() as f32;
+        (lines * self.text_box.line_height() - LINE_GAP).max(0.)
+    }
+
+    pub fn chars(&self, page: usize) -> usize {
+        self.pages[page].iter().map(|line| line.chars).sum()
+    }
+
+    // visible counts from the start of the page, for text that types itself out
+    #[profiling::function]
+    pub fn draw(
+        &self,
+        rl: &mut RaylibDrawHandle,
+        font: &Font,
+        page: usize,
+        position: Vector2,
+        visible: usize,
+    ) {
+        let text_box = &self.text_box;
+        let mut left = visible;
+
+        for (i, line) in self.pages[page].iter().enumerate() {
+            let x = position.x
+                + match text_box.align {
+                    Align::Left => 0.,
+                    Align::Center => (text_box.width - line.width) / 2.,
+                    Align::Right => text_box.width - line.width,
+                };
+            let y = position.y + i as f32 * text_box.line_height();
+
+            for run in line.runs.iter() {
+                if left == 0 {
+                    return;
+                }
+
+                let text: String = run.text.chars().take(left).collect();
+                left -= text.chars().count();
+
+                // the box can be see-through, a painted word shouldn't stick out of it
+                let color = run.style.color.map_or(text_box.color, |color| {
+                    color.alpha(text_box.color.a as f32 / 255.)
+                });
+                let run_position = Vector2::new(x + run.x, y);
+                rl.draw_text_ex(
+                    font,
+                    &text,
+                    run_position,
+                    text_box.size,
+                    text_box.spacing,
+                    color,
+                );
+                if run.style.emphasis {
+                    rl.draw_text_ex(
+                        font,
+                        &text,
+                        run_position + Vector2::new(EMPHASIS_OFFSET, 0.),
+                        text_box.size,
+                        text_box.spacing,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every character is 4 pixels wide with no spacing, so widths are easy to count
+    fn measure(text: &str) -> f32 {
+        text.chars().count() as f32 * 4.
+    }
+
+    fn text_box(width: f32) -> TextBox {
+        TextBox {
+            width,
+            lines_per_page: 0,
+            align: Align::Left,
+            size: 8.,
+            spacing: 0.,
+            color: Color::WHITE,
+        }
+    }
+
+    fn lines(width: f32, text: &str) -> Vec<String> {
+        text_box(width)
+            .wrap(&measure, &parse(text))
+            .iter()
+            .map(|line| line.iter().map(|(c, _)| *c).collect())
+            .collect()
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        assert_eq!(lines(40., "one two three four"), ["one two", "three four"]);
+    }
+
+    #[test]
+    fn keeps_line_breaks() {
+        assert_eq!(lines(400., "one\ntwo"), ["one", "two"]);
+    }
+
+    #[test]
+    fn cuts_words_wider_than_the_box() {
+        assert_eq!(lines(16., "abcdefghij"), ["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn pages_hold_lines_per_page() {
+        let text_box = TextBox {
+            lines_per_page: 2,
+            ..text_box(4.)
+        };
+        let pages = text_box.pages(&measure, "a b c d e");
+        let lines: Vec<usize> = pages.iter().map(Vec::len).collect();
+        assert_eq!(lines, [2, 2, 1]);
+    }
+
+    #[test]
+    fn stars_mark_emphasis() {
+        let chars = parse("a *b* c");
+        let text: String = chars.iter().map(|(c, _)| *c).collect();
+        assert_eq!(text, "a b c");
+        assert!(chars[2].1.emphasis);
+        assert!(!chars[4].1.emphasis);
+    }
+
+    #[test]
+    fn double_star_is_a_star() {
+        let chars = parse("5 ** 2");
+        let text: String = chars.iter().map(|(c, _)| *c).collect();
+        assert_eq!(text, "5 * 2");
+        assert!(chars.iter().all(|(_, style)| !style.emphasis));
+    }
+
+    #[test]
+    fn color_tags_paint_until_closed() {
+        let chars = parse("a{#ff0000}b{/}c");
+        let text: String = chars.iter().map(|(c, _)| *c).collect();
+        assert_eq!(text, "abc");
+        assert_eq!(chars[0].1.color, None);
+        assert_eq!(chars[1].1.color, Some(Color::new(255, 0, 0, 255)));
+        assert_eq!(chars[2].1.color, None);
+    }
+
+    #[test]
+    fn runs_split_on_style() {
+        let line = text_box(400.).line(&measure, parse("a *bc* d"));
+        let runs: Vec<(&str, f32)> = line
+            .runs
+            .iter()
+            .map(|run| (run.text.as_str(), run.x))
+            .collect();
+        assert_eq!(runs, [("a ", 0.), ("bc", 8.), (" d", 16.)]);
+        assert_eq!(line.width, 24.);
+    }
+}
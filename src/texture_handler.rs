@@ -1,64 +1,343 @@
-use std::{collections::HashMap, fs};
+use std::collections::HashMap;
 
-use raylib::prelude::*;
+use raylib::{ffi, prelude::*};
+use serde::Deserialize;
 
+use crate::animation::{AnimationPlayer, Animations};
+
+const ASSETS_PATH: &str = "static/assets.json";
+const TEXTURE_FOLDER: &str = "static/textures/";
+const FONT_FOLDER: &str = "static/fonts/";
+
+// keeps neighbours in the atlas from bleeding into each other
+const ATLAS_PADDING: i32 = 1;
+// what raylib pads glyphs with when it loads a font by itself
+const GLYPH_PADDING: i32 = 4;
+
+#[derive(Deserialize)]
+struct RegionData {
+    texture: String,
+    rect: [f32; 4],
+}
+
+#[derive(Deserialize)]
+struct FontFile {
+    file: String,
+    size: i32,
+}
+
+#[derive(Deserialize)]
+struct FontData {
+    #[serde(flatten)]
+    default: FontFile,
+    // tried in order before the default file, a glyph comes from the first one that has it,
+    // everything is rendered at the size of the first one
+    #[serde(default)]
+    languages: HashMap<String, Vec<FontFile>>,
+}
+
+#[derive(Deserialize)]
+struct AssetsWrapper {
+    atlas_width: i32,
+    textures: HashMap<String, String>,
+    #[serde(default)]
+    regions: HashMap<String, RegionData>,
+    fonts: HashMap<String, FontData>,
+}
+
+// every texture from the manifest is packed into one atlas at startup,
+// a name (a texture or a region of one) only points at a rectangle in it
 pub struct TextureHandler {
-    textures: HashMap<String, Texture2D>,
-    default_texture: Texture2D,
+    atlas: Texture2D,
+    sprites: HashMap<String, Rectangle>,
+    fonts: HashMap<String, FontData>,
+    animations: Animations,
 }
-const TEXTURE_FOLDER: &str = "static/textures/";
-const DEFAULT_TEXTURE: &str = "static/textures/tree.png";
 
 impl TextureHandler {
     #[profiling::function]
     pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
-        let mut textures = HashMap::new();
-
-        let filenames = fs::read_dir(TEXTURE_FOLDER).unwrap();
+        let Ok(string_json) = std::fs::read_to_string(ASSETS_PATH) else {
+            panic!("COULDN'T LOAD JSON FOR ASSETS");
+        };
+        let Ok(wrapper) = serde_json::from_str::<AssetsWrapper>(&string_json) else {
+            panic!("COULDN'T PARSE JSON FOR ASSETS");
+        };
 
-        for filename in filenames {
-            let file = match filename {
-                Ok(f) => f,
-                Err(e) => panic!("Couldn't load this texture - {e}"),
+        let mut images = Vec::new();
+        for (name, file) in wrapper.textures.iter() {
+            let Ok(image) = Image::load_image(&format!("{TEXTURE_FOLDER}{file}")) else {
+                panic!("COULDN'T LOAD TEXTURE {name} FROM {file}");
             };
+            images.push((name.clone(), image));
+        }
 
-            let name = file
-                .file_name()
-                .into_string()
-                .unwrap()
-                .split('.')
-                .next()
-                .unwrap()
-                .to_string();
+        let (places, height) = Self::pack(&images, wrapper.atlas_width);
 
-            let texture = rl
-                .load_texture(&thread, file.path().to_str().unwrap())
-                .unwrap();
+        let mut atlas_image = Image::gen_image_color(wrapper.atlas_width, height, Color::BLANK);
+        let mut sprites = HashMap::new();
+        for ((name, image), place) in images.iter().zip(places) {
+            atlas_image.draw(
+                image,
+                Rectangle::new(0., 0., place.width, place.height),
+                place,
+                Color::WHITE,
+            );
+            sprites.insert(name.clone(), place);
+        }
 
-            textures.insert(name, texture);
+        for (name, region) in wrapper.regions.iter() {
+            let Some(texture) = sprites.get(&region.texture) else {
+                panic!(
+                    "REGION {name} IS CUT FROM A MISSING TEXTURE {}",
+                    region.texture
+                );
+            };
+            let [x, y, width, height] = region.rect;
+            if x < 0. || y < 0. || x + width > texture.width || y + height > texture.height {
+                panic!("REGION {name} DOESN'T FIT INTO {}", region.texture);
+            }
+            let rect = Rectangle::new(texture.x + x, texture.y + y, width, height);
+            sprites.insert(name.clone(), rect);
         }
 
-        let default_texture = rl.load_texture(&thread, DEFAULT_TEXTURE).unwrap();
+        let Ok(atlas) = rl.load_texture_from_image(thread, &atlas_image) else {
+            panic!("COULDN'T LOAD THE TEXTURE ATLAS");
+        };
+
         Self {
-            textures,
-            default_texture,
+            atlas,
+            sprites,
+            fonts: wrapper.fonts,
+            animations: Animations::new(),
         }
     }
 
+    // shelf packing, tallest first, the atlas only grows as tall as the rows need
+    fn pack(images: &[(String, Image)], width: i32) -> (Vec<Rectangle>, i32) {
+        let mut order: Vec<usize> = (0..images.len()).collect();
+        order.sort_by_key(|&i| -images[i].1.height());
+
+        let mut places = vec![Rectangle::default(); images.len()];
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for i in order {
+            let (name, image) = &images[i];
+            if image.width() > width {
+                panic!("TEXTURE {name} IS WIDER THAN THE ATLAS");
+            }
+
+            if x + image.width() > width {
+                x = 0;
+                y += row_height + ATLAS_PADDING;
+                row_height = 0;
+            }
+
+            places[i] = Rectangle::new(
+                x as f32,
+                y as f32,
+                image.width() as f32,
+                image.height() as f32,
+            );
+            x += image.width() + ATLAS_PADDING;
+            row_height = row_height.max(image.height());
+        }
+
+        (places, y + row_height)
+    }
+
+    // everything the game refers to by name, grouped by where it's referred from,
+    // all the problems come back at once, one per line
     #[profiling::function]
-    pub fn get(&self, str: &str) -> &Texture2D {
-        return self.textures.get(str).unwrap();
+    pub fn validate(
+        &self,
+        textures: &[(&str, Vec<String>)],
+        sheets: &[(&str, Vec<String>)],
+    ) -> Result<(), String> {
+        let mut problems = Vec::new();
+
+        for (source, names) in textures.iter() {
+            for name in names
+                .iter()
+                .filter(|name| !self.sprites.contains_key(*name))
+            {
+                problems.push(format!("{source}: no texture or region \"{name}\""));
+            }
+        }
+
+        for (source, names) in sheets.iter() {
+            for name in names.iter().filter(|name| !self.animations.has_sheet(name)) {
+                problems.push(format!("{source}: no animation sheet \"{name}\""));
+            }
+        }
+
+        for (sheet, frame) in self.animations.frames() {
+            let Some(texture) = self.sprites.get(sheet) else {
+                problems.push(format!("animations.json: sheet \"{sheet}\" has no texture"));
+                continue;
+            };
+            if frame.x + frame.width > texture.width || frame.y + frame.height > texture.height {
+                problems.push(format!(
+                    "animations.json: frame {:?} is outside of \"{sheet}\"",
+                    [frame.x, frame.y, frame.width, frame.height]
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        Err(problems.join("\n"))
     }
 
+    // only the glyphs asked for get rendered, a language without files of its own uses the default one
     #[profiling::function]
-    pub fn get_safe(&self, str: &str) -> &Texture2D {
-        let texture = match self.textures.get(str) {
-            Some(f) => f,
-            _ => {
-                //println!("COULDN'T LOAD PROPER TEXTURE! USING DEFAULT - {DEFAULT_TEXTURE}");
-                &self.default_texture
+    pub fn load_font(
+        &self,
+        _rl: &mut RaylibHandle,
+        _thread: &RaylibThread,
+        name: &str,
+        language: &str,
+        glyphs: &str,
+    ) -> Font {
+        let Some(font) = self.fonts.get(name) else {
+            panic!("NO FONT {name} IN THE MANIFEST");
+        };
+        let chain: Vec<&FontFile> = font
+            .languages
+            .get(language)
+            .into_iter()
+            .flatten()
+            .chain([&font.default])
+            .collect();
+        let size = chain[0].size;
+
+        let mut codepoints: Vec<i32> = glyphs.chars().map(|c| c as i32).collect();
+        let count = codepoints.len();
+        let mut picked: Vec<Option<ffi::GlyphInfo>> = vec![None; count];
+
+        for file in chain.iter() {
+            let Ok(data) = std::fs::read(format!("{FONT_FOLDER}{}", file.file)) else {
+                panic!("COULDN'T LOAD FONT {name} FROM {}", file.file);
+            };
+            // SAFETY: raylib hands back one glyph per codepoint, the images it keeps get
+            // taken out of its array before the rest is freed
+            unsafe {
+                let loaded = ffi::LoadFontData(
+                    data.as_ptr(),
+                    data.len() as i32,
+                    size,
+                    codepoints.as_mut_ptr(),
+                    count as i32,
+                    ffi::FontType::FONT_DEFAULT as i32,
+                );
+                if loaded.is_null() {
+                    panic!("COULDN'T LOAD FONT {name} FROM {}", file.file);
+                }
+                let loaded_glyphs = std::slice::from_raw_parts_mut(loaded, count);
+                for (glyph, slot) in loaded_glyphs.iter_mut().zip(picked.iter_mut()) {
+                    if slot.is_none() && !glyph.image.data.is_null() {
+                        *slot = Some(*glyph);
+                        glyph.image = std::mem::zeroed();
+                    }
+                }
+                ffi::UnloadFontData(loaded, count as i32);
+            }
+        }
+
+        // nobody has it, it takes no space and draws nothing
+        let merged: Vec<ffi::GlyphInfo> = picked
+            .into_iter()
+            .zip(codepoints.iter())
+            .map(|(glyph, &value)| {
+                glyph.unwrap_or(ffi::GlyphInfo {
+                    value,
+                    // SAFETY: an image with no data and no size
+                    ..unsafe { std::mem::zeroed() }
+                })
+            })
+            .collect();
+
+        // SAFETY: the same steps LoadFontEx takes, the font owns the glyphs and rectangles after
+        unsafe {
+            let glyphs = ffi::MemAlloc((count * std::mem::size_of::<ffi::GlyphInfo>()) as u32)
+                as *mut ffi::GlyphInfo;
+            std::ptr::copy_nonoverlapping(merged.as_ptr(), glyphs, count);
+
+            let mut recs = std::ptr::null_mut();
+            let atlas =
+                ffi::GenImageFontAtlas(glyphs, &mut recs, count as i32, size, GLYPH_PADDING, 0);
+            let texture = ffi::LoadTextureFromImage(atlas);
+            for i in 0..count {
+                let glyph = &mut *glyphs.add(i);
+                ffi::UnloadImage(glyph.image);
+                glyph.image = ffi::ImageFromImage(atlas, *recs.add(i));
             }
+            ffi::UnloadImage(atlas);
+
+            Font::from_raw(ffi::Font {
+                baseSize: size,
+                glyphCount: count as i32,
+                glyphPadding: GLYPH_PADDING,
+                texture,
+                recs,
+                glyphs,
+            })
+        }
+    }
+
+    fn sprite(&self, name: &str) -> Rectangle {
+        let Some(sprite) = self.sprites.get(name) else {
+            panic!("NO TEXTURE {name}");
         };
-        return texture;
+        *sprite
+    }
+
+    #[profiling::function]
+    pub fn draw(&self, rl: &mut RaylibDrawHandle, name: &str, position: Vector2, tint: Color) {
+        rl.draw_texture_rec(&self.atlas, self.sprite(name), position, tint);
+    }
+
+    // source is relative to the sprite, whole frames past its edge wrap around
+    // the same way they did back when every texture was its own
+    #[profiling::function]
+    pub fn draw_pro(
+        &self,
+        rl: &mut RaylibDrawHandle,
+        name: &str,
+        source: Rectangle,
+        dest: Rectangle,
+        tint: Color,
+    ) {
+        let sprite = self.sprite(name);
+        let source = Rectangle::new(
+            sprite.x + source.x.rem_euclid(sprite.width),
+            sprite.y + source.y.rem_euclid(sprite.height),
+            source.width,
+            source.height,
+        );
+
+        rl.draw_texture_pro(&self.atlas, source, dest, Vector2::zero(), 0.0, tint);
+    }
+
+    pub fn animations(&self) -> &Animations {
+        &self.animations
+    }
+
+    // the sheet of a player is the texture it's cut from
+    pub fn draw_animation(
+        &self,
+        rl: &mut RaylibDrawHandle,
+        animation: &AnimationPlayer,
+        dest: Rectangle,
+        tint: Color,
+    ) {
+        self.draw_pro(
+            rl,
+            animation.sheet(),
+            self.animations.source(animation),
+            dest,
+            tint,
+        );
     }
 }
@@ -7,12 +7,18 @@ use raylib::{
 
 use crate::{
     SCREEN_HEIGHT, SCREEN_WIDTH,
+    animation::AnimationPlayer,
+    camera::WorldCamera,
     color::CustomColor,
-    dialogue::DialogueHandler,
+    dialogue::{DialogueHandler, Phrase},
+    game::Subsystem,
     hotkey_handler::{HotkeyCategory, HotkeyHandler},
-    map::{Level, TILE_SIZE_PX, TileType},
-    scene::{Scene, SceneHandler},
-    settings::SettingsHandler,
+    input::InputHandler,
+    locale::Locale,
+    map::{Level, TileType},
+    scene::{Cut, Scene, SceneChange, SceneHandler},
+    score::ReplayAction,
+    text_layout::{Align, TextBox},
     texture_handler::TextureHandler,
 };
 
@@ -22,14 +28,27 @@ const STATISTICS_TEXT_Y_OFFSET: f32 = 1.5;
 const STATISTICS_TEXT_SHIFT: f32 = 12.;
 
 const DIALOGUE_BOX_TEXTURE: &str = "dialogue_box";
+const DIALOGUE_BOX_WIDTH: f32 = 224.;
 const DIALOGUE_BOX_HEIGHT: f32 = 48.;
 const DIALOGUE_BOX_TEXTURE_TRANSPARENT_TOP: f32 = 12.;
 const DIALOGUE_TEXT_SIZE: f32 = 8.;
 const DIALOGUE_TEXT_SPACING: f32 = 0.5;
+const DIALOGUE_TEXT_MARGIN: f32 = 8.;
+const DIALOGUE_TEXT_Y_OFFSET: f32 = 28.;
+const DIALOGUE_LINES_PER_PAGE: usize = 2;
+const DIALOGUE_NEXT_MARGIN: f32 = 2.;
+
+const CHOICE_HEIGHT: f32 = 10.;
+const CHOICE_HOVER_COLOR: &str = "f9c22b";
+// PickButton1 to PickButton6
+const CHOICE_HOTKEYS: usize = 6;
 
 const SPEAKER_TEXTURE_WIDTH: f32 = 32.;
 const SPEAKER_TEXTURE_HEIGHT: f32 = 48.;
 
+const BARK_WIDTH: f32 = 96.;
+const BARK_PADDING: f32 = 2.;
+
 const BAR_TEXT_SIZE: f32 = 8.;
 const BAR_TEXT_SPACING: f32 = 1.;
 const BAR_X_OFFSET: f32 = 6.;
@@ -40,26 +59,23 @@ const WOOD_ICON_TEXTURE: &str = "wood_icon";
 const WOOD_ICON_OFFSET_Y: f32 = 12.;
 
 const BUTTON_LABELS: [&str; 3] = ["fire_td", "fire_lr", "fire_stop"];
-const PAUSE_BUTTON_LABELS: [&str; 3] = ["Заново", "Настройки", "Выйти"];
+const PEDESTAL_TEXTURE: &str = "pedestal";
+
+pub const TEXTURES: [&str; 5] = [
+    STATISTICS_BAR_TEXTURE,
+    DIALOGUE_BOX_TEXTURE,
+    SPIRIT_ICON_TEXTURE,
+    WOOD_ICON_TEXTURE,
+    PEDESTAL_TEXTURE,
+];
+// the build buttons show a fire burning
+pub const SHEETS: [&str; 3] = BUTTON_LABELS;
 
 const BUTTONS_X_OFFSET: f32 = BUTTON_TEXTURE_WIDTH / 2.;
 const BUTTONS_Y_OFFSET: f32 = -BUTTON_TEXTURE_HEIGHT / 4.;
 const BUTTON_TEXTURE_WIDTH: f32 = 16.;
 const BUTTON_TEXTURE_HEIGHT: f32 = 16.;
 
-const PAUSE_BUTTON_TEXTURE: &str = "game_buttons";
-const PAUSE_BUTTON_Y_OFFSET: f32 = 3.;
-const PAUSE_BUTTON_TEXTURE_WIDTH: f32 = 64.;
-const PAUSE_BUTTON_TEXTURE_HEIGHT: f32 = 16.;
-
-const PAUSE_TEXT_SIZE: f32 = 12.;
-const PAUSE_TEXT_SPACING: f32 = 1.05;
-
-const PANEL_TEXTURE: &str = "pause_menu";
-const PANEL_WIDTH: f32 = 128.;
-const PANEL_HEIGHT: f32 = 96.;
-const PANEL_TEXT_Y_OFFSET: f32 = 7.;
-
 pub fn get_text_size(
     font: &Font,
     text: &str,
@@ -90,7 +106,8 @@ impl Button {
         rl: &mut RaylibDrawHandle,
         text: &str,
         font: &Font,
-        texture: &Texture2D,
+        texture_handler: &TextureHandler,
+        texture: &str,
         texture_rectangle: &Rectangle,
         text_dimensions: raylib::ffi::Vector2,
         color: &Color,
@@ -99,14 +116,7 @@ impl Button {
         text_offset_pressed: Vector2,
         texture_offset: Vector2,
     ) {
-        rl.draw_texture_pro(
-            texture,
-            texture_rectangle,
-            self.rect,
-            Vector2::zero(),
-            0.0,
-            Color::WHITE,
-        );
+        texture_handler.draw_pro(rl, texture, *texture_rectangle, self.rect, Color::WHITE);
 
         rl.draw_text_pro(
             font,
@@ -132,14 +142,15 @@ impl Button {
 
 pub struct UIHandler {
     build_buttons: Vec<Button>,
-    pause_buttons: Vec<Button>,
-    quitting: bool,
     last_picked_bonfire_index: Option<usize>,
+    // picked with a key, the fire waits on the cursor until Select or a click puts it down
+    placing: bool,
+    label_animations: Vec<AnimationPlayer>,
 }
 
 impl UIHandler {
     #[profiling::function]
-    pub fn new(level_number: usize, scale: f32) -> Self {
+    pub fn new(level_number: usize) -> Self {
         let mut build_buttons = Vec::new();
 
         let len = min(BUTTON_LABELS.len(), level_number);
@@ -147,34 +158,30 @@ impl UIHandler {
             build_buttons.push(Button::default());
         }
 
-        let mut pause_buttons: Vec<Button> = Vec::new();
-        for _ in 0..PAUSE_BUTTON_LABELS.len() {
-            pause_buttons.push(Button::default());
-        }
-
-        Self::set_default(&mut build_buttons, &mut pause_buttons, scale);
+        Self::set_default(&mut build_buttons);
         Self {
             build_buttons,
-            quitting: false,
-            pause_buttons,
             last_picked_bonfire_index: None,
+            placing: false,
+            label_animations: BUTTON_LABELS.map(AnimationPlayer::new).to_vec(),
         }
     }
-    pub fn is_pause(&self) -> bool {
-        return self.quitting;
+    pub fn placing(&self) -> bool {
+        self.placing
     }
-    pub fn rescale_ui(&mut self, new_scale: f32) {
-        Self::set_default(&mut self.build_buttons, &mut self.pause_buttons, new_scale);
+
+    pub fn fires_unlocked(&self) -> usize {
+        self.build_buttons.len()
     }
-    fn set_default(build_buttons: &mut Vec<Button>, pause_buttons: &mut Vec<Button>, scale: f32) {
+
+    fn set_default(build_buttons: &mut [Button]) {
         let len = build_buttons.len();
         let is_len_odd = len % 2 != 0;
 
         for i in 0..len {
             let x = if is_len_odd {
-                (SCREEN_WIDTH as f32 - BUTTON_TEXTURE_WIDTH) / 2. * scale
+                (SCREEN_WIDTH as f32 - BUTTON_TEXTURE_WIDTH) / 2.
                     + (i as isize - (len - 1) as isize / 2) as f32
-                        * scale
                         * (BUTTON_TEXTURE_WIDTH + BUTTONS_X_OFFSET)
             } else {
                 let offset_from_center = if i < len / 2 {
@@ -182,25 +189,16 @@ impl UIHandler {
                 } else {
                     BUTTONS_X_OFFSET
                 };
-                (SCREEN_WIDTH as f32 / 2. + offset_from_center) * scale
-                    + (i as isize - len as isize / 2) as f32 * scale * BUTTON_TEXTURE_WIDTH
+                SCREEN_WIDTH as f32 / 2.
+                    + offset_from_center
+                    + (i as isize - len as isize / 2) as f32 * BUTTON_TEXTURE_WIDTH
             };
 
             build_buttons[i].rect.x = x;
             build_buttons[i].rect.y =
-                (SCREEN_HEIGHT as f32 - BUTTON_TEXTURE_HEIGHT + BUTTONS_Y_OFFSET) * scale;
-            build_buttons[i].rect.width = BUTTON_TEXTURE_WIDTH * scale;
-            build_buttons[i].rect.height = BUTTON_TEXTURE_HEIGHT * scale;
-        }
-
-        for i in 0..pause_buttons.len() {
-            pause_buttons[i].rect.x =
-                (SCREEN_WIDTH as f32 - PAUSE_BUTTON_TEXTURE_WIDTH) / 2. * scale;
-            pause_buttons[i].rect.y = (SCREEN_HEIGHT as f32 / 2.
-                + (PAUSE_BUTTON_Y_OFFSET + BUTTON_TEXTURE_HEIGHT) * (i as f32 - 1.))
-                * scale;
-            pause_buttons[i].rect.width = PAUSE_BUTTON_TEXTURE_WIDTH * scale;
-            pause_buttons[i].rect.height = PAUSE_BUTTON_TEXTURE_HEIGHT * scale;
+                SCREEN_HEIGHT as f32 - BUTTON_TEXTURE_HEIGHT + BUTTONS_Y_OFFSET;
+            build_buttons[i].rect.width = BUTTON_TEXTURE_WIDTH;
+            build_buttons[i].rect.height = BUTTON_TEXTURE_HEIGHT;
         }
     }
 
@@ -208,13 +206,11 @@ impl UIHandler {
     pub fn build(
         &mut self,
         level: &mut Level,
-        rl: &mut RaylibHandle,
+        input_handler: &InputHandler,
         hotkey_h: &mut HotkeyHandler,
         dialogue_h: &mut DialogueHandler,
-        settings_handler: &mut SettingsHandler,
     ) {
-        let dialoging = dialogue_h.current_phrase < dialogue_h.dialogue.len();
-        if dialoging {
+        if dialogue_h.active() {
             return;
         }
 
@@ -223,24 +219,14 @@ impl UIHandler {
             intent = HotkeyCategory::from_bonfire(BUTTON_LABELS[label_index]);
             let collision = unsafe {
                 CheckCollisionPointRec(
-                    (rl.get_mouse_position()
-                        - Vector2::new(
-                            rl.get_screen_width() as f32 / 2.
-                                - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32)
-                                    as f32
-                                    / 2.,
-                            rl.get_screen_height() as f32 / 2.
-                                - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32)
-                                    as f32
-                                    / 2.,
-                        ))
-                    .into(),
+                    input_handler.pointer().into(),
                     self.build_buttons[label_index].rect.into(),
                 )
             };
 
-            if (hotkey_h.check_pressed(rl, intent)
-                || (rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) && collision))
+            let hotkey = hotkey_h.check_pressed(input_handler, intent);
+            if (hotkey
+                || (input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT) && collision))
                 && (self.last_picked_bonfire_index.is_none()
                     || self
                         .last_picked_bonfire_index
@@ -251,6 +237,7 @@ impl UIHandler {
                 }
                 self.last_picked_bonfire_index = Some(label_index);
                 self.build_buttons[label_index].selected = true;
+                self.placing = hotkey;
                 return;
             }
         }
@@ -259,33 +246,32 @@ impl UIHandler {
             return;
         }
 
-        if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
-            || hotkey_h.check_pressed(rl, HotkeyCategory::Cancel)
+        if input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
+            || hotkey_h.check_pressed(input_handler, HotkeyCategory::Cancel)
         {
             self.build_buttons[self.last_picked_bonfire_index.unwrap()].selected = false;
             self.last_picked_bonfire_index = None;
+            self.placing = false;
             return;
         }
 
-        let pos = (rl.get_mouse_position()
-            - Vector2::new(
-                rl.get_screen_width() as f32 / 2.
-                    - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32 / 2.,
-                rl.get_screen_height() as f32 / 2.
-                    - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32 / 2.,
-            ))
-            / (Vector2::one()
-                * (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32);
+        let camera = WorldCamera;
+        let pos = camera.mouse_world(input_handler);
         let (x, y) = (pos.x as usize, pos.y as usize);
 
-        if rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT)
-            || hotkey_h.check_down(
-                rl,
-                HotkeyCategory::from_bonfire(
-                    BUTTON_LABELS[self.last_picked_bonfire_index.unwrap()],
-                ),
-            )
-        {
+        let held = if self.placing {
+            !hotkey_h.check_pressed(input_handler, HotkeyCategory::Select)
+                && !input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+        } else {
+            input_handler.pointer_down(MouseButton::MOUSE_BUTTON_LEFT)
+                || hotkey_h.check_down(
+                    input_handler,
+                    HotkeyCategory::from_bonfire(
+                        BUTTON_LABELS[self.last_picked_bonfire_index.unwrap()],
+                    ),
+                )
+        };
+        if held {
             if x >= level.tiles.len() || y >= level.tiles[0].len() {
                 return;
             }
@@ -298,11 +284,13 @@ impl UIHandler {
             return;
         }
         hotkey_h.clear_last();
+        self.placing = false;
 
         let button_index = self.last_picked_bonfire_index.unwrap();
         if self.build_buttons[button_index].selected && level.get_wood() > 0 {
-            match level.tiles[x][y] {
-                TileType::Air { selected: _ } => {}
+            // let go over the buttons or anywhere else off the level
+            match level.tiles.get(x).and_then(|column| column.get(y)) {
+                Some(TileType::Air { selected: _ }) => {}
                 _ => {
                     self.build_buttons[button_index].selected = false;
                     return;
@@ -329,6 +317,11 @@ impl UIHandler {
 
             level.tiles[x][y] = tile;
             level.remove_wood();
+            level.record(ReplayAction::Build {
+                fire: BUTTON_LABELS[button_index].to_string(),
+                x,
+                y,
+            });
         }
         self.build_buttons[self.last_picked_bonfire_index.unwrap()].selected = false;
         self.last_picked_bonfire_index = None;
@@ -341,88 +334,55 @@ impl UIHandler {
         scene_h: &mut SceneHandler,
         dialogue_h: &mut DialogueHandler,
         rl: &mut RaylibHandle,
-        settings_handler: &mut SettingsHandler,
-    ) -> (bool, bool, bool) {
-        if hotkey_h.check_pressed(rl, HotkeyCategory::Exit) {
-            self.quitting = !self.quitting;
+        input_handler: &InputHandler,
+    ) {
+        for animation in self.label_animations.iter_mut() {
+            animation.update(input_handler.frame_time(rl));
         }
 
-        if hotkey_h.check_pressed(rl, HotkeyCategory::Skip)
-            || (rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
-                && rl.get_mouse_y() as f32
-                    > (SCREEN_HEIGHT as f32 - DIALOGUE_BOX_HEIGHT
-                        + DIALOGUE_BOX_TEXTURE_TRANSPARENT_TOP)
-                        * settings_handler.settings.pixel_scale as f32)
-        {
-            if dialogue_h.dialogue.len() <= 0 {
-                dialogue_h.current_phrase += 1;
-            } else if dialogue_h.current_phrase + 1 < dialogue_h.dialogue.len() {
-                dialogue_h.current_phrase += 1;
-                dialogue_h.dialogue_accumulator = String::new();
-                dialogue_h.dialogue_counter = 0;
-            } else if dialogue_h.current_phrase == dialogue_h.dialogue.len() - 1 {
-                dialogue_h.current_phrase += 1;
-            }
-            return (false, false, false);
+        if hotkey_h.check_pressed(input_handler, HotkeyCategory::Exit) {
+            scene_h.change(SceneChange::Push(Scene::Pause), Box::new(Cut));
+            return;
         }
-
-        if !self.quitting {
-            return (false, false, false);
+        if hotkey_h.check_pressed(input_handler, HotkeyCategory::History) {
+            scene_h.change(SceneChange::Push(Scene::History), Box::new(Cut));
+            return;
         }
 
-        if hotkey_h.check_pressed(rl, HotkeyCategory::PickButton3)
-            || (self.pause_buttons[2].rect.check_collision_point_rec(
-                rl.get_mouse_position()
-                    - Vector2::new(
-                        rl.get_screen_width() as f32 / 2.
-                            - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                        rl.get_screen_height() as f32 / 2.
-                            - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                    ),
-            ) && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT))
-        {
-            scene_h.set(Scene::MainMenu);
-            self.quitting = false;
-            return (true, false, false);
-        };
-
-        if hotkey_h.check_pressed(rl, HotkeyCategory::PickButton1)
-            || (self.pause_buttons[0].rect.check_collision_point_rec(
-                rl.get_mouse_position()
-                    - Vector2::new(
-                        rl.get_screen_width() as f32 / 2.
-                            - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                        rl.get_screen_height() as f32 / 2.
-                            - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                    ),
-            ) && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT))
+        if hotkey_h.check_pressed(input_handler, HotkeyCategory::Skip)
+            || (input_handler.pointer_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+                && input_handler.pointer().y
+                    > (SCREEN_HEIGHT as f32 - DIALOGUE_BOX_HEIGHT
+                        + DIALOGUE_BOX_TEXTURE_TRANSPARENT_TOP))
         {
-            self.quitting = false;
-            return (false, true, false);
-        };
+            if dialogue_h.page + 1 < dialogue_h.page_count {
+                dialogue_h.next_page();
+            } else {
+                dialogue_h.next();
+            }
+            return;
+        }
 
-        if hotkey_h.check_pressed(rl, HotkeyCategory::PickButton2)
-            || (self.pause_buttons[1].rect.check_collision_point_rec(
-                rl.get_mouse_position()
-                    - Vector2::new(
-                        rl.get_screen_width() as f32 / 2.
-                            - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                        rl.get_screen_height() as f32 / 2.
-                            - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                    ),
-            ) && rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT))
-        {
-            self.quitting = false;
-            return (false, false, true);
+        // the choices come up with the last page of the phrase
+        let Some(phrase) = dialogue_h.current() else {
+            return;
         };
-
-        return (false, false, false);
+        if dialogue_h.page + 1 < dialogue_h.page_count {
+            return;
+        }
+        let count = phrase.choices.len();
+        let mouse = input_handler.pointer();
+        for i in 0..count.min(CHOICE_HOTKEYS) {
+            if hotkey_h.check_pressed(
+                input_handler,
+                (HotkeyCategory::PickButton1 as u8 + i as u8).into(),
+            ) || (hotkey_h.check_click(input_handler)
+                && choice_rect(i, count).check_collision_point_rec(mouse))
+            {
+                dialogue_h.choose(i);
+                return;
+            }
+        }
     }
 
     #[profiling::function]
@@ -431,41 +391,33 @@ impl UIHandler {
         texture_handler: &TextureHandler,
         dialogue_h: &mut DialogueHandler,
         level: &mut Level,
+        locale: &Locale,
         font: &Font,
         rl: &mut RaylibDrawHandle,
-        settings_handler: &mut SettingsHandler,
+        input_handler: &InputHandler,
     ) {
-        let dialoging = dialogue_h.current_phrase < dialogue_h.dialogue.len();
+        let dialoging = dialogue_h.active();
 
         for (label_index, button) in self.build_buttons.iter_mut().enumerate() {
             if dialoging {
                 break;
             }
 
-            let target_offset = if button.rect.check_collision_point_rec(
-                rl.get_mouse_position()
-                    - Vector2::new(
-                        rl.get_screen_width() as f32 / 2.
-                            - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                        rl.get_screen_height() as f32 / 2.
-                            - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                    ),
-            ) && !self.quitting
+            let target_offset = if button
+                .rect
+                .check_collision_point_rec(input_handler.pointer())
             {
-                6. * settings_handler.settings.pixel_scale as f32
+                6.
             } else {
-                2. * settings_handler.settings.pixel_scale as f32
+                2.
             };
 
             button.offset = lerp(button.offset, target_offset, 10. * rl.get_frame_time());
 
-            rl.draw_texture_ex(
-                texture_handler.get("pedestal"),
+            texture_handler.draw(
+                rl,
+                PEDESTAL_TEXTURE,
                 Vector2::new(button.rect.x, button.rect.y),
-                0.0,
-                settings_handler.settings.pixel_scale as f32,
                 Color::WHITE,
             );
 
@@ -473,104 +425,48 @@ impl UIHandler {
                 let mut offset_rect = button.rect;
                 offset_rect.y -= button.offset;
 
-                rl.draw_texture_pro(
-                    texture_handler.get(BUTTON_LABELS[label_index]),
-                    Rectangle::new(
-                        ((rl.get_time() * 8.) % 4.).floor() as f32 * BUTTON_TEXTURE_WIDTH,
-                        BUTTON_TEXTURE_HEIGHT,
-                        BUTTON_TEXTURE_WIDTH,
-                        BUTTON_TEXTURE_HEIGHT,
-                    ),
+                texture_handler.draw_animation(
+                    rl,
+                    &self.label_animations[label_index],
                     offset_rect,
-                    Vector2::zero(),
-                    0.0,
                     Color::WHITE,
                 );
             } else {
-                let mouse_pos = (rl.get_mouse_position()
-                    - Vector2::new(
-                        rl.get_screen_width() as f32 / 2.
-                            - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                        rl.get_screen_height() as f32 / 2.
-                            - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                    ))
-                    / (Vector2::one()
-                        * (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32);
+                let camera = WorldCamera;
+                let mouse_pos = camera.mouse_world(input_handler);
 
                 // snap to grid
                 //mouse_pos.x = mouse_pos.x.trunc();
                 //mouse_pos.y = mouse_pos.y.trunc();
 
-                rl.draw_texture_pro(
-                    texture_handler.get_safe(BUTTON_LABELS[label_index]),
-                    Rectangle::new(
-                        ((rl.get_time() * 8.) % 4.).floor() as f32 * BUTTON_TEXTURE_WIDTH,
-                        BUTTON_TEXTURE_HEIGHT,
-                        BUTTON_TEXTURE_WIDTH,
-                        BUTTON_TEXTURE_HEIGHT,
-                    ),
-                    Rectangle::new(
-                        mouse_pos.x
-                            * settings_handler.settings.pixel_scale as f32
-                            * TILE_SIZE_PX as f32,
-                        mouse_pos.y
-                            * settings_handler.settings.pixel_scale as f32
-                            * TILE_SIZE_PX as f32,
-                        (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32,
-                        (TILE_SIZE_PX * settings_handler.settings.pixel_scale as i32) as f32,
-                    ),
-                    Vector2::zero(),
-                    0.0,
+                texture_handler.draw_animation(
+                    rl,
+                    &self.label_animations[label_index],
+                    camera.tile_rect(mouse_pos),
                     Color::WHITE,
                 );
             }
         }
 
-        rl.draw_texture_ex(
-            texture_handler.get(STATISTICS_BAR_TEXTURE),
-            Vector2::one() * settings_handler.settings.pixel_scale as f32,
-            0.0,
-            settings_handler.settings.pixel_scale as f32,
-            Color::WHITE,
-        );
+        texture_handler.draw(rl, STATISTICS_BAR_TEXTURE, Vector2::one(), Color::WHITE);
 
-        let bar_offset = Vector2::new(
-            BAR_X_OFFSET * settings_handler.settings.pixel_scale as f32,
-            BAR_Y_OFFSET * settings_handler.settings.pixel_scale as f32,
-        );
+        let bar_offset = Vector2::new(BAR_X_OFFSET, BAR_Y_OFFSET);
 
-        rl.draw_texture_ex(
-            texture_handler.get(SPIRIT_ICON_TEXTURE),
-            bar_offset,
-            0.0,
-            settings_handler.settings.pixel_scale as f32,
-            Color::WHITE,
-        );
+        texture_handler.draw(rl, SPIRIT_ICON_TEXTURE, bar_offset, Color::WHITE);
 
         rl.draw_text_ex(
             font,
             format!("{}/{}", level.survived, level.survive).as_str(),
-            bar_offset
-                + Vector2::new(
-                    STATISTICS_TEXT_X_OFFSET * settings_handler.settings.pixel_scale as f32,
-                    STATISTICS_TEXT_Y_OFFSET * settings_handler.settings.pixel_scale as f32,
-                ),
-            BAR_TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-            BAR_TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
+            bar_offset + Vector2::new(STATISTICS_TEXT_X_OFFSET, STATISTICS_TEXT_Y_OFFSET),
+            BAR_TEXT_SIZE,
+            BAR_TEXT_SPACING,
             CustomColor::BLACK_TEXT,
         );
 
-        rl.draw_texture_ex(
-            texture_handler.get(WOOD_ICON_TEXTURE),
-            bar_offset
-                + Vector2::new(
-                    0.,
-                    WOOD_ICON_OFFSET_Y * settings_handler.settings.pixel_scale as f32,
-                ),
-            0.0,
-            settings_handler.settings.pixel_scale as f32,
+        texture_handler.draw(
+            rl,
+            WOOD_ICON_TEXTURE,
+            bar_offset + Vector2::new(0., WOOD_ICON_OFFSET_Y),
             Color::WHITE,
         );
 
@@ -579,173 +475,193 @@ impl UIHandler {
             format!("{}", level.get_wood()).as_str(),
             bar_offset
                 + Vector2::new(
-                    STATISTICS_TEXT_X_OFFSET * settings_handler.settings.pixel_scale as f32,
-                    STATISTICS_TEXT_Y_OFFSET * settings_handler.settings.pixel_scale as f32
-                        + STATISTICS_TEXT_SHIFT * settings_handler.settings.pixel_scale as f32,
+                    STATISTICS_TEXT_X_OFFSET,
+                    STATISTICS_TEXT_Y_OFFSET + STATISTICS_TEXT_SHIFT,
                 ),
-            BAR_TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-            BAR_TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
+            BAR_TEXT_SIZE,
+            BAR_TEXT_SPACING,
             CustomColor::BLACK_TEXT,
         );
 
-        if dialoging {
-            let (speaker, line) = &mut dialogue_h.dialogue[dialogue_h.current_phrase];
-
-            rl.draw_texture_ex(
-                texture_handler.get_safe(speaker),
-                Vector2::new(
-                    0.,
-                    (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                        - SPEAKER_TEXTURE_HEIGHT * settings_handler.settings.pixel_scale as f32,
-                ),
-                0.0,
-                settings_handler.settings.pixel_scale as f32,
+        if let Some(phrase) = dialogue_h.current().cloned() {
+            texture_handler.draw(
+                rl,
+                &phrase.portrait,
+                Vector2::new(0., SCREEN_HEIGHT as f32 - SPEAKER_TEXTURE_HEIGHT),
                 Color::WHITE,
             );
 
-            rl.draw_texture_ex(
-                texture_handler.get(DIALOGUE_BOX_TEXTURE),
+            texture_handler.draw(
+                rl,
+                DIALOGUE_BOX_TEXTURE,
                 Vector2::new(
-                    SPEAKER_TEXTURE_WIDTH * settings_handler.settings.pixel_scale as f32,
-                    (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                        - DIALOGUE_BOX_HEIGHT * settings_handler.settings.pixel_scale as f32,
+                    SPEAKER_TEXTURE_WIDTH,
+                    SCREEN_HEIGHT as f32 - DIALOGUE_BOX_HEIGHT,
                 ),
-                0.0,
-                settings_handler.settings.pixel_scale as f32,
                 Color::WHITE,
             );
 
-            if dialogue_h.dialogue_counter < line.chars().count() {
-                dialogue_h.dialogue_counter += 1;
-            }
+            let text_box = TextBox {
+                width: DIALOGUE_BOX_WIDTH - DIALOGUE_TEXT_MARGIN * 2.,
+                lines_per_page: DIALOGUE_LINES_PER_PAGE,
+                align: Align::Left,
+                size: DIALOGUE_TEXT_SIZE,
+                spacing: DIALOGUE_TEXT_SPACING,
+                color: CustomColor::BLACK_TEXT,
+            };
+            let layout = text_box.layout(font, &phrase.text);
+            dialogue_h.page_count = layout.pages();
+            dialogue_h.page = dialogue_h.page.min(layout.pages() - 1);
 
-            let mut temp_line = line.chars().rev();
-            let line_len = line.chars().count();
-            for _ in 0..line_len - dialogue_h.dialogue_counter {
-                temp_line.next();
-            }
+            let page_chars = layout.chars(dialogue_h.page);
+            dialogue_h.page_chars = page_chars;
 
-            rl.draw_text_ex(
+            layout.draw(
+                rl,
                 font,
-                &temp_line.rev().collect::<String>(),
+                dialogue_h.page,
                 Vector2::new(
-                    SPEAKER_TEXTURE_WIDTH * settings_handler.settings.pixel_scale as f32
-                        + 8. * settings_handler.settings.pixel_scale as f32,
-                    (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                        - 28. * settings_handler.settings.pixel_scale as f32,
+                    SPEAKER_TEXTURE_WIDTH + DIALOGUE_TEXT_MARGIN,
+                    SCREEN_HEIGHT as f32 - DIALOGUE_TEXT_Y_OFFSET,
                 ),
-                DIALOGUE_TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                DIALOGUE_TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-                CustomColor::BLACK_TEXT,
+                dialogue_h.typed(),
             );
 
-            if dialogue_h.dialogue_accumulator.chars().count() >= line.chars().count() {
-                rl.draw_text_ex(
+            if dialogue_h.page + 1 == dialogue_h.page_count && !phrase.choices.is_empty() {
+                self.draw_choices(&phrase.choices, font, rl, input_handler);
+            }
+
+            // sticks to the right edge whatever it says
+            if dialogue_h.typed() >= page_chars && phrase.choices.is_empty() {
+                let text_box = TextBox {
+                    width: SCREEN_WIDTH as f32 - DIALOGUE_NEXT_MARGIN,
+                    lines_per_page: 0,
+                    align: Align::Right,
+                    size: 12.,
+                    spacing: 0.,
+                    color: Color::RAYWHITE.alpha((rl.get_time() * 2.).sin().abs() as f32),
+                };
+                text_box.layout(font, locale.get("dialogue.next")).draw(
+                    rl,
                     font,
-                    "Далее...",
-                    Vector2::new(
-                        (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32
-                            - 32. * settings_handler.settings.pixel_scale as f32,
-                        (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                            - 12. * settings_handler.settings.pixel_scale as f32,
-                    ),
-                    12. * settings_handler.settings.pixel_scale as f32,
-                    0.,
-                    Color::RAYWHITE.alpha((rl.get_time() * 2.).sin().abs() as f32),
-                )
+                    0,
+                    Vector2::new(0., SCREEN_HEIGHT as f32 - 12.),
+                    usize::MAX,
+                );
             }
         }
 
-        if !self.quitting {
-            return;
+        if let Some(phrase) = dialogue_h.bark() {
+            self.draw_bark(phrase, texture_handler, font, rl);
         }
+    }
+}
 
-        let panel_position = Vector2::new(
-            (SCREEN_WIDTH as f32 - PANEL_WIDTH) * settings_handler.settings.pixel_scale as f32 / 2.,
-            (SCREEN_HEIGHT as f32 - PANEL_HEIGHT) * settings_handler.settings.pixel_scale as f32
-                / 2.,
-        );
-
-        rl.draw_texture_ex(
-            texture_handler.get(PANEL_TEXTURE),
-            panel_position,
-            0.0,
-            settings_handler.settings.pixel_scale as f32,
-            Color::WHITE,
+impl UIHandler {
+    // next to the speaker's head, small enough to play around
+    fn draw_bark(
+        &self,
+        phrase: &Phrase,
+        texture_handler: &TextureHandler,
+        font: &Font,
+        rl: &mut RaylibDrawHandle,
+    ) {
+        let top = SCREEN_HEIGHT as f32 - SPEAKER_TEXTURE_HEIGHT;
+        texture_handler.draw(rl, &phrase.portrait, Vector2::new(0., top), Color::WHITE);
+
+        let text_box = TextBox {
+            width: BARK_WIDTH,
+            lines_per_page: 0,
+            align: Align::Left,
+            size: DIALOGUE_TEXT_SIZE,
+            spacing: DIALOGUE_TEXT_SPACING,
+            color: Color::RAYWHITE,
+        };
+        let layout = text_box.layout(font, &phrase.text);
+
+        rl.draw_rectangle_rec(
+            Rectangle::new(
+                SPEAKER_TEXTURE_WIDTH,
+                top,
+                layout.width(0) + BARK_PADDING * 2.,
+                layout.height(0) + BARK_PADDING * 2.,
+            ),
+            Color::BLACK.alpha(0.6),
         );
-
-        let text_size = get_text_size(
+        layout.draw(
+            rl,
             font,
-            "Меню",
-            PAUSE_TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-            PAUSE_TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
+            0,
+            Vector2::new(SPEAKER_TEXTURE_WIDTH + BARK_PADDING, top + BARK_PADDING),
+            usize::MAX,
         );
+    }
 
-        rl.draw_text_ex(
-            font,
-            "Меню",
-            panel_position
-                + Vector2::new(
-                    settings_handler.settings.pixel_scale as f32 * PAUSE_BUTTON_TEXTURE_WIDTH
-                        - text_size.x / 2.,
-                    settings_handler.settings.pixel_scale as f32 * PANEL_TEXT_Y_OFFSET
-                        - text_size.y / 2.,
-                ),
-            settings_handler.settings.pixel_scale as f32 * 12.,
-            PAUSE_TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-            CustomColor::BLACK_TEXT,
-        );
-
-        for (index, button_rect) in self.pause_buttons.iter().enumerate() {
-            let mouse_over = button_rect.rect.check_collision_point_rec(
-                rl.get_mouse_position()
-                    - Vector2::new(
-                        rl.get_screen_width() as f32 / 2.
-                            - (SCREEN_WIDTH * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                        rl.get_screen_height() as f32 / 2.
-                            - (SCREEN_HEIGHT * settings_handler.settings.pixel_scale as i32) as f32
-                                / 2.,
-                    ),
-            );
-
-            let (texture_offset, text_offset) =
-                if mouse_over && rl.is_mouse_button_up(MouseButton::MOUSE_BUTTON_LEFT) {
-                    (PAUSE_BUTTON_TEXTURE_HEIGHT, -2.)
+    fn draw_choices(
+        &self,
+        choices: &[(String, String)],
+        font: &Font,
+        rl: &mut RaylibDrawHandle,
+        input_handler: &InputHandler,
+    ) {
+        let mouse = input_handler.pointer();
+
+        for (i, (text, _)) in choices.iter().enumerate() {
+            let rect = choice_rect(i, choices.len());
+            let hovered = rect.check_collision_point_rec(mouse);
+            rl.draw_rectangle_rec(rect, Color::BLACK.alpha(if hovered { 0.8 } else { 0.6 }));
+
+            let text_box = TextBox {
+                width: rect.width - 4.,
+                lines_per_page: 1,
+                align: Align::Left,
+                size: DIALOGUE_TEXT_SIZE,
+                spacing: DIALOGUE_TEXT_SPACING,
+                color: if hovered {
+                    Color::from_hex(CHOICE_HOVER_COLOR).unwrap()
                 } else {
-                    (0., 0.)
-                };
-
-            let source = Rectangle::new(
-                0.,
-                texture_offset,
-                PAUSE_BUTTON_TEXTURE_WIDTH,
-                PAUSE_BUTTON_TEXTURE_HEIGHT,
-            );
-
-            let text_size_button = get_text_size(
-                font,
-                PAUSE_BUTTON_LABELS[index],
-                PAUSE_TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                PAUSE_TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-            );
-
-            button_rect.draw_with_text_middle(
+                    Color::RAYWHITE
+                },
+            };
+            text_box.layout(font, &format!("{}. {text}", i + 1)).draw(
                 rl,
-                PAUSE_BUTTON_LABELS[index],
                 font,
-                texture_handler.get_safe(PAUSE_BUTTON_TEXTURE),
-                &source,
-                text_size_button,
-                &CustomColor::BLACK_TEXT,
-                PAUSE_TEXT_SIZE * settings_handler.settings.pixel_scale as f32,
-                PAUSE_TEXT_SPACING * settings_handler.settings.pixel_scale as f32,
-                Vector2::new(
-                    0.,
-                    text_offset * settings_handler.settings.pixel_scale as f32,
-                ),
-                Vector2::zero(),
+                0,
+                Vector2::new(rect.x + 2., rect.y + 1.),
+                usize::MAX,
             );
         }
     }
 }
+
+// stacked right above the dialogue box, the last one closest to it
+// where the current phrase's choices are, none until its last page is up
+pub fn choice_rects(dialogue_h: &DialogueHandler) -> Vec<Rectangle> {
+    let Some(phrase) = dialogue_h.current() else {
+        return Vec::new();
+    };
+    if dialogue_h.page + 1 < dialogue_h.page_count {
+        return Vec::new();
+    }
+    let count = phrase.choices.len();
+    (0..count.min(CHOICE_HOTKEYS))
+        .map(|i| choice_rect(i, count))
+        .collect()
+}
+
+fn choice_rect(index: usize, count: usize) -> Rectangle {
+    let bottom = SCREEN_HEIGHT as f32 - DIALOGUE_BOX_HEIGHT + DIALOGUE_BOX_TEXTURE_TRANSPARENT_TOP;
+    Rectangle::new(
+        SPEAKER_TEXTURE_WIDTH + DIALOGUE_TEXT_MARGIN,
+        bottom - (count - index) as f32 * (CHOICE_HEIGHT + 1.),
+        DIALOGUE_BOX_WIDTH - DIALOGUE_TEXT_MARGIN * 2.,
+        CHOICE_HEIGHT,
+    )
+}
+
+impl Subsystem for UIHandler {
+    // one more fire to build with every level
+    fn level_started(&mut self, level_number: u8) {
+        *self = UIHandler::new(level_number as usize);
+    }
+}
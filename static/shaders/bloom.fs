@@ -13,14 +13,15 @@ out vec4 finalColor;
 
 // NOTE: Add your custom variables here
 
-const vec2 size = vec2(16*16*4, 16*9*4);   // Framebuffer size
+uniform vec2 resolution;             // Framebuffer size, set every frame
+uniform float quality = 3.0;         // Defines size factor: Lower = smaller glow, better quality
+
 const float samples = 12.0;          // Pixels per axis; higher = bigger glow, worse performance
-const float quality = 10.0;          // Defines size factor: Lower = smaller glow, better quality
 
 void main()
 {
     vec4 sum = vec4(0);
-    vec2 sizeFactor = vec2(1)/size*quality;
+    vec2 sizeFactor = vec2(1)/resolution*quality;
 
     // Texel color fetching from texture sampler
     vec4 source = texture(texture0, fragTexCoord);
@@ -0,0 +1,44 @@
+#version 330
+
+// Input vertex attributes (from vertex shader)
+in vec2 fragTexCoord;
+in vec4 fragColor;
+
+// Input uniform values
+uniform sampler2D texture0;
+uniform vec4 colDiffuse;
+
+// Output fragment color
+out vec4 finalColor;
+
+// NOTE: Add your custom variables here
+
+uniform vec2 resolution;             // Framebuffer size, set every frame
+uniform float time;                  // Seconds since start, set every frame
+uniform float curvature = 6.0;       // Lower = more bent screen
+uniform float scanlines = 0.25;      // How dark every other line gets
+uniform float flicker = 0.02;
+
+void main()
+{
+    // Bend the picture like an old tube
+    vec2 uv = fragTexCoord*2.0 - 1.0;
+    vec2 offset = uv.yx/curvature;
+    uv = uv + uv*offset*offset;
+    uv = uv*0.5 + 0.5;
+
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0)
+    {
+        finalColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec4 source = texture(texture0, uv);
+
+    // One scanline per game pixel
+    float line = mod(floor(uv.y*resolution.y), 2.0);
+    vec3 color = source.rgb*(1.0 - scanlines*line);
+    color *= 1.0 - flicker*sin(time*60.0);
+
+    finalColor = vec4(color, source.a)*colDiffuse;
+}
@@ -13,13 +13,14 @@ out vec4 finalColor;
 
 // NOTE: Add your custom variables here
 
-float gamma = 0.6;
-float numColors = 8.0;
+uniform float gamma = 0.6;
+uniform float numColors = 8.0;
 
 void main()
 {
     // Texel color fetching from texture sampler
-    vec3 texelColor = texture(texture0, fragTexCoord.xy).rgb;
+    vec4 texel = texture(texture0, fragTexCoord.xy);
+    vec3 texelColor = texel.rgb;
 
     texelColor = pow(texelColor, vec3(gamma, gamma, gamma));
     texelColor = texelColor*numColors;
@@ -27,5 +28,5 @@ void main()
     texelColor = texelColor/numColors;
     texelColor = pow(texelColor, vec3(1.0/gamma));
 
-    finalColor = vec4(texelColor, 1.0);
+    finalColor = vec4(texelColor, texel.a)*colDiffuse;
 }
\ No newline at end of file
@@ -0,0 +1,32 @@
+#version 330
+
+// Input vertex attributes (from vertex shader)
+in vec2 fragTexCoord;
+in vec4 fragColor;
+
+// Input uniform values
+uniform sampler2D texture0;
+uniform vec4 colDiffuse;
+
+// Output fragment color
+out vec4 finalColor;
+
+// NOTE: Add your custom variables here
+
+uniform vec2 resolution;             // Framebuffer size, set every frame
+uniform float strength = 0.5;        // How dark the corners get
+uniform float radius = 0.75;         // Where the darkening starts
+uniform vec3 tint = vec3(0.0);       // Color the corners fade to
+
+void main()
+{
+    vec4 source = texture(texture0, fragTexCoord);
+
+    // Keep the falloff round on a wide screen
+    vec2 uv = (fragTexCoord - 0.5)*vec2(resolution.x/resolution.y, 1.0);
+    float vignette = 1.0 - smoothstep(radius - 0.45, radius, length(uv));
+
+    vec3 color = mix(tint, source.rgb, mix(1.0, vignette, strength));
+
+    finalColor = vec4(color, source.a)*colDiffuse;
+}
@@ -0,0 +1,21 @@
+use std::process::Command;
+
+// the game opens a real window for these, so they stay out of a plain cargo test
+// and a headless machine or CI doesn't fail on them, on a desktop run them with
+// cargo test --test scripts -- --ignored
+
+// the game plays the script and closes, a step that never sees the scene it waits for panics
+fn play(script: &str) {
+    let status = Command::new(env!("CARGO_BIN_EXE_veles_night"))
+        .args(["--script", script])
+        .status()
+        .expect("COULDN'T START THE GAME");
+    assert!(status.success(), "{script} DIDN'T GET TO THE END");
+}
+
+// start from the menu, chop the tree in the way, wait for the win and sign the leaderboard
+#[test]
+#[ignore = "opens a window, run with --ignored"]
+fn first_level() {
+    play("tests/scripts/first_level.json");
+}